@@ -0,0 +1,117 @@
+/* C API for umbral-pre. See src/lib.rs for the documentation of each function. */
+
+#ifndef UMBRAL_PRE_H
+#define UMBRAL_PRE_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define UMBRAL_OK 0
+#define UMBRAL_ERROR_NULL_POINTER -1
+#define UMBRAL_ERROR_INVALID_BYTES -2
+#define UMBRAL_ERROR_INVALID_ARGUMENT -3
+#define UMBRAL_ERROR_ENCRYPTION_FAILED -4
+#define UMBRAL_ERROR_DECRYPTION_FAILED -5
+#define UMBRAL_ERROR_VERIFICATION_FAILED -6
+#define UMBRAL_ERROR_PANIC -99
+
+typedef struct UmbralSecretKey UmbralSecretKey;
+typedef struct UmbralPublicKey UmbralPublicKey;
+typedef struct UmbralParameters UmbralParameters;
+typedef struct UmbralCapsule UmbralCapsule;
+typedef struct UmbralKeyFrag UmbralKeyFrag;
+typedef struct UmbralCapsuleFrag UmbralCapsuleFrag;
+
+typedef struct {
+    uint8_t *data;
+    size_t len;
+} UmbralBuffer;
+
+void umbral_buffer_free(UmbralBuffer buffer);
+
+#define UMBRAL_OPAQUE_OBJECT(type, prefix) \
+    void prefix##_free(type *obj); \
+    int prefix##_to_bytes(const type *obj, UmbralBuffer *out); \
+    int prefix##_from_bytes(const uint8_t *data, size_t len, type **out);
+
+UMBRAL_OPAQUE_OBJECT(UmbralSecretKey, umbral_secret_key)
+UMBRAL_OPAQUE_OBJECT(UmbralPublicKey, umbral_public_key)
+UMBRAL_OPAQUE_OBJECT(UmbralParameters, umbral_parameters)
+UMBRAL_OPAQUE_OBJECT(UmbralCapsule, umbral_capsule)
+UMBRAL_OPAQUE_OBJECT(UmbralKeyFrag, umbral_kfrag)
+UMBRAL_OPAQUE_OBJECT(UmbralCapsuleFrag, umbral_cfrag)
+
+#undef UMBRAL_OPAQUE_OBJECT
+
+int umbral_secret_key_random(UmbralSecretKey **out);
+
+int umbral_public_key_from_secret_key(const UmbralSecretKey *sk, UmbralPublicKey **out);
+
+int umbral_parameters_new(UmbralParameters **out);
+
+int umbral_encrypt(
+    const UmbralParameters *params,
+    const UmbralPublicKey *pk,
+    const uint8_t *plaintext,
+    size_t plaintext_len,
+    UmbralCapsule **out_capsule,
+    UmbralBuffer *out_ciphertext);
+
+int umbral_decrypt_original(
+    const UmbralSecretKey *sk,
+    const UmbralCapsule *capsule,
+    const uint8_t *ciphertext,
+    size_t ciphertext_len,
+    UmbralBuffer *out_plaintext);
+
+int umbral_generate_kfrags(
+    const UmbralParameters *params,
+    const UmbralSecretKey *delegating_sk,
+    const UmbralPublicKey *receiving_pk,
+    const UmbralSecretKey *signing_sk,
+    size_t threshold,
+    size_t num_kfrags,
+    bool sign_delegating_key,
+    bool sign_receiving_key,
+    UmbralKeyFrag **out_kfrags);
+
+int umbral_kfrag_verify(
+    const UmbralKeyFrag *kfrag,
+    const UmbralPublicKey *signing_pk,
+    const UmbralPublicKey *delegating_pk,
+    const UmbralPublicKey *receiving_pk);
+
+int umbral_reencrypt(
+    const UmbralCapsule *capsule,
+    const UmbralKeyFrag *kfrag,
+    const uint8_t *metadata,
+    size_t metadata_len,
+    UmbralCapsuleFrag **out_cfrag);
+
+int umbral_cfrag_verify(
+    const UmbralCapsuleFrag *cfrag,
+    const UmbralCapsule *capsule,
+    const UmbralPublicKey *delegating_pk,
+    const UmbralPublicKey *receiving_pk,
+    const UmbralPublicKey *signing_pk);
+
+int umbral_decrypt_reencrypted(
+    const UmbralSecretKey *decrypting_sk,
+    const UmbralPublicKey *delegating_pk,
+    const UmbralCapsule *capsule,
+    const UmbralCapsuleFrag *const *cfrags,
+    size_t num_cfrags,
+    const uint8_t *ciphertext,
+    size_t ciphertext_len,
+    UmbralBuffer *out_plaintext);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* UMBRAL_PRE_H */
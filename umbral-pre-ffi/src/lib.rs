@@ -0,0 +1,651 @@
+//! C API for `umbral-pre`.
+//!
+//! All the objects are passed around as opaque pointers created by this library,
+//! and must be released with the corresponding `umbral_*_free()` function.
+//! Byte strings returned by the library are [`UmbralBuffer`] objects
+//! which must be released with [`umbral_buffer_free()`].
+//!
+//! Every function returns an integer status code (one of the `UMBRAL_*` constants),
+//! with the results written to the output pointers.
+//! Output pointers are only written to on success.
+//! Panics are caught at the boundary and reported as [`UMBRAL_ERROR_PANIC`].
+//!
+//! See `include/umbral_pre.h` for the corresponding C declarations.
+
+use std::os::raw::c_int;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::ptr;
+use std::slice;
+
+use umbral_pre::{
+    Capsule, CapsuleFrag, KeyFrag, Parameters, PublicKey, SecretKey, SerializableToArray,
+};
+
+/// The operation succeeded.
+pub const UMBRAL_OK: c_int = 0;
+/// A required pointer argument was null.
+pub const UMBRAL_ERROR_NULL_POINTER: c_int = -1;
+/// The input bytes could not be deserialized into the requested object.
+pub const UMBRAL_ERROR_INVALID_BYTES: c_int = -2;
+/// The threshold or the number of fragments is invalid.
+pub const UMBRAL_ERROR_INVALID_ARGUMENT: c_int = -3;
+/// The encryption failed (e.g. the plaintext is too large).
+pub const UMBRAL_ERROR_ENCRYPTION_FAILED: c_int = -4;
+/// The decryption failed.
+pub const UMBRAL_ERROR_DECRYPTION_FAILED: c_int = -5;
+/// The verification of a fragment failed.
+pub const UMBRAL_ERROR_VERIFICATION_FAILED: c_int = -6;
+/// A panic occurred inside the library.
+pub const UMBRAL_ERROR_PANIC: c_int = -99;
+
+/// A byte string owned by the library.
+#[repr(C)]
+pub struct UmbralBuffer {
+    /// Pointer to the data.
+    pub data: *mut u8,
+    /// Length of the data in bytes.
+    pub len: usize,
+}
+
+impl UmbralBuffer {
+    fn from_boxed(bytes: Box<[u8]>) -> Self {
+        let len = bytes.len();
+        let data = Box::into_raw(bytes) as *mut u8;
+        Self { data, len }
+    }
+}
+
+fn guard(f: impl FnOnce() -> Result<(), c_int>) -> c_int {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(Ok(())) => UMBRAL_OK,
+        Ok(Err(code)) => code,
+        Err(_) => UMBRAL_ERROR_PANIC,
+    }
+}
+
+unsafe fn as_ref<'a, T>(ptr: *const T) -> Result<&'a T, c_int> {
+    ptr.as_ref().ok_or(UMBRAL_ERROR_NULL_POINTER)
+}
+
+unsafe fn as_slice<'a>(data: *const u8, len: usize) -> Result<&'a [u8], c_int> {
+    if len == 0 {
+        return Ok(&[]);
+    }
+    if data.is_null() {
+        return Err(UMBRAL_ERROR_NULL_POINTER);
+    }
+    Ok(slice::from_raw_parts(data, len))
+}
+
+unsafe fn write_out<T>(out: *mut *mut T, obj: T) -> Result<(), c_int> {
+    if out.is_null() {
+        return Err(UMBRAL_ERROR_NULL_POINTER);
+    }
+    *out = Box::into_raw(Box::new(obj));
+    Ok(())
+}
+
+unsafe fn write_buffer(out: *mut UmbralBuffer, bytes: Box<[u8]>) -> Result<(), c_int> {
+    if out.is_null() {
+        return Err(UMBRAL_ERROR_NULL_POINTER);
+    }
+    *out = UmbralBuffer::from_boxed(bytes);
+    Ok(())
+}
+
+/// Releases a buffer returned by the library.
+///
+/// # Safety
+///
+/// `buffer` must have been returned by this library and not released before.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_buffer_free(buffer: UmbralBuffer) {
+    if !buffer.data.is_null() {
+        let slice = ptr::slice_from_raw_parts_mut(buffer.data, buffer.len);
+        drop(Box::from_raw(slice));
+    }
+}
+
+macro_rules! opaque_object {
+    ($type:ty, $free:ident, $to_bytes:ident, $from_bytes:ident) => {
+        /// Releases the object.
+        ///
+        /// # Safety
+        ///
+        /// `obj` must be null, or an object returned by this library and not released before.
+        #[no_mangle]
+        pub unsafe extern "C" fn $free(obj: *mut $type) {
+            if !obj.is_null() {
+                drop(Box::from_raw(obj));
+            }
+        }
+
+        /// Serializes the object into a newly allocated buffer.
+        ///
+        /// # Safety
+        ///
+        /// `obj` must be a valid object returned by this library,
+        /// and `out` must point to writable memory.
+        #[no_mangle]
+        pub unsafe extern "C" fn $to_bytes(obj: *const $type, out: *mut UmbralBuffer) -> c_int {
+            guard(|| {
+                let obj = as_ref(obj)?;
+                write_buffer(out, obj.to_array().as_slice().into())
+            })
+        }
+
+        /// Deserializes the object from `len` bytes at `data`.
+        ///
+        /// # Safety
+        ///
+        /// `data` must point to `len` readable bytes, and `out` must point to writable memory.
+        #[no_mangle]
+        pub unsafe extern "C" fn $from_bytes(
+            data: *const u8,
+            len: usize,
+            out: *mut *mut $type,
+        ) -> c_int {
+            guard(|| {
+                let bytes = as_slice(data, len)?;
+                let obj = <$type>::from_bytes(bytes).ok_or(UMBRAL_ERROR_INVALID_BYTES)?;
+                write_out(out, obj)
+            })
+        }
+    };
+}
+
+opaque_object!(
+    SecretKey,
+    umbral_secret_key_free,
+    umbral_secret_key_to_bytes,
+    umbral_secret_key_from_bytes
+);
+opaque_object!(
+    PublicKey,
+    umbral_public_key_free,
+    umbral_public_key_to_bytes,
+    umbral_public_key_from_bytes
+);
+opaque_object!(
+    Parameters,
+    umbral_parameters_free,
+    umbral_parameters_to_bytes,
+    umbral_parameters_from_bytes
+);
+opaque_object!(
+    Capsule,
+    umbral_capsule_free,
+    umbral_capsule_to_bytes,
+    umbral_capsule_from_bytes
+);
+opaque_object!(
+    KeyFrag,
+    umbral_kfrag_free,
+    umbral_kfrag_to_bytes,
+    umbral_kfrag_from_bytes
+);
+opaque_object!(
+    CapsuleFrag,
+    umbral_cfrag_free,
+    umbral_cfrag_to_bytes,
+    umbral_cfrag_from_bytes
+);
+
+/// Generates a new random secret key.
+///
+/// # Safety
+///
+/// `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_secret_key_random(out: *mut *mut SecretKey) -> c_int {
+    guard(|| write_out(out, SecretKey::random()))
+}
+
+/// Returns the public key corresponding to the given secret key.
+///
+/// # Safety
+///
+/// `sk` must be a valid object returned by this library, and `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_public_key_from_secret_key(
+    sk: *const SecretKey,
+    out: *mut *mut PublicKey,
+) -> c_int {
+    guard(|| {
+        let sk = as_ref(sk)?;
+        write_out(out, PublicKey::from_secret_key(sk))
+    })
+}
+
+/// Creates the default scheme parameters.
+///
+/// # Safety
+///
+/// `out` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_parameters_new(out: *mut *mut Parameters) -> c_int {
+    guard(|| write_out(out, Parameters::new()))
+}
+
+/// Encrypts `plaintext_len` bytes at `plaintext` for the given public key.
+///
+/// # Safety
+///
+/// The object pointers must be valid objects returned by this library,
+/// `plaintext` must point to `plaintext_len` readable bytes,
+/// and the output pointers must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_encrypt(
+    params: *const Parameters,
+    pk: *const PublicKey,
+    plaintext: *const u8,
+    plaintext_len: usize,
+    out_capsule: *mut *mut Capsule,
+    out_ciphertext: *mut UmbralBuffer,
+) -> c_int {
+    guard(|| {
+        let params = as_ref(params)?;
+        let pk = as_ref(pk)?;
+        let plaintext = as_slice(plaintext, plaintext_len)?;
+        if out_capsule.is_null() || out_ciphertext.is_null() {
+            return Err(UMBRAL_ERROR_NULL_POINTER);
+        }
+        let (capsule, ciphertext) = umbral_pre::encrypt(params, pk, plaintext)
+            .map_err(|_| UMBRAL_ERROR_ENCRYPTION_FAILED)?;
+        write_out(out_capsule, capsule)?;
+        write_buffer(out_ciphertext, ciphertext)
+    })
+}
+
+/// Decrypts a ciphertext with the secret key it was encrypted for.
+///
+/// # Safety
+///
+/// The object pointers must be valid objects returned by this library,
+/// `ciphertext` must point to `ciphertext_len` readable bytes,
+/// and `out_plaintext` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_decrypt_original(
+    sk: *const SecretKey,
+    capsule: *const Capsule,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    out_plaintext: *mut UmbralBuffer,
+) -> c_int {
+    guard(|| {
+        let sk = as_ref(sk)?;
+        let capsule = as_ref(capsule)?;
+        let ciphertext = as_slice(ciphertext, ciphertext_len)?;
+        let plaintext = umbral_pre::decrypt_original(sk, capsule, ciphertext)
+            .ok_or(UMBRAL_ERROR_DECRYPTION_FAILED)?;
+        write_buffer(out_plaintext, plaintext)
+    })
+}
+
+/// Generates `num_kfrags` key fragments, writing them to the array `out_kfrags`
+/// (which must have room for `num_kfrags` pointers).
+///
+/// # Safety
+///
+/// The object pointers must be valid objects returned by this library,
+/// and `out_kfrags` must point to `num_kfrags` writable pointers.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn umbral_generate_kfrags(
+    params: *const Parameters,
+    delegating_sk: *const SecretKey,
+    receiving_pk: *const PublicKey,
+    signing_sk: *const SecretKey,
+    threshold: usize,
+    num_kfrags: usize,
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+    out_kfrags: *mut *mut KeyFrag,
+) -> c_int {
+    guard(|| {
+        let params = as_ref(params)?;
+        let delegating_sk = as_ref(delegating_sk)?;
+        let receiving_pk = as_ref(receiving_pk)?;
+        let signing_sk = as_ref(signing_sk)?;
+        if out_kfrags.is_null() {
+            return Err(UMBRAL_ERROR_NULL_POINTER);
+        }
+        if threshold == 0 || threshold > num_kfrags {
+            return Err(UMBRAL_ERROR_INVALID_ARGUMENT);
+        }
+        let kfrags = umbral_pre::generate_kfrags(
+            params,
+            delegating_sk,
+            receiving_pk,
+            signing_sk,
+            threshold,
+            num_kfrags,
+            sign_delegating_key,
+            sign_receiving_key,
+        );
+        for (i, kfrag) in kfrags.iter().enumerate() {
+            *out_kfrags.add(i) = Box::into_raw(Box::new(kfrag.clone()));
+        }
+        Ok(())
+    })
+}
+
+/// Verifies the integrity of a key fragment.
+/// `delegating_pk` and `receiving_pk` can be null if they were not signed.
+///
+/// # Safety
+///
+/// The non-null object pointers must be valid objects returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_kfrag_verify(
+    kfrag: *const KeyFrag,
+    signing_pk: *const PublicKey,
+    delegating_pk: *const PublicKey,
+    receiving_pk: *const PublicKey,
+) -> c_int {
+    guard(|| {
+        let kfrag = as_ref(kfrag)?;
+        let signing_pk = as_ref(signing_pk)?;
+        match kfrag.verify(signing_pk, delegating_pk.as_ref(), receiving_pk.as_ref()) {
+            true => Ok(()),
+            false => Err(UMBRAL_ERROR_VERIFICATION_FAILED),
+        }
+    })
+}
+
+/// Reencrypts a capsule with a key fragment.
+/// `metadata` can be null if `metadata_len` is 0, in which case no metadata is attached.
+///
+/// # Safety
+///
+/// The object pointers must be valid objects returned by this library,
+/// `metadata` must point to `metadata_len` readable bytes,
+/// and `out_cfrag` must point to writable memory.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_reencrypt(
+    capsule: *const Capsule,
+    kfrag: *const KeyFrag,
+    metadata: *const u8,
+    metadata_len: usize,
+    out_cfrag: *mut *mut CapsuleFrag,
+) -> c_int {
+    guard(|| {
+        let capsule = as_ref(capsule)?;
+        let kfrag = as_ref(kfrag)?;
+        let metadata = if metadata.is_null() {
+            None
+        } else {
+            Some(as_slice(metadata, metadata_len)?)
+        };
+        write_out(out_cfrag, umbral_pre::reencrypt(capsule, kfrag, metadata))
+    })
+}
+
+/// Verifies the integrity of a capsule fragment.
+///
+/// # Safety
+///
+/// The object pointers must be valid objects returned by this library.
+#[no_mangle]
+pub unsafe extern "C" fn umbral_cfrag_verify(
+    cfrag: *const CapsuleFrag,
+    capsule: *const Capsule,
+    delegating_pk: *const PublicKey,
+    receiving_pk: *const PublicKey,
+    signing_pk: *const PublicKey,
+) -> c_int {
+    guard(|| {
+        let cfrag = as_ref(cfrag)?;
+        let capsule = as_ref(capsule)?;
+        let delegating_pk = as_ref(delegating_pk)?;
+        let receiving_pk = as_ref(receiving_pk)?;
+        let signing_pk = as_ref(signing_pk)?;
+        match cfrag.verify(capsule, delegating_pk, receiving_pk, signing_pk) {
+            true => Ok(()),
+            false => Err(UMBRAL_ERROR_VERIFICATION_FAILED),
+        }
+    })
+}
+
+/// Decrypts a ciphertext using the capsule fragments from the array `cfrags`
+/// of length `num_cfrags`.
+///
+/// # Safety
+///
+/// The object pointers must be valid objects returned by this library,
+/// `cfrags` must point to `num_cfrags` such pointers,
+/// `ciphertext` must point to `ciphertext_len` readable bytes,
+/// and `out_plaintext` must point to writable memory.
+#[allow(clippy::too_many_arguments)]
+#[no_mangle]
+pub unsafe extern "C" fn umbral_decrypt_reencrypted(
+    decrypting_sk: *const SecretKey,
+    delegating_pk: *const PublicKey,
+    capsule: *const Capsule,
+    cfrags: *const *const CapsuleFrag,
+    num_cfrags: usize,
+    ciphertext: *const u8,
+    ciphertext_len: usize,
+    out_plaintext: *mut UmbralBuffer,
+) -> c_int {
+    guard(|| {
+        let decrypting_sk = as_ref(decrypting_sk)?;
+        let delegating_pk = as_ref(delegating_pk)?;
+        let capsule = as_ref(capsule)?;
+        let ciphertext = as_slice(ciphertext, ciphertext_len)?;
+        if cfrags.is_null() {
+            return Err(UMBRAL_ERROR_NULL_POINTER);
+        }
+        let cfrags = slice::from_raw_parts(cfrags, num_cfrags)
+            .iter()
+            .map(|cfrag| as_ref(*cfrag).cloned())
+            .collect::<Result<Vec<CapsuleFrag>, c_int>>()?;
+        let plaintext = umbral_pre::decrypt_reencrypted(
+            decrypting_sk,
+            delegating_pk,
+            capsule,
+            &cfrags,
+            ciphertext,
+        )
+        .ok_or(UMBRAL_ERROR_DECRYPTION_FAILED)?;
+        write_buffer(out_plaintext, plaintext)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::ptr;
+    use std::slice;
+
+    use super::*;
+
+    fn new_buffer() -> UmbralBuffer {
+        UmbralBuffer {
+            data: ptr::null_mut(),
+            len: 0,
+        }
+    }
+
+    unsafe fn buffer_contents(buffer: &UmbralBuffer) -> Vec<u8> {
+        slice::from_raw_parts(buffer.data, buffer.len).to_vec()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        unsafe {
+            let mut params = ptr::null_mut();
+            assert_eq!(umbral_parameters_new(&mut params), UMBRAL_OK);
+
+            let mut delegating_sk = ptr::null_mut();
+            let mut delegating_pk = ptr::null_mut();
+            let mut signing_sk = ptr::null_mut();
+            let mut signing_pk = ptr::null_mut();
+            let mut receiving_sk = ptr::null_mut();
+            let mut receiving_pk = ptr::null_mut();
+            assert_eq!(umbral_secret_key_random(&mut delegating_sk), UMBRAL_OK);
+            assert_eq!(
+                umbral_public_key_from_secret_key(delegating_sk, &mut delegating_pk),
+                UMBRAL_OK
+            );
+            assert_eq!(umbral_secret_key_random(&mut signing_sk), UMBRAL_OK);
+            assert_eq!(
+                umbral_public_key_from_secret_key(signing_sk, &mut signing_pk),
+                UMBRAL_OK
+            );
+            assert_eq!(umbral_secret_key_random(&mut receiving_sk), UMBRAL_OK);
+            assert_eq!(
+                umbral_public_key_from_secret_key(receiving_sk, &mut receiving_pk),
+                UMBRAL_OK
+            );
+
+            let plaintext = b"peace at dawn";
+            let mut capsule = ptr::null_mut();
+            let mut ciphertext = new_buffer();
+            assert_eq!(
+                umbral_encrypt(
+                    params,
+                    delegating_pk,
+                    plaintext.as_ptr(),
+                    plaintext.len(),
+                    &mut capsule,
+                    &mut ciphertext
+                ),
+                UMBRAL_OK
+            );
+
+            let mut plaintext_alice = new_buffer();
+            assert_eq!(
+                umbral_decrypt_original(
+                    delegating_sk,
+                    capsule,
+                    ciphertext.data,
+                    ciphertext.len,
+                    &mut plaintext_alice
+                ),
+                UMBRAL_OK
+            );
+            assert_eq!(buffer_contents(&plaintext_alice), plaintext);
+            umbral_buffer_free(plaintext_alice);
+
+            // Send the capsule over the wire
+            let mut capsule_bytes = new_buffer();
+            assert_eq!(
+                umbral_capsule_to_bytes(capsule, &mut capsule_bytes),
+                UMBRAL_OK
+            );
+            let mut capsule_back = ptr::null_mut();
+            assert_eq!(
+                umbral_capsule_from_bytes(capsule_bytes.data, capsule_bytes.len, &mut capsule_back),
+                UMBRAL_OK
+            );
+            assert_eq!(*capsule_back, *capsule);
+            umbral_buffer_free(capsule_bytes);
+
+            let threshold = 2;
+            let num_kfrags = 3;
+            let mut kfrags = [ptr::null_mut(); 3];
+            assert_eq!(
+                umbral_generate_kfrags(
+                    params,
+                    delegating_sk,
+                    receiving_pk,
+                    signing_sk,
+                    threshold,
+                    num_kfrags,
+                    true,
+                    true,
+                    kfrags.as_mut_ptr()
+                ),
+                UMBRAL_OK
+            );
+
+            let mut cfrags = Vec::new();
+            for kfrag in kfrags[..threshold].iter() {
+                assert_eq!(
+                    umbral_kfrag_verify(*kfrag, signing_pk, delegating_pk, receiving_pk),
+                    UMBRAL_OK
+                );
+                let mut cfrag = ptr::null_mut();
+                assert_eq!(
+                    umbral_reencrypt(capsule_back, *kfrag, ptr::null(), 0, &mut cfrag),
+                    UMBRAL_OK
+                );
+                assert_eq!(
+                    umbral_cfrag_verify(cfrag, capsule, delegating_pk, receiving_pk, signing_pk),
+                    UMBRAL_OK
+                );
+                cfrags.push(cfrag as *const CapsuleFrag);
+            }
+
+            let mut plaintext_bob = new_buffer();
+            assert_eq!(
+                umbral_decrypt_reencrypted(
+                    receiving_sk,
+                    delegating_pk,
+                    capsule,
+                    cfrags.as_ptr(),
+                    1,
+                    ciphertext.data,
+                    ciphertext.len,
+                    &mut plaintext_bob
+                ),
+                UMBRAL_ERROR_DECRYPTION_FAILED
+            );
+            assert_eq!(
+                umbral_decrypt_reencrypted(
+                    receiving_sk,
+                    delegating_pk,
+                    capsule,
+                    cfrags.as_ptr(),
+                    cfrags.len(),
+                    ciphertext.data,
+                    ciphertext.len,
+                    &mut plaintext_bob
+                ),
+                UMBRAL_OK
+            );
+            assert_eq!(buffer_contents(&plaintext_bob), plaintext);
+            umbral_buffer_free(plaintext_bob);
+
+            for cfrag in cfrags {
+                umbral_cfrag_free(cfrag as *mut CapsuleFrag);
+            }
+            for kfrag in kfrags.iter() {
+                umbral_kfrag_free(*kfrag);
+            }
+            umbral_buffer_free(ciphertext);
+            umbral_capsule_free(capsule_back);
+            umbral_capsule_free(capsule);
+            for sk in [delegating_sk, signing_sk, receiving_sk].iter() {
+                umbral_secret_key_free(*sk);
+            }
+            for pk in [delegating_pk, signing_pk, receiving_pk].iter() {
+                umbral_public_key_free(*pk);
+            }
+            umbral_parameters_free(params);
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        unsafe {
+            let mut sk = ptr::null_mut();
+            assert_eq!(
+                umbral_secret_key_random(ptr::null_mut()),
+                UMBRAL_ERROR_NULL_POINTER
+            );
+            assert_eq!(
+                umbral_public_key_from_secret_key(ptr::null(), ptr::null_mut()),
+                UMBRAL_ERROR_NULL_POINTER
+            );
+
+            let garbage = [1u8; 5];
+            assert_eq!(
+                umbral_secret_key_from_bytes(garbage.as_ptr(), garbage.len(), &mut sk),
+                UMBRAL_ERROR_INVALID_BYTES
+            );
+            assert!(sk.is_null());
+        }
+    }
+}
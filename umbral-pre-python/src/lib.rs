@@ -1,6 +1,12 @@
+use pyo3::class::basic::CompareOp;
+use pyo3::exceptions::PyTypeError;
 use pyo3::prelude::*;
 use pyo3::types::PyBytes;
 use pyo3::wrap_pyfunction;
+use pyo3::{PyNativeType, PyObjectProtocol};
+use sha2::{Digest, Sha256};
+
+use umbral_pre::SerializableToArray;
 
 #[pyclass(module = "umbral")]
 pub struct SecretKey {
@@ -15,6 +21,14 @@ impl SecretKey {
             backend: umbral_pre::SecretKey::random(),
         }
     }
+
+    /// Returns the corresponding public key
+    /// (same as `PublicKey.from_secret_key()`).
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey {
+            backend: *self.backend.public_key(),
+        }
+    }
 }
 
 #[pyclass(module = "umbral")]
@@ -22,6 +36,12 @@ pub struct PublicKey {
     backend: umbral_pre::PublicKey,
 }
 
+impl PublicKey {
+    fn fingerprint_bytes(&self) -> Vec<u8> {
+        Sha256::digest(&self.backend.to_array()).to_vec()
+    }
+}
+
 #[pymethods]
 impl PublicKey {
     #[staticmethod]
@@ -30,6 +50,38 @@ impl PublicKey {
             backend: umbral_pre::PublicKey::from_secret_key(&sk.backend),
         }
     }
+
+    /// Returns the hex-encoded SHA-256 digest of the serialized key.
+    pub fn fingerprint(&self) -> String {
+        self.fingerprint_bytes()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+}
+
+#[pyproto]
+impl PyObjectProtocol for PublicKey {
+    fn __richcmp__(&self, other: &PyAny, op: CompareOp) -> PyResult<PyObject> {
+        let py = other.py();
+        // Comparisons with other types are left to Python
+        // (so that e.g. `pk == 1` is `False` instead of an exception).
+        let other = match other.extract::<PyRef<PublicKey>>() {
+            Ok(other) => other,
+            Err(_) => return Ok(py.NotImplemented()),
+        };
+        match op {
+            CompareOp::Eq => Ok((self.backend == other.backend).into_py(py)),
+            CompareOp::Ne => Ok((self.backend != other.backend).into_py(py)),
+            _ => Err(PyTypeError::new_err("PublicKey objects are not ordered")),
+        }
+    }
+
+    fn __hash__(&self) -> isize {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&self.fingerprint_bytes()[..8]);
+        i64::from_be_bytes(bytes) as isize
+    }
 }
 
 #[pyclass(module = "umbral")]
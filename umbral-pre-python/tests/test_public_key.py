@@ -0,0 +1,48 @@
+import hashlib
+
+import pytest
+
+import umbral_pre
+
+
+def test_fingerprint():
+    sk = umbral_pre.SecretKey.random()
+    pk = umbral_pre.PublicKey.from_secret_key(sk)
+
+    fingerprint = pk.fingerprint()
+    assert len(fingerprint) == 64
+    assert fingerprint == pk.fingerprint()
+    assert int(fingerprint, 16) >= 0
+
+    other_pk = umbral_pre.PublicKey.from_secret_key(umbral_pre.SecretKey.random())
+    assert other_pk.fingerprint() != fingerprint
+
+
+def test_equality_and_hashing():
+    sk = umbral_pre.SecretKey.random()
+    pk = umbral_pre.PublicKey.from_secret_key(sk)
+    same_pk = umbral_pre.PublicKey.from_secret_key(sk)
+    other_pk = umbral_pre.PublicKey.from_secret_key(umbral_pre.SecretKey.random())
+
+    assert pk == same_pk
+    assert not pk != same_pk
+    assert pk != other_pk
+    assert not pk == other_pk
+
+    assert hash(pk) == hash(same_pk)
+    assert len({pk, same_pk, other_pk}) == 2
+    assert {pk: 1}[same_pk] == 1
+
+    with pytest.raises(TypeError):
+        pk < other_pk
+
+
+def test_comparison_with_other_types():
+    pk = umbral_pre.PublicKey.from_secret_key(umbral_pre.SecretKey.random())
+
+    # Python falls back to the identity comparison instead of raising
+    assert not pk == 1
+    assert pk != "public key"
+    assert pk != None
+    assert pk not in [1, "public key", None]
+    assert pk != umbral_pre.Parameters()
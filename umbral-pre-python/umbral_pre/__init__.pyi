@@ -6,11 +6,23 @@ class SecretKey:
     def random() -> SecretKey:
         ...
 
+    def public_key(self) -> PublicKey:
+        ...
+
 class PublicKey:
     @staticmethod
     def from_secret_key(sk: SecretKey) -> PublicKey:
         ...
 
+    def fingerprint(self) -> str:
+        ...
+
+    def __eq__(self, other: object) -> bool:
+        ...
+
+    def __hash__(self) -> int:
+        ...
+
 
 class Parameters: ...
 
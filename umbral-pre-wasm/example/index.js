@@ -34,6 +34,7 @@ let capsule = result.capsule;
 
 // Since data was encrypted with Alice's public key, Alice can open the capsule
 // and decrypt the ciphertext with her private key.
+// (`decrypt_original()` returns `undefined` if the decryption fails.)
 
 let plaintext_alice = umbral.decrypt_original(alice_sk, capsule, ciphertext);
 console.assert(dec.decode(plaintext_alice) == plaintext, "decrypt_original() failed");
@@ -10,8 +10,84 @@ static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 use wasm_bindgen::prelude::{wasm_bindgen, JsValue};
 
 use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
 use alloc::{vec, vec::Vec};
 
+use generic_array::typenum::Unsigned;
+use sha2::{Digest, Sha256};
+use umbral_pre::{DeserializationError, SerializableToArray};
+
+/// Backend types whose deserialization reports the reason of a failure.
+trait Deserializable: SerializableToArray {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError>;
+}
+
+impl Deserializable for umbral_pre::SecretKey {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError> {
+        // The length is checked separately, so the scalar is the only thing that can be wrong.
+        Self::from_bytes(data).ok_or(DeserializationError::InvalidScalar)
+    }
+}
+
+impl Deserializable for umbral_pre::PublicKey {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError> {
+        Ok(Self::try_from_bytes(data)?)
+    }
+}
+
+impl Deserializable for umbral_pre::Parameters {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(data)
+    }
+}
+
+impl Deserializable for umbral_pre::Capsule {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(data)
+    }
+}
+
+impl Deserializable for umbral_pre::CapsuleFrag {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(data)
+    }
+}
+
+impl Deserializable for umbral_pre::KeyFrag {
+    fn deserialize(data: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(data)
+    }
+}
+
+/// Deserializes an object, reporting the reason of a failure to the JS side.
+fn from_bytes<T: Deserializable>(type_name: &str, data: &[u8]) -> Result<T, JsValue> {
+    let expected = <T as SerializableToArray>::Size::to_usize();
+    if data.len() != expected {
+        return Err(JsValue::from_str(&format!(
+            "expected {} bytes for {}, got {}",
+            expected,
+            type_name,
+            data.len()
+        )));
+    }
+    T::deserialize(data).map_err(|err| {
+        let message = match err {
+            DeserializationError::WrongLength => format!("wrong number of bytes for {}", type_name),
+            DeserializationError::InvalidPoint(_) => {
+                format!("invalid curve point in {}", type_name)
+            }
+            DeserializationError::InvalidScalar => format!("invalid scalar in {}", type_name),
+            DeserializationError::InvalidSignature(_) => {
+                format!("invalid signature in {}", type_name)
+            }
+            DeserializationError::InvalidBool => format!("invalid boolean flag in {}", type_name),
+            DeserializationError::InvalidCapsule => format!("{} failed verification", type_name),
+        };
+        JsValue::from_str(&message)
+    })
+}
+
 #[wasm_bindgen]
 pub struct SecretKey(umbral_pre::SecretKey);
 
@@ -22,6 +98,23 @@ impl SecretKey {
         console_error_panic_hook::set_once(); // TODO (#16): find a better place to initialize it
         Self(umbral_pre::SecretKey::random())
     }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(data: &[u8]) -> Result<SecretKey, JsValue> {
+        from_bytes("SecretKey", data).map(Self)
+    }
+
+    /// Returns the corresponding public key
+    /// (same as `PublicKey.from_secret_key()`, but without a separate call).
+    #[wasm_bindgen(js_name = publicKey)]
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(*self.0.public_key())
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        self.0.to_array().to_vec().into_boxed_slice()
+    }
 }
 
 #[wasm_bindgen]
@@ -33,6 +126,30 @@ impl PublicKey {
     pub fn from_secret_key(secret_key: &SecretKey) -> Self {
         Self(umbral_pre::PublicKey::from_secret_key(&secret_key.0))
     }
+
+    /// Returns the hex-encoded SHA-256 digest of the serialized key,
+    /// which can be used to index collections by key.
+    pub fn fingerprint(&self) -> String {
+        Sha256::digest(&self.0.to_array())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_js_string(&self) -> String {
+        format!("PublicKey({})", self.fingerprint())
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(data: &[u8]) -> Result<PublicKey, JsValue> {
+        from_bytes("PublicKey", data).map(Self)
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        self.0.to_array().to_vec().into_boxed_slice()
+    }
 }
 
 #[wasm_bindgen]
@@ -44,6 +161,16 @@ impl Parameters {
     pub fn new() -> Self {
         Self(umbral_pre::Parameters::new())
     }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(data: &[u8]) -> Result<Parameters, JsValue> {
+        from_bytes("Parameters", data).map(Self)
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        self.0.to_array().to_vec().into_boxed_slice()
+    }
 }
 
 impl Default for Parameters {
@@ -68,6 +195,29 @@ impl Capsule {
             cfrags: vec![cfrag.clone()],
         }
     }
+
+    #[wasm_bindgen]
+    pub fn verify(&self) -> bool {
+        self.0.verify()
+    }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(data: &[u8]) -> Result<Capsule, JsValue> {
+        from_bytes("Capsule", data).map(Self)
+    }
+
+    /// Same as `fromBytes()`, but does not verify the capsule
+    /// (see the warning for `Capsule::from_bytes_unchecked()` in the main crate);
+    /// call `verify()` before using the result.
+    #[wasm_bindgen(js_name = fromBytesUnchecked)]
+    pub fn from_bytes_unchecked(data: &[u8]) -> Option<Capsule> {
+        umbral_pre::Capsule::from_bytes_unchecked(data).map(Self)
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        self.0.to_array().to_vec().into_boxed_slice()
+    }
 }
 
 #[wasm_bindgen]
@@ -80,17 +230,27 @@ impl CapsuleFrag {
     pub fn verify(
         &self,
         capsule: &Capsule,
-        signing_pubkey: &PublicKey,
         delegating_pubkey: &PublicKey,
         receiving_pubkey: &PublicKey,
+        signing_pubkey: &PublicKey,
     ) -> bool {
         self.0.verify(
             &capsule.0,
-            &signing_pubkey.0,
             &delegating_pubkey.0,
             &receiving_pubkey.0,
+            &signing_pubkey.0,
         )
     }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(data: &[u8]) -> Result<CapsuleFrag, JsValue> {
+        from_bytes("CapsuleFrag", data).map(Self)
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        self.0.to_array().to_vec().into_boxed_slice()
+    }
 }
 
 #[wasm_bindgen]
@@ -161,8 +321,18 @@ pub fn encrypt(
 ) -> Option<EncryptionResult> {
     let backend_params = params.0;
     let backend_pubkey = alice_pubkey.0;
-    let (capsule, ciphertext) =
-        umbral_pre::encrypt(&backend_params, &backend_pubkey, plaintext).unwrap();
+    encryption_result(umbral_pre::encrypt(
+        &backend_params,
+        &backend_pubkey,
+        plaintext,
+    ))
+}
+
+/// Converts the result of the backend encryption, turning a failure into `undefined`.
+fn encryption_result(
+    result: Result<(umbral_pre::Capsule, Box<[u8]>), umbral_pre::EncryptionError>,
+) -> Option<EncryptionResult> {
+    let (capsule, ciphertext) = result.ok()?;
     Some(EncryptionResult::new(ciphertext, Capsule(capsule)))
 }
 
@@ -171,8 +341,8 @@ pub fn decrypt_original(
     decrypting_key: &SecretKey,
     capsule: &Capsule,
     ciphertext: &[u8],
-) -> Box<[u8]> {
-    umbral_pre::decrypt_original(&decrypting_key.0, &capsule.0, ciphertext).unwrap()
+) -> Option<Box<[u8]>> {
+    umbral_pre::decrypt_original(&decrypting_key.0, &capsule.0, ciphertext)
 }
 
 #[wasm_bindgen]
@@ -229,6 +399,16 @@ impl KeyFrag {
             Some(&backend_receiving_pubkey),
         )
     }
+
+    #[wasm_bindgen(js_name = fromBytes)]
+    pub fn from_bytes(data: &[u8]) -> Result<KeyFrag, JsValue> {
+        from_bytes("KeyFrag", data).map(Self)
+    }
+
+    #[wasm_bindgen(js_name = toBytes)]
+    pub fn to_bytes(&self) -> Box<[u8]> {
+        self.0.to_array().to_vec().into_boxed_slice()
+    }
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -271,3 +451,38 @@ pub fn reencrypt(capsule: &Capsule, kfrag: &KeyFrag, metadata: Option<Box<[u8]>>
     let backend_cfrag = umbral_pre::reencrypt(&capsule.0, &kfrag.0, metadata_slice);
     CapsuleFrag(backend_cfrag)
 }
+
+/// Same as [`reencrypt()`], but takes the capsule and the key fragment serialized,
+/// and returns the serialized capsule fragment,
+/// so that a proxy receiving raw bytes does not need to create the wrapper objects.
+#[wasm_bindgen]
+pub fn reencrypt_bytes(
+    capsule_bytes: &[u8],
+    kfrag_bytes: &[u8],
+    metadata: Option<Box<[u8]>>,
+) -> Result<Box<[u8]>, JsValue> {
+    let capsule: umbral_pre::Capsule = from_bytes("Capsule", capsule_bytes)?;
+    let kfrag: umbral_pre::KeyFrag = from_bytes("KeyFrag", kfrag_bytes)?;
+    let metadata_slice = metadata.as_ref().map(|x| x.as_ref());
+    let cfrag = umbral_pre::reencrypt(&capsule, &kfrag, metadata_slice);
+    Ok(cfrag.to_array().to_vec().into_boxed_slice())
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use super::encryption_result;
+
+    // `usize` is 32-bit in wasm, so a plaintext exceeding `MAX_PLAINTEXT_SIZE`
+    // cannot be passed to `encrypt()`, and the failure has to be tested directly.
+    #[wasm_bindgen_test]
+    fn test_encryption_failure() {
+        let result = encryption_result(Err(umbral_pre::EncryptionError::PlaintextTooLarge));
+        assert!(result.is_none());
+
+        let result = encryption_result(Err(umbral_pre::EncryptionError::BackendFailure));
+        assert!(result.is_none());
+    }
+}
@@ -0,0 +1,320 @@
+//! Tests of the bindings, run in Node with `wasm-pack test --node`.
+
+#![cfg(target_arch = "wasm32")]
+
+use umbral_pre::SerializableToArray;
+use umbral_pre_wasm::{
+    decrypt_original, encrypt, reencrypt, reencrypt_bytes, Capsule, CapsuleFrag, KeyFrag,
+    Parameters, PublicKey, SecretKey,
+};
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn test_public_key_fingerprint() {
+    let sk = SecretKey::random();
+    let pk = PublicKey::from_secret_key(&sk);
+
+    let fingerprint = pk.fingerprint();
+    assert_eq!(fingerprint.len(), 64);
+    assert!(fingerprint.chars().all(|c| c.is_ascii_hexdigit()));
+    assert_eq!(sk.public_key().fingerprint(), fingerprint);
+
+    let other_pk = SecretKey::random().public_key();
+    assert_ne!(other_pk.fingerprint(), fingerprint);
+
+    assert_eq!(pk.to_js_string(), format!("PublicKey({})", fingerprint));
+}
+
+/// Checks that `to_bytes()` round-trips through `from_bytes()`,
+/// and that inputs one byte shorter or longer are rejected with a message.
+fn check_serialization<T>(
+    type_name: &str,
+    obj: &T,
+    to_bytes: impl Fn(&T) -> Box<[u8]>,
+    from_bytes: impl Fn(&[u8]) -> Result<T, JsValue>,
+) {
+    let bytes = to_bytes(obj);
+    let obj_back = from_bytes(&bytes).unwrap();
+    assert_eq!(to_bytes(&obj_back), bytes);
+
+    let size = bytes.len();
+    check_error(
+        &from_bytes,
+        &bytes[1..],
+        &format!(
+            "expected {} bytes for {}, got {}",
+            size,
+            type_name,
+            size - 1
+        ),
+    );
+
+    let mut longer = bytes.to_vec();
+    longer.push(0);
+    check_error(
+        &from_bytes,
+        &longer,
+        &format!(
+            "expected {} bytes for {}, got {}",
+            size,
+            type_name,
+            size + 1
+        ),
+    );
+
+    check_error(
+        &from_bytes,
+        &[],
+        &format!("expected {} bytes for {}, got 0", size, type_name),
+    );
+}
+
+/// Checks that `from_bytes()` rejects `bytes` with the given message.
+fn check_error<T>(from_bytes: impl Fn(&[u8]) -> Result<T, JsValue>, bytes: &[u8], message: &str) {
+    let error = from_bytes(bytes).err().unwrap();
+    assert_eq!(error.as_string().unwrap(), message);
+}
+
+/// Returns `bytes` with the first byte (the SEC1 prefix of the point in front)
+/// replaced by an invalid one.
+fn with_bad_point(bytes: &[u8]) -> Vec<u8> {
+    let mut bad = bytes.to_vec();
+    bad[0] = 0x05;
+    bad
+}
+
+#[wasm_bindgen_test]
+fn test_serialization() {
+    let params = Parameters::new();
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+    let signing_sk = SecretKey::random();
+    let receiving_pk = SecretKey::random().public_key();
+
+    check_serialization(
+        "Parameters",
+        &params,
+        Parameters::to_bytes,
+        Parameters::from_bytes,
+    );
+    check_error(
+        Parameters::from_bytes,
+        &with_bad_point(&params.to_bytes()),
+        "invalid curve point in Parameters",
+    );
+
+    check_serialization(
+        "SecretKey",
+        &delegating_sk,
+        SecretKey::to_bytes,
+        SecretKey::from_bytes,
+    );
+    check_error(
+        SecretKey::from_bytes,
+        &[0xff; 32],
+        "invalid scalar in SecretKey",
+    );
+
+    check_serialization(
+        "PublicKey",
+        &delegating_pk,
+        PublicKey::to_bytes,
+        PublicKey::from_bytes,
+    );
+    check_error(
+        PublicKey::from_bytes,
+        &with_bad_point(&delegating_pk.to_bytes()),
+        "invalid curve point in PublicKey",
+    );
+
+    let capsule = encrypt(&params, &delegating_pk, b"peace at dawn")
+        .unwrap()
+        .capsule;
+    check_serialization("Capsule", &capsule, Capsule::to_bytes, Capsule::from_bytes);
+    let capsule_bytes = capsule.to_bytes();
+    check_error(
+        Capsule::from_bytes,
+        &with_bad_point(&capsule_bytes),
+        "invalid curve point in Capsule",
+    );
+    let mut bad_scalar = capsule_bytes.to_vec();
+    let scalar_start = bad_scalar.len() - 32;
+    for byte in bad_scalar[scalar_start..].iter_mut() {
+        *byte = 0xff;
+    }
+    check_error(
+        Capsule::from_bytes,
+        &bad_scalar,
+        "invalid scalar in Capsule",
+    );
+    let mut bad_signature = capsule_bytes.to_vec();
+    let last = bad_signature.len() - 1;
+    bad_signature[last] ^= 1;
+    check_error(
+        Capsule::from_bytes,
+        &bad_signature,
+        "Capsule failed verification",
+    );
+
+    // `generate_kfrags()` returns `JsValue`s, which cannot be converted back on the Rust side,
+    // so the key fragment is created with the main crate.
+    let kfrags = umbral_pre::generate_kfrags(
+        &umbral_pre::Parameters::from_bytes(params.to_bytes()).unwrap(),
+        &umbral_pre::SecretKey::from_bytes(delegating_sk.to_bytes()).unwrap(),
+        &umbral_pre::PublicKey::from_bytes(receiving_pk.to_bytes()).unwrap(),
+        &umbral_pre::SecretKey::from_bytes(signing_sk.to_bytes()).unwrap(),
+        1,
+        1,
+        true,
+        true,
+    );
+    let kfrag = KeyFrag::from_bytes(&kfrags[0].to_array()).unwrap();
+    check_serialization("KeyFrag", &kfrag, KeyFrag::to_bytes, KeyFrag::from_bytes);
+    check_error(
+        KeyFrag::from_bytes,
+        &with_bad_point(&kfrag.to_bytes()),
+        "invalid curve point in KeyFrag",
+    );
+
+    let cfrag = reencrypt(&capsule, &kfrag, None);
+    check_serialization(
+        "CapsuleFrag",
+        &cfrag,
+        CapsuleFrag::to_bytes,
+        CapsuleFrag::from_bytes,
+    );
+    check_error(
+        CapsuleFrag::from_bytes,
+        &with_bad_point(&cfrag.to_bytes()),
+        "invalid curve point in CapsuleFrag",
+    );
+}
+
+#[wasm_bindgen_test]
+fn test_capsule_verify() {
+    let params = Parameters::new();
+    let delegating_pk = SecretKey::random().public_key();
+
+    let capsule = encrypt(&params, &delegating_pk, b"peace at dawn")
+        .unwrap()
+        .capsule;
+    assert!(capsule.verify());
+
+    // Swap `E` and `V` (the two points following the parameters)
+    let capsule_bytes = capsule.to_bytes();
+    let point_size = params.to_bytes().len();
+    let mut swapped = capsule_bytes.to_vec();
+    swapped[point_size..point_size * 3].rotate_left(point_size);
+
+    // Rejected when deserialized normally...
+    assert!(Capsule::from_bytes(&swapped).is_err());
+
+    // ...and reported as invalid when deserialized without the verification.
+    let unchecked = Capsule::from_bytes_unchecked(&swapped).unwrap();
+    assert!(!unchecked.verify());
+
+    let unchecked = Capsule::from_bytes_unchecked(&capsule_bytes).unwrap();
+    assert!(unchecked.verify());
+}
+
+#[wasm_bindgen_test]
+fn test_decrypt_original() {
+    let params = Parameters::new();
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+    let plaintext = b"peace at dawn";
+    let result = encrypt(&params, &delegating_pk, plaintext).unwrap();
+    let capsule = result.capsule;
+    let ciphertext = result.ciphertext();
+
+    let plaintext_alice = decrypt_original(&delegating_sk, &capsule, &ciphertext).unwrap();
+    assert_eq!(&plaintext_alice[..], plaintext);
+
+    // A wrong key results in `undefined` instead of a panic
+    let other_sk = SecretKey::random();
+    assert!(decrypt_original(&other_sk, &capsule, &ciphertext).is_none());
+
+    // Same for a modified ciphertext...
+    let mut corrupted = ciphertext.to_vec();
+    let last = corrupted.len() - 1;
+    corrupted[last] ^= 1;
+    assert!(decrypt_original(&delegating_sk, &capsule, &corrupted).is_none());
+
+    // ...a truncated one...
+    assert!(decrypt_original(&delegating_sk, &capsule, &ciphertext[..10]).is_none());
+
+    // ...and an empty one.
+    assert!(decrypt_original(&delegating_sk, &capsule, &[]).is_none());
+}
+
+#[wasm_bindgen_test]
+fn test_reencrypt_bytes() {
+    let params = Parameters::new();
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+    let signing_sk = SecretKey::random();
+    let signing_pk = PublicKey::from_secret_key(&signing_sk);
+    let receiving_sk = SecretKey::random();
+    let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+    let plaintext = b"peace at dawn";
+    let result = encrypt(&params, &delegating_pk, plaintext).unwrap();
+    let capsule = result.capsule;
+    let ciphertext = result.ciphertext();
+
+    let kfrags = umbral_pre::generate_kfrags(
+        &umbral_pre::Parameters::from_bytes(params.to_bytes()).unwrap(),
+        &umbral_pre::SecretKey::from_bytes(delegating_sk.to_bytes()).unwrap(),
+        &umbral_pre::PublicKey::from_bytes(receiving_pk.to_bytes()).unwrap(),
+        &umbral_pre::SecretKey::from_bytes(signing_sk.to_bytes()).unwrap(),
+        1,
+        1,
+        true,
+        true,
+    );
+    let kfrag_bytes = kfrags[0].to_array();
+
+    let metadata: Box<[u8]> = Box::new(*b"metadata");
+    let cfrag_bytes =
+        reencrypt_bytes(&capsule.to_bytes(), &kfrag_bytes, Some(metadata.clone())).unwrap();
+
+    // The result is a valid capsule fragment for the capsule
+    let cfrag = CapsuleFrag::from_bytes(&cfrag_bytes).unwrap();
+    assert!(cfrag.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+    let plaintext_bob = capsule
+        .with_cfrag(&cfrag)
+        .decrypt_reencrypted(&receiving_sk, &delegating_pk, &ciphertext)
+        .unwrap();
+    assert_eq!(&plaintext_bob[..], plaintext);
+
+    // Same as going through the objects (up to the randomness of the proof)
+    let kfrag = KeyFrag::from_bytes(&kfrag_bytes).unwrap();
+    let cfrag_objects = reencrypt(&capsule, &kfrag, Some(metadata));
+    assert!(cfrag_objects.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+    assert_eq!(cfrag_objects.to_bytes().len(), cfrag_bytes.len());
+
+    // Invalid inputs are rejected with a message instead of a panic
+    let capsule_bytes = capsule.to_bytes();
+    let error = reencrypt_bytes(&capsule_bytes[1..], &kfrag_bytes, None)
+        .err()
+        .unwrap();
+    assert!(error.as_string().unwrap().contains("Capsule"));
+
+    let error = reencrypt_bytes(&capsule_bytes, &kfrag_bytes[1..], None)
+        .err()
+        .unwrap();
+    assert!(error.as_string().unwrap().contains("KeyFrag"));
+
+    let bad_capsule = vec![0xffu8; capsule_bytes.len()];
+    let error = reencrypt_bytes(&bad_capsule, &kfrag_bytes, None)
+        .err()
+        .unwrap();
+    assert_eq!(error.as_string().unwrap(), "invalid curve point in Capsule");
+
+    let error = reencrypt_bytes(&capsule_bytes, &with_bad_point(&kfrag_bytes), None)
+        .err()
+        .unwrap();
+    assert_eq!(error.as_string().unwrap(), "invalid curve point in KeyFrag");
+}
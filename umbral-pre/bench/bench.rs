@@ -1,7 +1,15 @@
 use criterion::measurement::Measurement;
-use criterion::{criterion_group, criterion_main, BenchmarkGroup, Criterion};
+use criterion::{criterion_group, criterion_main, BenchmarkGroup, BenchmarkId, Criterion};
 
-use umbral_pre::bench::unsafe_hash_to_point;
+use umbral_pre::bench::{lambda_coeff, lambda_coeffs, unsafe_hash_to_point};
+use umbral_pre::math::CurveScalar;
+use umbral_pre::{
+    decrypt_reencrypted, encrypt, generate_kfrags, generate_kfrags_with_ids, reencrypt,
+    CapsuleFrag, KeyFrag, Parameters, PublicKey, SecretKey, SerializableToArray,
+};
+
+#[cfg(feature = "parallel")]
+use umbral_pre::{reencrypt_batch_parallel, Capsule};
 
 fn bench_unsafe_hash_to_point<'a, M: Measurement>(group: &mut BenchmarkGroup<'a, M>) {
     let data = b"abcdefg";
@@ -11,10 +19,221 @@ fn bench_unsafe_hash_to_point<'a, M: Measurement>(group: &mut BenchmarkGroup<'a,
     });
 }
 
+fn bench_verify<'a, M: Measurement>(group: &mut BenchmarkGroup<'a, M>) {
+    let params = Parameters::new();
+
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+    let signing_sk = SecretKey::random();
+    let signing_pk = PublicKey::from_secret_key(&signing_sk);
+    let receiving_sk = SecretKey::random();
+    let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+    let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+    let kfrags = generate_kfrags(
+        &params,
+        &delegating_sk,
+        &receiving_pk,
+        &signing_sk,
+        2,
+        3,
+        true,
+        true,
+    );
+    let cfrag = reencrypt(&capsule, &kfrags[0], None);
+
+    group.bench_function("KeyFrag::verify", |b| {
+        b.iter(|| kfrags[0].verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)))
+    });
+    group.bench_function("CapsuleFrag::verify", |b| {
+        b.iter(|| cfrag.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk))
+    });
+}
+
+// To measure the overhead of the correctness proofs,
+// compare the results with and without `RUSTFLAGS="--cfg umbral_insecure_no_proofs"`.
+fn bench_reencrypt<'a, M: Measurement>(group: &mut BenchmarkGroup<'a, M>) {
+    let params = Parameters::new();
+
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+    let signing_sk = SecretKey::random();
+    let receiving_sk = SecretKey::random();
+    let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+    let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+    let kfrags = generate_kfrags(
+        &params,
+        &delegating_sk,
+        &receiving_pk,
+        &signing_sk,
+        2,
+        3,
+        true,
+        true,
+    );
+
+    let name = if cfg!(umbral_insecure_no_proofs) {
+        "reencrypt (no proofs)"
+    } else {
+        "reencrypt"
+    };
+    group.bench_function(name, |b| b.iter(|| reencrypt(&capsule, &kfrags[0], None)));
+
+    // A proxy can keep the deserialized `KeyFrag` around instead of parsing it per request;
+    // these show what the parsing costs.
+    let kfrag_bytes = kfrags[0].to_array();
+    group.bench_function("KeyFrag::from_bytes", |b| {
+        b.iter(|| KeyFrag::from_bytes(kfrag_bytes).unwrap())
+    });
+    group.bench_function("reencrypt (kfrag from bytes)", |b| {
+        b.iter(|| {
+            let kfrag = KeyFrag::from_bytes(kfrag_bytes).unwrap();
+            reencrypt(&capsule, &kfrag, None)
+        })
+    });
+}
+
+// Keys and kfrag IDs are derived deterministically, so that the runs are comparable.
+fn bench_reconstruction<'a, M: Measurement>(group: &mut BenchmarkGroup<'a, M>) {
+    let params = Parameters::new();
+
+    let seed = b"umbral reconstruction benchmark";
+    let delegating_sk = SecretKey::from_seed(seed, "m/0'").unwrap();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+    let signing_sk = SecretKey::from_seed(seed, "m/1'").unwrap();
+    let receiving_sk = SecretKey::from_seed(seed, "m/2'").unwrap();
+    let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+    let (capsule, ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+
+    for threshold in [2usize, 4, 8, 16, 32].iter() {
+        let ids: Vec<CurveScalar> = (1..=*threshold as u64).map(CurveScalar::from_u64).collect();
+        let kfrags = generate_kfrags_with_ids(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            *threshold,
+            &ids,
+            true,
+            true,
+            None,
+        )
+        .unwrap();
+        let cfrags: Vec<CapsuleFrag> = kfrags
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("lambda_coeff", threshold),
+            &ids,
+            |b, ids| {
+                b.iter(|| {
+                    (0..ids.len())
+                        .map(|i| lambda_coeff(ids, i))
+                        .collect::<Vec<_>>()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("lambda_coeffs", threshold),
+            &ids,
+            |b, ids| b.iter(|| lambda_coeffs(ids)),
+        );
+        // The ciphertext is short, so the time is dominated by the reconstruction of the key.
+        group.bench_with_input(
+            BenchmarkId::new("decrypt_reencrypted", threshold),
+            &cfrags,
+            |b, cfrags| {
+                b.iter(|| {
+                    decrypt_reencrypted(
+                        &receiving_sk,
+                        &delegating_pk,
+                        &capsule,
+                        cfrags,
+                        &ciphertext,
+                    )
+                })
+            },
+        );
+    }
+}
+
+#[cfg(feature = "parallel")]
+fn bench_reencrypt_batch<'a, M: Measurement>(group: &mut BenchmarkGroup<'a, M>) {
+    let params = Parameters::new();
+
+    let delegating_sk = SecretKey::random();
+    let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+    let signing_sk = SecretKey::random();
+    let receiving_sk = SecretKey::random();
+    let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+    let kfrags = generate_kfrags(
+        &params,
+        &delegating_sk,
+        &receiving_pk,
+        &signing_sk,
+        2,
+        3,
+        true,
+        true,
+    );
+
+    for num_capsules in [1usize, 4, 16, 64].iter() {
+        let capsules: Vec<Capsule> = (0..*num_capsules)
+            .map(|_| {
+                encrypt(&params, &delegating_pk, b"peace at dawn")
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        group.bench_with_input(
+            BenchmarkId::new("sequential", num_capsules),
+            &capsules,
+            |b, capsules| {
+                b.iter(|| {
+                    capsules
+                        .iter()
+                        .map(|capsule| reencrypt(capsule, &kfrags[0], None))
+                        .collect::<Vec<CapsuleFrag>>()
+                })
+            },
+        );
+        group.bench_with_input(
+            BenchmarkId::new("parallel", num_capsules),
+            &capsules,
+            |b, capsules| b.iter(|| reencrypt_batch_parallel(capsules, &kfrags[0], None)),
+        );
+    }
+}
+
 fn bench_all(c: &mut Criterion) {
     let mut group = c.benchmark_group("internals");
     bench_unsafe_hash_to_point(&mut group);
     group.finish();
+
+    let mut group = c.benchmark_group("verification");
+    bench_verify(&mut group);
+    group.finish();
+
+    let mut group = c.benchmark_group("reencryption");
+    bench_reencrypt(&mut group);
+    group.finish();
+
+    let mut group = c.benchmark_group("reconstruction");
+    bench_reconstruction(&mut group);
+    group.finish();
+
+    #[cfg(feature = "parallel")]
+    {
+        let mut group = c.benchmark_group("reencrypt_batch");
+        bench_reencrypt_batch(&mut group);
+        group.finish();
+    }
 }
 
 criterion_group!(benches, bench_all);
@@ -0,0 +1,6 @@
+fn main() {
+    // Declares the insecure benchmarking cfg (see `Cargo.toml`), so that the compilers
+    // checking cfg names do not warn about it. Older toolchains ignore this instruction.
+    println!("cargo:rustc-check-cfg=cfg(umbral_insecure_no_proofs)");
+    println!("cargo:rerun-if-changed=build.rs");
+}
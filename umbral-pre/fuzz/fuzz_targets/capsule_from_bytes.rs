@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use umbral_pre::{Capsule, SerializableToArray};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(capsule) = Capsule::from_bytes(data) {
+        // Anything that deserializes must serialize back to the same bytes.
+        assert_eq!(capsule.to_array().as_slice(), data);
+        assert!(capsule.verify());
+    }
+});
@@ -0,0 +1,12 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use umbral_pre::{encrypt, CapsuleFrag, Parameters, PublicKey, SecretKey, SerializableToArray};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(cfrag) = CapsuleFrag::from_bytes(data) {
+        assert_eq!(cfrag.to_array().as_slice(), data);
+        let pk = PublicKey::from_secret_key(&SecretKey::random());
+        let (capsule, _ciphertext) = encrypt(&Parameters::new(), &pk, b"").unwrap();
+        let _ = cfrag.verify(&capsule, &pk, &pk, &pk);
+    }
+});
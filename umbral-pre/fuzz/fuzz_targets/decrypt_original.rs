@@ -0,0 +1,22 @@
+#![no_main]
+use generic_array::typenum::Unsigned;
+use libfuzzer_sys::fuzz_target;
+use umbral_pre::{decrypt_original, Capsule, SecretKey, SerializableToArray};
+
+fuzz_target!(|data: &[u8]| {
+    // The input is split into a secret key, a capsule, and the ciphertext.
+    let sk_size = <SecretKey as SerializableToArray>::Size::to_usize();
+    let capsule_size = <Capsule as SerializableToArray>::Size::to_usize();
+    let (sk_bytes, rest) = data.split_at(data.len().min(sk_size));
+    let (capsule_bytes, ciphertext) = rest.split_at(rest.len().min(capsule_size));
+
+    let sk = match SecretKey::from_bytes(sk_bytes) {
+        Some(sk) => sk,
+        None => return,
+    };
+    let capsule = match Capsule::from_bytes(capsule_bytes) {
+        Some(capsule) => capsule,
+        None => return,
+    };
+    let _ = decrypt_original(&sk, &capsule, ciphertext);
+});
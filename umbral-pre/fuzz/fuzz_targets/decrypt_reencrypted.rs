@@ -0,0 +1,32 @@
+#![no_main]
+use generic_array::typenum::Unsigned;
+use libfuzzer_sys::fuzz_target;
+use umbral_pre::{
+    decrypt_reencrypted, encrypt, CapsuleFrag, Parameters, PublicKey, SecretKey,
+    SerializableToArray,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // The input is split into a sequence of capsule fragments and the ciphertext.
+    let cfrag_size = <CapsuleFrag as SerializableToArray>::Size::to_usize();
+    let num_cfrags = data.first().map_or(0, |n| (*n % 4) as usize);
+    let data = data.get(1..).unwrap_or(&[]);
+    if data.len() < num_cfrags * cfrag_size {
+        return;
+    }
+    let (cfrag_bytes, ciphertext) = data.split_at(num_cfrags * cfrag_size);
+
+    let cfrags = cfrag_bytes
+        .chunks(cfrag_size)
+        .map(CapsuleFrag::from_bytes)
+        .collect::<Option<Vec<_>>>();
+    let cfrags = match cfrags {
+        Some(cfrags) => cfrags,
+        None => return,
+    };
+
+    let delegating_pk = PublicKey::from_secret_key(&SecretKey::random());
+    let receiving_sk = SecretKey::random();
+    let (capsule, _ciphertext) = encrypt(&Parameters::new(), &delegating_pk, b"").unwrap();
+    let _ = decrypt_reencrypted(&receiving_sk, &delegating_pk, &capsule, &cfrags, ciphertext);
+});
@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use umbral_pre::{KeyFrag, PublicKey, SecretKey, SerializableToArray};
+
+fuzz_target!(|data: &[u8]| {
+    if let Some(kfrag) = KeyFrag::from_bytes(data) {
+        assert_eq!(kfrag.to_array().as_slice(), data);
+        let pk = PublicKey::from_secret_key(&SecretKey::random());
+        let _ = kfrag.verify(&pk, Some(&pk), Some(&pk));
+    }
+});
@@ -2,4 +2,5 @@
 //! This module re-exports some internals for the purposes of benchmarking.
 //! Should not be used by regular users.
 
+pub use crate::capsule::{lambda_coeff, lambda_coeffs};
 pub use crate::hashing::unsafe_hash_to_point;
@@ -0,0 +1,134 @@
+//! A container for exporting and importing a complete delegation.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use serde::{Deserialize, Serialize};
+
+use crate::curve::PublicKey;
+use crate::key_frag::KeyFrag;
+use crate::params::Parameters;
+
+/// Everything needed to hand over a delegation to the reencrypting parties:
+/// the key fragments along with the public keys they can be verified against.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DelegationBundle {
+    /// Scheme parameters the key fragments were created with.
+    pub params: Parameters,
+    /// The public key of the delegating party.
+    pub delegating_pk: PublicKey,
+    /// The public key of the receiving party.
+    pub receiving_pk: PublicKey,
+    /// The public key corresponding to the secret key the key fragments were signed with.
+    pub verifying_pk: PublicKey,
+    /// The key fragments.
+    pub kfrags: Vec<KeyFrag>,
+}
+
+impl DelegationBundle {
+    /// Checks that all the key fragments were created with the bundle's parameters,
+    /// and verify with the bundle's keys.
+    #[must_use = "the verification result must be checked"]
+    pub fn verify(&self) -> bool {
+        self.kfrags.iter().all(|kfrag| {
+            kfrag.params == self.params
+                && kfrag.verify(
+                    &self.verifying_pk,
+                    Some(&self.delegating_pk),
+                    Some(&self.receiving_pk),
+                )
+        })
+    }
+
+    /// Serializes the bundle into a JSON document.
+    pub fn to_json(&self) -> String {
+        // Serialization of these objects cannot fail.
+        serde_json::to_string(self).unwrap()
+    }
+
+    /// Deserializes the bundle from a JSON document produced by [`to_json()`](`Self::to_json()`).
+    ///
+    /// Returns `None` if the document is malformed, any of the objects fails to deserialize,
+    /// or the bundle fails [`verify()`](`Self::verify()`).
+    pub fn from_json(json: &str) -> Option<Self> {
+        let bundle: Self = serde_json::from_str(json).ok()?;
+        if bundle.verify() {
+            Some(bundle)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use super::DelegationBundle;
+    use crate::{
+        decrypt_reencrypted, encrypt, generate_kfrags, reencrypt, CapsuleFrag, Parameters,
+        PublicKey, SecretKey,
+    };
+
+    #[test]
+    fn test_json_round_trip() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let signing_sk = SecretKey::random();
+        let verifying_pk = PublicKey::from_secret_key(&signing_sk);
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+
+        let bundle = DelegationBundle {
+            params,
+            delegating_pk,
+            receiving_pk,
+            verifying_pk,
+            kfrags: kfrags.to_vec(),
+        };
+
+        let json = bundle.to_json();
+        let bundle_back = DelegationBundle::from_json(&json).unwrap();
+        assert_eq!(bundle, bundle_back);
+
+        // Reencrypt with the reloaded kfrags
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let cfrags: Vec<CapsuleFrag> = bundle_back.kfrags[..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // A bundle with mismatched keys is rejected on load
+        let mut wrong_bundle = bundle;
+        wrong_bundle.receiving_pk = delegating_pk;
+        assert!(DelegationBundle::from_json(&wrong_bundle.to_json()).is_none());
+
+        assert!(DelegationBundle::from_json("{}").is_none());
+    }
+}
@@ -1,17 +1,50 @@
-use crate::capsule_frag::CapsuleFrag;
-use crate::constants::{NON_INTERACTIVE, X_COORDINATE};
+use crate::capsule_frag::{CapsuleFrag, ReencryptedShare, SlimCapsuleFrag};
 use crate::curve::{CurvePoint, CurveScalar, PublicKey, SecretKey};
+use crate::domain::{NON_INTERACTIVE, X_COORDINATE};
 use crate::hashing::ScalarDigest;
 use crate::params::Parameters;
-use crate::traits::SerializableToArray;
+use crate::traits::{
+    DeserializationError, FieldReader, SerializableToArray, SerializableToFixedBytes,
+};
 
 use alloc::vec::Vec;
+use core::fmt;
 
 use generic_array::sequence::Concat;
 use generic_array::GenericArray;
-use typenum::op;
+use sha3::{Digest, Sha3_256};
+use typenum::{op, Unsigned, U32};
+use zeroize::{Zeroize, Zeroizing};
+
+/// Problems with a set of capsule fragments detected by [`Capsule::can_open()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OpenReencryptedError {
+    /// There are fewer capsule fragments than the threshold.
+    NotEnoughCfrags,
+    /// The capsule fragments were produced by different sets of key fragments
+    /// (their precursors differ).
+    MismatchedPrecursors,
+    /// Several capsule fragments were produced by the same key fragment.
+    RepeatingKfragIds,
+}
+
+impl fmt::Display for OpenReencryptedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotEnoughCfrags => write!(f, "Not enough capsule fragments"),
+            Self::MismatchedPrecursors => write!(f, "Capsule fragments have different precursors"),
+            Self::RepeatingKfragIds => {
+                write!(f, "Capsule fragments have repeating key fragment IDs")
+            }
+        }
+    }
+}
 
 /// Encapsulated symmetric key used to encrypt the plaintext.
+///
+/// Deserialization checks that the parameters and both points are valid curve points,
+/// that the signature is a canonical scalar, and that the capsule is well-formed
+/// (its signature verifies), so a deserialized capsule needs no further checks.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Capsule {
     pub(crate) params: Parameters,
@@ -37,14 +70,16 @@ impl SerializableToArray for Capsule {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        let (params, rest) = Parameters::take(*arr)?;
-        let (point_e, rest) = CurvePoint::take(rest)?;
-        let (point_v, rest) = CurvePoint::take(rest)?;
-        let signature = CurveScalar::take_last(rest)?;
-        Self::new_verified(params, point_e, point_v, signature)
+        Self::try_from_bytes(arr).ok()
     }
 }
 
+/// The size of a serialized [`Capsule`] in bytes.
+pub const CAPSULE_SIZE: usize = 131;
+
+impl SerializableToFixedBytes<CAPSULE_SIZE> for Capsule {}
+const _: [(); CAPSULE_SIZE] = [(); <<Capsule as SerializableToArray>::Size as Unsigned>::USIZE];
+
 impl Capsule {
     pub(crate) fn new_verified(
         params: Parameters,
@@ -64,8 +99,82 @@ impl Capsule {
         }
     }
 
+    /// Deserializes a capsule, reporting the reason of a failure.
+    ///
+    /// [`SerializableToArray::from_bytes()`] is equivalent, but discards the error.
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, DeserializationError> {
+        let mut reader = FieldReader::new::<Self>(bytes.as_ref())?;
+        let params = Parameters { u: reader.point()? };
+        let point_e = reader.point()?;
+        let point_v = reader.point()?;
+        let signature = reader.scalar()?;
+        Self::new_verified(params, point_e, point_v, signature)
+            .ok_or(DeserializationError::InvalidCapsule)
+    }
+
+    /// Deserializes a capsule without verifying it (see [`verify()`](`Self::verify()`)),
+    /// which [`from_bytes()`](`SerializableToArray::from_bytes()`) does.
+    ///
+    /// The points are still decoded, and therefore checked to be on the curve
+    /// (the compressed form requires it), so only the verification is skipped,
+    /// which saves two point multiplications.
+    ///
+    /// **Warning:** only use this for bytes produced by
+    /// [`to_array()`](`SerializableToArray::to_array()`) of a valid capsule
+    /// that never left a trusted environment (e.g. a local cache).
+    /// An unverified capsule can be malformed in a way that lets an attacker
+    /// learn something from its reencryption or decryption.
+    /// Key and capsule fragments do not have an equivalent method,
+    /// since their deserialization only decodes the fields.
+    pub fn from_bytes_unchecked(bytes: impl AsRef<[u8]>) -> Option<Self> {
+        let bytes = bytes.as_ref();
+        if bytes.len() != CapsuleSize::to_usize() {
+            return None;
+        }
+        let arr = GenericArray::<u8, CapsuleSize>::from_slice(bytes);
+        let (params, rest) = Parameters::take(*arr)?;
+        let (point_e, rest) = CurvePoint::take(rest)?;
+        let (point_v, rest) = CurvePoint::take(rest)?;
+        let signature = CurveScalar::take_last(rest)?;
+        Some(Self {
+            params,
+            point_e,
+            point_v,
+            signature,
+        })
+    }
+
+    /// Returns the SHA3-256 digest of the serialized capsule.
+    ///
+    /// It can be stored along with the ciphertext, so that the decrypting party
+    /// can cheaply detect a wrong capsule before opening it
+    /// (see [`decrypt_reencrypted_with_capsule_check()`](`crate::decrypt_reencrypted_with_capsule_check()`)).
+    pub fn digest(&self) -> GenericArray<u8, U32> {
+        Sha3_256::digest(&self.to_array())
+    }
+
+    /// Returns the parameters the capsule was created with.
+    pub fn params(&self) -> Parameters {
+        self.params
+    }
+
+    /// Returns `true` if the capsule was created with the given parameters.
+    ///
+    /// Capsules created with different [`Parameters`] cannot be reencrypted
+    /// with key fragments from another parameter set (the proofs will not verify).
+    /// Keys are independent of the parameters and can be used with any set.
+    pub fn params_match(&self, params: &Parameters) -> bool {
+        &self.params == params
+    }
+
     /// Verifies the integrity of the capsule.
-    fn verify(&self) -> bool {
+    ///
+    /// Capsules created by encryption or deserialized with
+    /// [`from_bytes()`](`SerializableToArray::from_bytes()`) are already verified;
+    /// this is the check to run on capsules obtained with
+    /// [`from_bytes_unchecked()`](`Self::from_bytes_unchecked()`).
+    #[must_use = "the verification result must be checked"]
+    pub fn verify(&self) -> bool {
         let g = CurvePoint::generator();
         let h = ScalarDigest::new()
             .chain_point(&self.point_e)
@@ -74,21 +183,82 @@ impl Capsule {
         &g * &self.signature == &self.point_v + &(&self.point_e * &h)
     }
 
+    /// Checks that `cfrags` can be used to open the capsule,
+    /// given the `threshold` the key fragments were created with:
+    /// there are enough of them, they come from the same set of key fragments,
+    /// and their key fragment IDs are distinct.
+    ///
+    /// This is much cheaper than the actual decryption, but does not verify the fragments,
+    /// so the decryption can still fail (see [`CapsuleFrag::verify()`]).
+    pub fn can_open(
+        &self,
+        cfrags: &[CapsuleFrag],
+        threshold: usize,
+    ) -> Result<(), OpenReencryptedError> {
+        Self::can_open_shares(cfrags, threshold)
+    }
+
+    fn can_open_shares<F: ReencryptedShare>(
+        cfrags: &[F],
+        threshold: usize,
+    ) -> Result<(), OpenReencryptedError> {
+        if cfrags.is_empty() || cfrags.len() < threshold {
+            return Err(OpenReencryptedError::NotEnoughCfrags);
+        }
+
+        let precursor = cfrags[0].precursor();
+        if !cfrags.iter().all(|cfrag| cfrag.precursor() == precursor) {
+            return Err(OpenReencryptedError::MismatchedPrecursors);
+        }
+
+        for (i, cfrag) in cfrags.iter().enumerate() {
+            if cfrags[..i]
+                .iter()
+                .any(|other| other.kfrag_id() == cfrag.kfrag_id())
+            {
+                return Err(OpenReencryptedError::RepeatingKfragIds);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Lazily verifies the capsule fragments coming from `cfrags`
+    /// (see [`CapsuleFrag::verify()`]), yielding only the valid ones.
+    ///
+    /// Combined with `.take(threshold)`, this stops verifying
+    /// as soon as enough valid fragments are collected.
+    pub fn verify_filter<'a, I>(
+        &'a self,
+        delegating_pk: &'a PublicKey,
+        receiving_pk: &'a PublicKey,
+        signing_pk: &'a PublicKey,
+        cfrags: I,
+    ) -> impl Iterator<Item = CapsuleFrag> + 'a
+    where
+        I: IntoIterator<Item = CapsuleFrag>,
+        I::IntoIter: 'a,
+    {
+        cfrags
+            .into_iter()
+            .filter(move |cfrag| cfrag.verify(self, delegating_pk, receiving_pk, signing_pk))
+    }
+
     /// Generates a symmetric key and its associated KEM ciphertext
     pub(crate) fn from_pubkey(params: &Parameters, pk: &PublicKey) -> (Capsule, CurvePoint) {
         let g = CurvePoint::generator();
 
-        let priv_r = CurveScalar::random_nonzero();
+        let priv_r = Zeroizing::new(CurveScalar::random_nonzero());
         let pub_r = &g * &priv_r;
 
-        let priv_u = CurveScalar::random_nonzero();
+        let priv_u = Zeroizing::new(CurveScalar::random_nonzero());
         let pub_u = &g * &priv_u;
 
         let h = ScalarDigest::new().chain_points(&[pub_r, pub_u]).finalize();
 
-        let s = &priv_u + &(&priv_r * &h);
+        let s = &*priv_u + &(&*priv_r * &h);
 
-        let shared_key = &pk.to_point() * &(&priv_r + &priv_u);
+        let shared_key = &pk.to_point() * &Zeroizing::new(&*priv_r + &*priv_u);
 
         let capsule = Self {
             params: *params,
@@ -105,53 +275,110 @@ impl Capsule {
         &(&self.point_e + &self.point_v) * &private_key.to_secret_scalar()
     }
 
-    #[allow(clippy::many_single_char_names)]
     pub(crate) fn open_reencrypted(
         &self,
         receiving_sk: &SecretKey,
         delegating_pk: &PublicKey,
         cfrags: &[CapsuleFrag],
     ) -> Option<CurvePoint> {
-        if cfrags.is_empty() {
+        let opening = self.open_shares(receiving_sk, delegating_pk, cfrags)?;
+        if !opening.is_valid {
             return None;
         }
+        Some(opening.shared_key)
+    }
 
-        let precursor = cfrags[0].precursor;
-
-        if !cfrags.iter().all(|cfrag| cfrag.precursor == precursor) {
+    pub(crate) fn open_reencrypted_slim(
+        &self,
+        receiving_sk: &SecretKey,
+        delegating_pk: &PublicKey,
+        cfrags: &[SlimCapsuleFrag],
+    ) -> Option<CurvePoint> {
+        let opening = self.open_shares(receiving_sk, delegating_pk, cfrags)?;
+        if !opening.is_valid {
             return None;
         }
+        Some(opening.shared_key)
+    }
+
+    /// Performs the reconstruction of the symmetric key from capsule fragments
+    /// (as [`decrypt_reencrypted()`](`crate::decrypt_reencrypted()`) does),
+    /// returning the intermediate values for inspection.
+    ///
+    /// Unlike the decryption functions, a failed correctness check is not an error here:
+    /// it is reported in [`ReencryptedOpening::is_valid`], along with the values it was performed on.
+    /// Returns `None` if the reconstruction itself cannot be performed
+    /// (see [`can_open()`](`Self::can_open()`)).
+    ///
+    /// **Warning:** the result contains the shared key the plaintext is encrypted with.
+    /// This method is intended for debugging only,
+    /// and is only available with the `debug-reconstruction` feature.
+    #[cfg(feature = "debug-reconstruction")]
+    #[must_use]
+    pub fn open_reencrypted_debug(
+        &self,
+        receiving_sk: &SecretKey,
+        delegating_pk: &PublicKey,
+        cfrags: &[CapsuleFrag],
+    ) -> Option<ReencryptedOpening> {
+        let opening = self.open_shares(receiving_sk, delegating_pk, cfrags)?;
+        Some(ReencryptedOpening {
+            e_prime: opening.e_prime,
+            v_prime: opening.v_prime,
+            d: opening.d,
+            shared_key: opening.shared_key,
+            is_valid: opening.is_valid,
+        })
+    }
+
+    #[allow(clippy::many_single_char_names)]
+    fn open_shares<F: ReencryptedShare>(
+        &self,
+        receiving_sk: &SecretKey,
+        delegating_pk: &PublicKey,
+        cfrags: &[F],
+    ) -> Option<SharesOpening> {
+        Self::can_open_shares(cfrags, 1).ok()?;
+
+        let precursor = cfrags[0].precursor();
 
-        let pub_key = PublicKey::from_secret_key(receiving_sk).to_point();
-        let dh_point = &precursor * &receiving_sk.to_secret_scalar();
+        let pub_key = receiving_sk.public_key().to_point();
+        let dh_point = Zeroizing::new(&precursor * &receiving_sk.to_secret_scalar());
 
         // Combination of CFrags via Shamir's Secret Sharing reconstruction
-        let points = [precursor, pub_key, dh_point];
+        let points = Zeroizing::new([precursor, pub_key, *dh_point]);
         let mut lc = Vec::<CurveScalar>::with_capacity(cfrags.len());
         for cfrag in cfrags {
             let coeff = ScalarDigest::new()
-                .chain_points(&points)
+                .chain_points(&points[..])
                 .chain_bytes(X_COORDINATE)
-                .chain_scalar(&cfrag.kfrag_id)
+                .chain_scalar(&cfrag.kfrag_id())
                 .finalize();
             lc.push(coeff);
         }
 
-        let mut e_prime = CurvePoint::identity();
-        let mut v_prime = CurvePoint::identity();
-        for (i, cfrag) in (&cfrags).iter().enumerate() {
-            // There is a minuscule probability that two elements of `lc` are equal,
-            // in which case we'd rather fail gracefully.
-            let lambda_i = lambda_coeff(&lc, i)?;
-            e_prime = &e_prime + &(&cfrag.point_e1 * &lambda_i);
-            v_prime = &v_prime + &(&cfrag.point_v1 * &lambda_i);
-        }
+        // There is a minuscule probability that two elements of `lc` are equal,
+        // in which case we'd rather fail gracefully.
+        let lambdas = lambda_coeffs(&lc)?;
+
+        let e_prime: CurvePoint = cfrags
+            .iter()
+            .zip(lambdas.iter())
+            .map(|(cfrag, lambda_i)| &cfrag.point_e1() * lambda_i)
+            .sum();
+        let v_prime: CurvePoint = cfrags
+            .iter()
+            .zip(lambdas.iter())
+            .map(|(cfrag, lambda_i)| &cfrag.point_v1() * lambda_i)
+            .sum();
 
         // Secret value 'd' allows to make Umbral non-interactive
-        let d = ScalarDigest::new()
-            .chain_points(&[precursor, pub_key, dh_point])
-            .chain_bytes(NON_INTERACTIVE)
-            .finalize();
+        let d = Zeroizing::new(
+            ScalarDigest::new()
+                .chain_points(&points[..])
+                .chain_bytes(NON_INTERACTIVE)
+                .finalize(),
+        );
 
         let e = self.point_e;
         let v = self.point_v;
@@ -168,16 +395,82 @@ impl Capsule {
         // we'd rather fail gracefully than panic.
         let inv_d = inv_d_opt?;
 
-        if &orig_pub_key * &(&s * &inv_d) != &(&e_prime * &h) + &v_prime {
-            return None;
-        }
+        let is_valid = &orig_pub_key * &(&s * &inv_d) == &(&e_prime * &h) + &v_prime;
 
         let shared_key = &(&e_prime + &v_prime) * &d;
-        Some(shared_key)
+        Some(SharesOpening {
+            e_prime,
+            v_prime,
+            d: *d,
+            shared_key,
+            is_valid,
+        })
+    }
+}
+
+/// The result of the symmetric key reconstruction from capsule fragments.
+struct SharesOpening {
+    e_prime: CurvePoint,
+    v_prime: CurvePoint,
+    d: CurveScalar,
+    shared_key: CurvePoint,
+    is_valid: bool,
+}
+
+impl Drop for SharesOpening {
+    fn drop(&mut self) {
+        self.d.zeroize();
+        self.shared_key.zeroize();
+    }
+}
+
+/// Intermediate values of the symmetric key reconstruction,
+/// returned by [`Capsule::open_reencrypted_debug()`].
+///
+/// `d` and `shared_key` are secret: they are omitted from the `Debug` output,
+/// and zeroized when the object is dropped.
+#[cfg(feature = "debug-reconstruction")]
+pub struct ReencryptedOpening {
+    /// The reconstructed point `E'` (the combination of the `E1` points of the capsule fragments).
+    pub e_prime: CurvePoint,
+    /// The reconstructed point `V'` (the combination of the `V1` points of the capsule fragments).
+    pub v_prime: CurvePoint,
+    /// The non-interactive secret `d` derived from the precursor and the receiving key.
+    pub d: CurveScalar,
+    /// The shared key, `(E' + V') * d`.
+    pub shared_key: CurvePoint,
+    /// Whether the correctness check `delegating_pk * (s / d) == E' * h + V'` passed
+    /// (`s` being the capsule signature, and `h` the hash of its points).
+    pub is_valid: bool,
+}
+
+#[cfg(feature = "debug-reconstruction")]
+impl fmt::Debug for ReencryptedOpening {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReencryptedOpening")
+            .field("e_prime", &self.e_prime)
+            .field("v_prime", &self.v_prime)
+            .field("d", &format_args!("..."))
+            .field("shared_key", &format_args!("..."))
+            .field("is_valid", &self.is_valid)
+            .finish()
     }
 }
 
-fn lambda_coeff(xs: &[CurveScalar], i: usize) -> Option<CurveScalar> {
+#[cfg(feature = "debug-reconstruction")]
+impl Drop for ReencryptedOpening {
+    fn drop(&mut self) {
+        self.d.zeroize();
+        self.shared_key.zeroize();
+    }
+}
+
+/// Returns the Lagrange basis coefficient for `xs[i]` evaluated at zero,
+/// or `None` if `xs` contains repeating elements.
+///
+/// Performs `xs.len() - 1` scalar inversions;
+/// use [`lambda_coeffs()`] to calculate the coefficients for all the elements.
+pub fn lambda_coeff(xs: &[CurveScalar], i: usize) -> Option<CurveScalar> {
     let mut res = CurveScalar::one();
     for j in 0..xs.len() {
         if j != i {
@@ -189,17 +482,123 @@ fn lambda_coeff(xs: &[CurveScalar], i: usize) -> Option<CurveScalar> {
     Some(res)
 }
 
+/// Returns the Lagrange basis coefficients for all the elements of `xs` evaluated at zero
+/// (equal to calling [`lambda_coeff()`] for each of them),
+/// or `None` if `xs` contains repeating elements.
+///
+/// The numerators are calculated with prefix and suffix products,
+/// and the denominators are inverted in a batch (Montgomery's trick),
+/// so only one scalar inversion is performed.
+pub fn lambda_coeffs(xs: &[CurveScalar]) -> Option<Vec<CurveScalar>> {
+    let n = xs.len();
+
+    // Denominators: prod_{j != i} (x_j - x_i)
+    let mut denominators = Vec::<CurveScalar>::with_capacity(n);
+    for i in 0..n {
+        let mut denominator = CurveScalar::one();
+        for j in 0..n {
+            if j != i {
+                denominator = &denominator * &(&xs[j] - &xs[i]);
+            }
+        }
+        denominators.push(denominator);
+    }
+
+    // Batch inversion: one of the denominators is zero
+    // (and therefore their product is) if and only if `xs` contains repeating elements.
+    let mut partial_products = Vec::<CurveScalar>::with_capacity(n);
+    let mut product = CurveScalar::one();
+    for denominator in denominators.iter() {
+        partial_products.push(product);
+        product = &product * denominator;
+    }
+    let inv_product_opt: Option<CurveScalar> = product.invert().into();
+    let mut inv_product = inv_product_opt?;
+    let mut inv_denominators = Vec::<CurveScalar>::with_capacity(n);
+    for (denominator, partial_product) in denominators.iter().zip(partial_products).rev() {
+        inv_denominators.push(&inv_product * &partial_product);
+        inv_product = &inv_product * denominator;
+    }
+    inv_denominators.reverse();
+
+    // Numerators: prod_{j != i} x_j, as a product of a prefix and a suffix
+    let mut suffix_products = Vec::<CurveScalar>::with_capacity(n);
+    let mut suffix_product = CurveScalar::one();
+    for x in xs.iter().rev() {
+        suffix_products.push(suffix_product);
+        suffix_product = &suffix_product * x;
+    }
+    suffix_products.reverse();
+
+    let mut prefix_product = CurveScalar::one();
+    let mut res = Vec::<CurveScalar>::with_capacity(n);
+    for i in 0..n {
+        let numerator = &prefix_product * &suffix_products[i];
+        res.push(&numerator * &inv_denominators[i]);
+        prefix_product = &prefix_product * &xs[i];
+    }
+    Some(res)
+}
+
 #[cfg(test)]
 mod tests {
 
+    #[cfg(feature = "debug-reconstruction")]
+    use alloc::format;
     use alloc::vec::Vec;
+    #[cfg(not(umbral_insecure_no_proofs))]
+    use core::cell::Cell;
 
-    use super::Capsule;
+    use typenum::Unsigned;
+
+    use super::{lambda_coeff, lambda_coeffs, Capsule, OpenReencryptedError, CAPSULE_SIZE};
+    use crate::curve::{CurvePoint, CurveScalar, PointError};
+    use crate::traits::{DeserializationError, SerializableToFixedBytes};
     use crate::{
         encrypt, generate_kfrags, reencrypt, CapsuleFrag, Parameters, PublicKey, SecretKey,
         SerializableToArray,
     };
 
+    #[test]
+    fn test_lambda_coeffs() {
+        for n in [0usize, 1, 2, 3, 8, 17].iter() {
+            let xs: Vec<CurveScalar> = (0..*n).map(|_| CurveScalar::random_nonzero()).collect();
+            let naive = (0..*n)
+                .map(|i| lambda_coeff(&xs, i))
+                .collect::<Option<Vec<_>>>()
+                .unwrap();
+            assert_eq!(lambda_coeffs(&xs).unwrap(), naive);
+        }
+
+        // Repeating elements
+        let mut xs: Vec<CurveScalar> = (0..4).map(|_| CurveScalar::random_nonzero()).collect();
+        xs[3] = xs[1];
+        assert!(lambda_coeff(&xs, 1).is_none());
+        assert!(lambda_coeffs(&xs).is_none());
+    }
+
+    #[test]
+    fn test_params_match() {
+        let params = Parameters::new();
+
+        // A different (but valid) parameter set
+        let other_u = &params.u() * &CurveScalar::random_nonzero();
+        let other_params = Parameters::from_bytes(other_u.to_array()).unwrap();
+        assert_ne!(params, other_params);
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        assert!(capsule.params_match(&params));
+        assert!(!capsule.params_match(&other_params));
+
+        let (capsule, _ciphertext) =
+            encrypt(&other_params, &delegating_pk, b"peace at dawn").unwrap();
+        assert!(capsule.params_match(&other_params));
+        assert!(!capsule.params_match(&params));
+    }
+
     #[test]
     fn test_serialize() {
         let params = Parameters::new();
@@ -213,6 +612,286 @@ mod tests {
         let capsule_arr = capsule.to_array();
         let capsule_back = Capsule::from_array(&capsule_arr).unwrap();
         assert_eq!(capsule, capsule_back);
+
+        let capsule_bytes: [u8; CAPSULE_SIZE] = capsule.to_fixed_bytes();
+        assert_eq!(&capsule_bytes[..], capsule_arr.as_slice());
+        assert_eq!(Capsule::from_fixed_bytes(&capsule_bytes).unwrap(), capsule);
+    }
+
+    #[test]
+    fn test_verify() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        assert!(capsule.verify());
+
+        let tampered = Capsule {
+            point_e: capsule.point_v,
+            point_v: capsule.point_e,
+            ..capsule
+        };
+        assert!(!tampered.verify());
+    }
+
+    #[test]
+    fn test_from_bytes_unchecked() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let capsule_arr = capsule.to_array();
+        assert_eq!(
+            Capsule::from_bytes_unchecked(capsule_arr),
+            Capsule::from_bytes(capsule_arr)
+        );
+        assert_eq!(Capsule::from_bytes_unchecked(capsule_arr), Some(capsule));
+
+        // An invalid capsule is not detected
+        let tampered = Capsule {
+            point_e: capsule.point_v,
+            point_v: capsule.point_e,
+            ..capsule
+        };
+        let tampered_arr = tampered.to_array();
+        assert!(Capsule::from_bytes(tampered_arr).is_none());
+        let tampered_back = Capsule::from_bytes_unchecked(tampered_arr).unwrap();
+        assert!(!tampered_back.verify());
+
+        // But the length and the encoding are still checked
+        assert!(Capsule::from_bytes_unchecked(&capsule_arr[1..]).is_none());
+        let mut bad_point = capsule_arr;
+        let offset = <Parameters as SerializableToArray>::Size::to_usize();
+        bad_point[offset] = 5;
+        assert!(Capsule::from_bytes_unchecked(bad_point).is_none());
+    }
+
+    #[cfg(not(umbral_insecure_no_proofs))]
+    #[test]
+    fn test_verify_filter() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let signing_pk = PublicKey::from_secret_key(&signing_sk);
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let (other_capsule, _ciphertext) =
+            encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            4,
+            true,
+            true,
+        );
+
+        // Fragments of another capsule are interleaved with the valid ones
+        let cfrags: Vec<CapsuleFrag> = kfrags
+            .iter()
+            .flat_map(|kfrag| {
+                Vec::from([
+                    reencrypt(&other_capsule, kfrag, None),
+                    reencrypt(&capsule, kfrag, None),
+                ])
+            })
+            .collect();
+
+        let valid: Vec<CapsuleFrag> = capsule
+            .verify_filter(
+                &delegating_pk,
+                &receiving_pk,
+                &signing_pk,
+                cfrags.iter().cloned(),
+            )
+            .collect();
+        assert_eq!(valid.len(), kfrags.len());
+        assert!(valid
+            .iter()
+            .zip(cfrags.iter().skip(1).step_by(2))
+            .all(|(x, y)| x == y));
+
+        // Stops consuming the input once enough valid fragments are found
+        let consumed = Cell::new(0);
+        let first_two: Vec<CapsuleFrag> = capsule
+            .verify_filter(
+                &delegating_pk,
+                &receiving_pk,
+                &signing_pk,
+                cfrags
+                    .iter()
+                    .cloned()
+                    .inspect(|_| consumed.set(consumed.get() + 1)),
+            )
+            .take(2)
+            .collect();
+        assert_eq!(first_two, &valid[..2]);
+        assert_eq!(consumed.get(), 4);
+    }
+
+    #[test]
+    fn test_can_open() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let make_cfrags = || {
+            let kfrags = generate_kfrags(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                2,
+                3,
+                true,
+                true,
+            );
+            kfrags
+                .iter()
+                .map(|kfrag| reencrypt(&capsule, kfrag, None))
+                .collect::<Vec<CapsuleFrag>>()
+        };
+        let cfrags = make_cfrags();
+
+        assert_eq!(capsule.can_open(&cfrags[..2], 2), Ok(()));
+        assert!(capsule
+            .open_reencrypted(&receiving_sk, &delegating_pk, &cfrags[..2])
+            .is_some());
+
+        // Insufficient count
+        assert_eq!(
+            capsule.can_open(&cfrags[..1], 2),
+            Err(OpenReencryptedError::NotEnoughCfrags)
+        );
+        assert_eq!(
+            capsule.can_open(&[], 0),
+            Err(OpenReencryptedError::NotEnoughCfrags)
+        );
+
+        // Fragments from a different set of kfrags
+        let other_cfrags = make_cfrags();
+        let mixed = [cfrags[0].clone(), other_cfrags[1].clone()];
+        assert_eq!(
+            capsule.can_open(&mixed, 2),
+            Err(OpenReencryptedError::MismatchedPrecursors)
+        );
+        assert!(capsule
+            .open_reencrypted(&receiving_sk, &delegating_pk, &mixed)
+            .is_none());
+
+        // Two fragments from the same kfrag
+        let duplicate = [cfrags[0].clone(), cfrags[1].clone(), cfrags[0].clone()];
+        assert_eq!(
+            capsule.can_open(&duplicate, 2),
+            Err(OpenReencryptedError::RepeatingKfragIds)
+        );
+        assert!(capsule
+            .open_reencrypted(&receiving_sk, &delegating_pk, &duplicate)
+            .is_none());
+    }
+
+    #[test]
+    fn test_deserialize_flipped_signature() {
+        let params = Parameters::new();
+        let pk = PublicKey::from_secret_key(&SecretKey::random());
+        let (capsule, _ciphertext) = encrypt(&params, &pk, b"peace at dawn").unwrap();
+        let capsule_arr = capsule.to_array();
+
+        // Any flipped bit of the signature makes the capsule inconsistent,
+        // so it is rejected on deserialization, and not only on decryption.
+        let scalar_start = capsule_arr.len() - 32;
+        for bit in 0..256 {
+            let mut flipped = capsule_arr;
+            flipped[scalar_start + bit / 8] ^= 1 << (bit % 8);
+            assert!(Capsule::from_bytes(flipped).is_none());
+
+            // Flipping a high bit can make the scalar exceed the curve order,
+            // in which case it cannot be decoded even without the verification.
+            if let Some(unchecked) = Capsule::from_bytes_unchecked(flipped) {
+                assert!(!unchecked.verify());
+            }
+        }
+    }
+
+    #[test]
+    fn test_deserialize_malformed() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let capsule_arr = capsule.to_array();
+
+        // Wrong length
+        assert!(Capsule::from_bytes(&capsule_arr[..capsule_arr.len() - 1]).is_none());
+        assert!(Capsule::from_bytes(b"").is_none());
+        assert_eq!(
+            Capsule::try_from_bytes(&capsule_arr[..capsule_arr.len() - 1]),
+            Err(DeserializationError::WrongLength)
+        );
+
+        // `point_e` has an invalid SEC1 prefix (it starts right after the parameters)
+        let point_size = params.to_array().len();
+        let mut bad_point = capsule_arr;
+        bad_point[point_size] = 0x05;
+        assert!(Capsule::from_array(&bad_point).is_none());
+        assert_eq!(
+            Capsule::try_from_bytes(bad_point),
+            Err(DeserializationError::InvalidPoint(
+                PointError::MalformedEncoding
+            ))
+        );
+
+        // `point_v` has an x coordinate that does not correspond to a point on the curve
+        let mut off_curve = [0u8; 33];
+        off_curve[0] = 2;
+        while CurvePoint::try_from_bytes(off_curve).is_ok() {
+            off_curve[32] += 1;
+        }
+        let mut bad_point = capsule_arr;
+        bad_point[point_size * 2..point_size * 3].copy_from_slice(&off_curve);
+        assert_eq!(
+            Capsule::try_from_bytes(bad_point),
+            Err(DeserializationError::InvalidPoint(PointError::NotOnCurve))
+        );
+
+        // The signature is not less than the curve order
+        let mut bad_scalar = capsule_arr;
+        let scalar_start = bad_scalar.len() - 32;
+        for byte in bad_scalar[scalar_start..].iter_mut() {
+            *byte = 0xff;
+        }
+        assert!(Capsule::from_array(&bad_scalar).is_none());
+        assert_eq!(
+            Capsule::try_from_bytes(bad_scalar),
+            Err(DeserializationError::InvalidScalar)
+        );
+
+        // All the fields are valid, but the capsule does not verify
+        let mut bad_signature = capsule_arr;
+        bad_signature[capsule_arr.len() - 1] ^= 1;
+        assert_eq!(
+            Capsule::try_from_bytes(bad_signature),
+            Err(DeserializationError::InvalidCapsule)
+        );
+
+        assert_eq!(Capsule::try_from_bytes(capsule_arr), Ok(capsule));
     }
 
     #[test]
@@ -287,4 +966,74 @@ mod tests {
             .open_reencrypted(&receiving_sk, &delegating_pk, &cfrags)
             .is_none());
     }
+
+    #[cfg(feature = "debug-reconstruction")]
+    #[test]
+    fn test_open_reencrypted_debug() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let (capsule, key_seed) = Capsule::from_pubkey(&params, &delegating_pk);
+
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+        let cfrags: Vec<CapsuleFrag> = kfrags
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, &kfrag, None))
+            .collect();
+
+        let opening = capsule
+            .open_reencrypted_debug(&receiving_sk, &delegating_pk, &cfrags[..2])
+            .unwrap();
+        assert!(opening.is_valid);
+        assert_eq!(opening.shared_key, key_seed);
+        assert_eq!(
+            opening.shared_key,
+            &(&opening.e_prime + &opening.v_prime) * &opening.d
+        );
+
+        // Any subset of `threshold` fragments reconstructs the same `E'` and `V'`
+        // (`E' = E * rk`, where `rk = d^-1 * delegating_sk`)
+        let opening2 = capsule
+            .open_reencrypted_debug(&receiving_sk, &delegating_pk, &cfrags[1..])
+            .unwrap();
+        assert_eq!(opening.e_prime, opening2.e_prime);
+        assert_eq!(opening.v_prime, opening2.v_prime);
+        assert_eq!(opening.shared_key, opening2.shared_key);
+
+        // The secret values are not printed
+        let debug = format!("{:?}", opening);
+        assert!(debug.contains("d: ..."));
+        assert!(debug.contains("shared_key: ..."));
+        let rk = &delegating_sk.to_secret_scalar() * &opening.d.invert().unwrap();
+        assert_eq!(opening.e_prime, &capsule.point_e * &rk);
+        assert_eq!(opening.v_prime, &capsule.point_v * &rk);
+
+        // Too few fragments: the reconstruction goes through, but the check fails
+        let opening = capsule
+            .open_reencrypted_debug(&receiving_sk, &delegating_pk, &cfrags[..1])
+            .unwrap();
+        assert!(!opening.is_valid);
+        assert_ne!(opening.shared_key, key_seed);
+        assert!(capsule
+            .open_reencrypted(&receiving_sk, &delegating_pk, &cfrags[..1])
+            .is_none());
+
+        assert!(capsule
+            .open_reencrypted_debug(&receiving_sk, &delegating_pk, &[])
+            .is_none());
+    }
 }
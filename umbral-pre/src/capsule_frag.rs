@@ -1,23 +1,41 @@
 use crate::capsule::Capsule;
 use crate::curve::{CurvePoint, CurveScalar};
 use crate::curve::{PublicKey, Signature};
+use crate::domain::METADATA_COMMITMENT;
 use crate::hashing::{ScalarDigest, SignatureDigest};
 use crate::key_frag::KeyFrag;
-use crate::traits::SerializableToArray;
+use crate::params::Parameters;
+use crate::traits::{
+    DeserializationError, FieldReader, SerializableToArray, SerializableToFixedBytes,
+};
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::iter::FromIterator;
 
 use generic_array::sequence::Concat;
 use generic_array::GenericArray;
-use typenum::op;
+use rand_core::{CryptoRng, OsRng, RngCore};
+use typenum::{op, Unsigned};
+#[cfg(not(umbral_insecure_no_proofs))]
+use zeroize::Zeroizing;
 
-#[derive(Clone, Debug, PartialEq)]
+/// The proof of correctness of a reencryption, included in a [`CapsuleFrag`].
+///
+/// All its components are fixed-size, so it is `Copy`;
+/// it is reconstructed from its serialized components by
+/// [`from_array()`](`SerializableToArray::from_array()`).
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CapsuleFragProof {
-    point_e2: CurvePoint,
-    point_v2: CurvePoint,
-    kfrag_commitment: CurvePoint,
-    kfrag_pok: CurvePoint,
-    signature: CurveScalar,
-    kfrag_signature: Signature,
-    metadata: CurveScalar,
+    pub(crate) point_e2: CurvePoint,
+    pub(crate) point_v2: CurvePoint,
+    pub(crate) kfrag_commitment: CurvePoint,
+    pub(crate) kfrag_pok: CurvePoint,
+    pub(crate) signature: CurveScalar,
+    pub(crate) kfrag_signature: Signature,
+    pub(crate) metadata: CurveScalar,
 }
 
 type PointSize = <CurvePoint as SerializableToArray>::Size;
@@ -41,28 +59,28 @@ impl SerializableToArray for CapsuleFragProof {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        let (point_e2, rest) = CurvePoint::take(*arr)?;
-        let (point_v2, rest) = CurvePoint::take(rest)?;
-        let (kfrag_commitment, rest) = CurvePoint::take(rest)?;
-        let (kfrag_pok, rest) = CurvePoint::take(rest)?;
-        let (signature, rest) = CurveScalar::take(rest)?;
-        let (kfrag_signature, rest) = Signature::take(rest)?;
-        let metadata = CurveScalar::take_last(rest)?;
-        Some(Self {
-            point_e2,
-            point_v2,
-            kfrag_commitment,
-            kfrag_pok,
-            signature,
-            kfrag_signature,
-            metadata,
-        })
+        let mut reader = FieldReader::new::<Self>(arr).ok()?;
+        Self::read(&mut reader).ok()
     }
 }
 
 impl CapsuleFragProof {
+    fn read(reader: &mut FieldReader<'_>) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            point_e2: reader.point()?,
+            point_v2: reader.point()?,
+            kfrag_commitment: reader.point()?,
+            kfrag_pok: reader.point()?,
+            signature: reader.scalar()?,
+            kfrag_signature: reader.signature()?,
+            metadata: reader.scalar()?,
+        })
+    }
+
+    #[cfg(not(umbral_insecure_no_proofs))]
     #[allow(clippy::many_single_char_names)]
     fn from_kfrag_and_cfrag(
+        rng: &mut (impl CryptoRng + RngCore),
         capsule: &Capsule,
         kfrag: &KeyFrag,
         cfrag_e1: &CurvePoint,
@@ -72,7 +90,7 @@ impl CapsuleFragProof {
         let params = capsule.params;
 
         let rk = kfrag.key;
-        let t = CurveScalar::random_nonzero();
+        let t = Zeroizing::new(CurveScalar::random_nonzero_with_rng(rng));
 
         // Here are the formulaic constituents shared with `CapsuleFrag::verify()`.
 
@@ -96,7 +114,7 @@ impl CapsuleFragProof {
 
         ////////
 
-        let z3 = &t + &(&rk * &h);
+        let z3 = &*t + &(&rk * &h);
 
         Self {
             point_e2: e2,
@@ -108,9 +126,35 @@ impl CapsuleFragProof {
             metadata: *metadata,
         }
     }
+
+    /// A proof with the correct format that does not prove anything,
+    /// used instead of the real one
+    /// when the crate is built with `--cfg umbral_insecure_no_proofs`.
+    #[cfg(umbral_insecure_no_proofs)]
+    fn insecure_placeholder(kfrag: &KeyFrag, metadata: &CurveScalar) -> Self {
+        let g = CurvePoint::generator();
+        Self {
+            point_e2: g,
+            point_v2: g,
+            kfrag_commitment: kfrag.proof.commitment,
+            kfrag_pok: g,
+            signature: CurveScalar::default(),
+            kfrag_signature: kfrag.proof.signature_for_bob(),
+            metadata: *metadata,
+        }
+    }
 }
 
 /// A reencrypted fragment of a [`Capsule`] created by a proxy.
+///
+/// Fragments are ordered by their key fragment ID (see [`kfrag_id()`](`Self::kfrag_id()`)),
+/// so that a set of them can be put in a canonical order.
+/// The ordering has no cryptographic meaning.
+///
+/// Deserialization checks that every embedded point (`e1`, `v1`, the precursor,
+/// and the points of the correctness proof) is a valid curve point,
+/// that every scalar is canonical, and that the key fragment signature has valid components.
+/// It does not check the correctness proof, use [`verify()`](`Self::verify()`) for that.
 #[derive(Clone, Debug, PartialEq)]
 pub struct CapsuleFrag {
     pub(crate) point_e1: CurvePoint,
@@ -135,44 +179,168 @@ impl SerializableToArray for CapsuleFrag {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        let (point_e1, rest) = CurvePoint::take(*arr)?;
-        let (point_v1, rest) = CurvePoint::take(rest)?;
-        let (kfrag_id, rest) = CurveScalar::take(rest)?;
-        let (precursor, rest) = CurvePoint::take(rest)?;
-        let proof = CapsuleFragProof::take_last(rest)?;
-        Some(Self {
-            point_e1,
-            point_v1,
-            kfrag_id,
-            precursor,
-            proof,
-        })
+        Self::try_from_bytes(arr).ok()
+    }
+}
+
+/// The size of a serialized [`CapsuleFrag`] in bytes.
+pub const CAPSULE_FRAG_SIZE: usize = 391;
+
+impl SerializableToFixedBytes<CAPSULE_FRAG_SIZE> for CapsuleFrag {}
+const _: [(); CAPSULE_FRAG_SIZE] =
+    [(); <<CapsuleFrag as SerializableToArray>::Size as Unsigned>::USIZE];
+
+// Ties are broken by the serialized contents, to stay consistent with `Eq`.
+impl Eq for CapsuleFrag {}
+
+impl PartialOrd for CapsuleFrag {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CapsuleFrag {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.kfrag_id
+            .to_array()
+            .cmp(&other.kfrag_id.to_array())
+            .then_with(|| self.to_array().cmp(&other.to_array()))
     }
 }
 
 impl CapsuleFrag {
+    /// Deserializes a capsule fragment, reporting the reason of a failure.
+    ///
+    /// [`SerializableToArray::from_bytes()`] is equivalent, but discards the error.
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, DeserializationError> {
+        let mut reader = FieldReader::new::<Self>(bytes.as_ref())?;
+        Ok(Self {
+            point_e1: reader.point()?,
+            point_v1: reader.point()?,
+            kfrag_id: reader.scalar()?,
+            precursor: reader.point()?,
+            proof: CapsuleFragProof::read(&mut reader)?,
+        })
+    }
+
     pub(crate) fn reencrypted(capsule: &Capsule, kfrag: &KeyFrag, metadata: Option<&[u8]>) -> Self {
-        let rk = kfrag.key;
-        let e1 = &capsule.point_e * &rk;
-        let v1 = &capsule.point_v * &rk;
+        Self::reencrypted_with_rng(&mut OsRng, capsule, kfrag, metadata)
+    }
+
+    pub(crate) fn reencrypted_with_rng(
+        rng: &mut (impl CryptoRng + RngCore),
+        capsule: &Capsule,
+        kfrag: &KeyFrag,
+        metadata: Option<&[u8]>,
+    ) -> Self {
         let metadata_scalar = match metadata {
-            Some(s) => ScalarDigest::new().chain_bytes(s).finalize(),
+            Some(s) => metadata_to_scalar(s),
             None => CurveScalar::default(),
         };
+        Self::reencrypted_with_metadata_scalar(rng, capsule, kfrag, &metadata_scalar)
+    }
+
+    pub(crate) fn reencrypted_with_metadata_commitment(
+        capsule: &Capsule,
+        kfrag: &KeyFrag,
+        metadata: &[u8],
+    ) -> (Self, MetadataOpening) {
+        let opening = MetadataOpening {
+            metadata: metadata.into(),
+            blinding: CurveScalar::random_nonzero(),
+        };
+        let metadata_scalar = opening.commitment_scalar(&capsule.params);
+        let cfrag =
+            Self::reencrypted_with_metadata_scalar(&mut OsRng, capsule, kfrag, &metadata_scalar);
+        (cfrag, opening)
+    }
+
+    fn reencrypted_with_metadata_scalar(
+        rng: &mut (impl CryptoRng + RngCore),
+        capsule: &Capsule,
+        kfrag: &KeyFrag,
+        metadata_scalar: &CurveScalar,
+    ) -> Self {
+        let rk = kfrag.key;
+        let e1 = &capsule.point_e * &rk;
+        let v1 = &capsule.point_v * &rk;
+        #[cfg(not(umbral_insecure_no_proofs))]
         let proof =
-            CapsuleFragProof::from_kfrag_and_cfrag(&capsule, &kfrag, &e1, &v1, &metadata_scalar);
+            CapsuleFragProof::from_kfrag_and_cfrag(rng, capsule, kfrag, &e1, &v1, metadata_scalar);
+        #[cfg(umbral_insecure_no_proofs)]
+        let proof = {
+            let _ = rng;
+            CapsuleFragProof::insecure_placeholder(kfrag, metadata_scalar)
+        };
 
-        Self {
+        let cfrag = Self {
             point_e1: e1,
             point_v1: v1,
             kfrag_id: kfrag.id,
             precursor: kfrag.precursor,
             proof,
-        }
+        };
+
+        #[cfg(all(debug_assertions, not(umbral_insecure_no_proofs)))]
+        cfrag.debug_self_check(capsule, kfrag);
+
+        cfrag
+    }
+
+    /// Checks in debug builds that the proof of a freshly created fragment verifies,
+    /// to catch regressions in its construction.
+    ///
+    /// A key fragment that was tampered with or created with different parameters
+    /// legitimately produces a fragment that fails verification,
+    /// so the check is skipped for those.
+    #[cfg(all(debug_assertions, not(umbral_insecure_no_proofs)))]
+    fn debug_self_check(&self, capsule: &Capsule, kfrag: &KeyFrag) {
+        let consistent_kfrag = capsule.params_match(&kfrag.params)
+            && kfrag.proof.commitment == &capsule.params.u * &kfrag.key;
+        debug_assert!(
+            !consistent_kfrag || self.to_slim().verify(capsule),
+            "a freshly created capsule fragment failed verification"
+        );
+    }
+
+    /// Returns the ID of the key fragment this capsule fragment was created with
+    /// (see [`KeyFrag::id()`]).
+    ///
+    /// Capsule fragments with the same ID cannot be combined in decryption,
+    /// so this can be used to deduplicate them.
+    pub fn kfrag_id(&self) -> CurveScalar {
+        self.kfrag_id
+    }
+
+    /// Returns `true` if both capsule fragments are reencryptions of the same capsule
+    /// with the same key fragment.
+    ///
+    /// Unlike `==`, ignores the correctness proofs,
+    /// which differ between reencryptions because of the randomness they use.
+    pub fn same_source(&self, other: &CapsuleFrag) -> bool {
+        self.point_e1 == other.point_e1
+            && self.point_v1 == other.point_v1
+            && self.kfrag_id == other.kfrag_id
+            && self.precursor == other.precursor
     }
 
     /// Verifies the integrity of the capsule fragment, given the original capsule,
     /// the encrypting party's key, the decrypting party's key, and the signing key.
+    ///
+    /// **Warning:** always returns `true`
+    /// if the crate is built with `--cfg umbral_insecure_no_proofs`.
+    ///
+    /// The result is marked `#[must_use]`, so discarding it is reported by the compiler:
+    ///
+    /// ```compile_fail
+    /// #![deny(unused_must_use)]
+    /// use umbral_pre::{Capsule, CapsuleFrag, PublicKey};
+    ///
+    /// fn check(cfrag: &CapsuleFrag, capsule: &Capsule, pk: &PublicKey) {
+    ///     cfrag.verify(capsule, pk, pk, pk);
+    /// }
+    /// ```
+    #[must_use = "the verification result must be checked"]
     pub fn verify(
         &self,
         capsule: &Capsule,
@@ -180,6 +348,270 @@ impl CapsuleFrag {
         receiving_pk: &PublicKey,
         signing_pk: &PublicKey,
     ) -> bool {
+        self.verify_with_context(capsule, delegating_pk, receiving_pk, signing_pk, None)
+    }
+
+    /// Same as [`verify()`](`Self::verify()`), for capsule fragments created from key fragments
+    /// made by [`generate_kfrags_with_context()`](`crate::generate_kfrags_with_context()`)
+    /// with the given `context`.
+    #[must_use = "the verification result must be checked"]
+    pub fn verify_with_context(
+        &self,
+        capsule: &Capsule,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+        signing_pk: &PublicKey,
+        context: Option<&[u8]>,
+    ) -> bool {
+        if cfg!(umbral_insecure_no_proofs) {
+            return true;
+        }
+
+        let valid_kfrag_signature = SignatureDigest::new()
+            .chain_scalar(&self.kfrag_id)
+            .chain_pubkey(delegating_pk)
+            .chain_pubkey(receiving_pk)
+            .chain_point(&self.proof.kfrag_commitment)
+            .chain_point(&self.precursor)
+            .chain_context(context)
+            .verify(signing_pk, &self.proof.kfrag_signature);
+
+        valid_kfrag_signature & self.to_slim().verify(capsule)
+    }
+
+    /// Same as [`verify()`](`Self::verify()`), but on success also returns
+    /// the commitment to the key fragment this capsule fragment was created with.
+    ///
+    /// The commitment equals the one of the originating [`KeyFrag`],
+    /// so it can be used to find out which key fragment a proxy used
+    /// when that is not communicated separately.
+    /// Note that with `--cfg umbral_insecure_no_proofs`
+    /// the commitment is returned without checking.
+    pub fn verify_and_extract(
+        &self,
+        capsule: &Capsule,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+        signing_pk: &PublicKey,
+    ) -> Result<CurvePoint, CapsuleFragVerificationError> {
+        if self.verify(capsule, delegating_pk, receiving_pk, signing_pk) {
+            Ok(self.proof.kfrag_commitment)
+        } else {
+            Err(CapsuleFragVerificationError::VerificationFailed)
+        }
+    }
+
+    /// Checks that the metadata this capsule fragment was created with
+    /// (by [`reencrypt_with_metadata_commitment()`](`crate::reencrypt_with_metadata_commitment()`))
+    /// is the one revealed by `opening`.
+    ///
+    /// `params` must be the parameters of the capsule the fragment was created for.
+    /// The binding of the metadata to the fragment is covered by [`verify()`](`Self::verify()`),
+    /// so the fragment should be verified as well.
+    #[must_use = "the verification result must be checked"]
+    pub fn open_metadata(&self, params: &Parameters, opening: &MetadataOpening) -> bool {
+        self.proof.metadata == opening.commitment_scalar(params)
+    }
+
+    /// Returns the message signed by the delegating party in the key fragment
+    /// this capsule fragment was created with, for verification by external code.
+    ///
+    /// The message is the concatenation of:
+    /// - the key fragment ID (see [`kfrag_id()`](`Self::kfrag_id()`)),
+    ///   as a 32-byte big-endian scalar;
+    /// - `delegating_pk` and `receiving_pk`, as 33-byte compressed points;
+    /// - the commitment to the key fragment and its precursor, as 33-byte compressed points;
+    /// - if `context` is given
+    ///   (see [`generate_kfrags_with_context()`](`crate::generate_kfrags_with_context()`)),
+    ///   its length as a 64-bit big-endian integer followed by the context itself.
+    ///
+    /// The signature returned by [`kfrag_signature()`](`Self::kfrag_signature()`)
+    /// is an ECDSA signature of the SHA3-256 digest of this message.
+    /// This is the check performed by [`verify()`](`Self::verify()`)
+    /// in addition to verifying the reencryption.
+    pub fn kfrag_validity_message(
+        &self,
+        delegating_pk: &PublicKey,
+        receiving_pk: &PublicKey,
+        context: Option<&[u8]>,
+    ) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&self.kfrag_id.to_array());
+        message.extend_from_slice(&delegating_pk.to_array());
+        message.extend_from_slice(&receiving_pk.to_array());
+        message.extend_from_slice(&self.proof.kfrag_commitment.to_array());
+        message.extend_from_slice(&self.precursor.to_array());
+        if let Some(context) = context {
+            message.extend_from_slice(&(context.len() as u64).to_be_bytes());
+            message.extend_from_slice(context);
+        }
+        message
+    }
+
+    /// Returns the signature of the delegating party over
+    /// [`kfrag_validity_message()`](`Self::kfrag_validity_message()`).
+    pub fn kfrag_signature(&self) -> Signature {
+        self.proof.kfrag_signature
+    }
+
+    /// Returns a copy of the capsule fragment without the key fragment signature
+    /// (see [`SlimCapsuleFrag`] for the consequences).
+    pub fn to_slim(&self) -> SlimCapsuleFrag {
+        SlimCapsuleFrag {
+            point_e1: self.point_e1,
+            point_v1: self.point_v1,
+            kfrag_id: self.kfrag_id,
+            precursor: self.precursor,
+            point_e2: self.proof.point_e2,
+            point_v2: self.proof.point_v2,
+            kfrag_commitment: self.proof.kfrag_commitment,
+            kfrag_pok: self.proof.kfrag_pok,
+            signature: self.proof.signature,
+            metadata: self.proof.metadata,
+        }
+    }
+}
+
+/// Hashes the plain metadata given to [`reencrypt()`](`crate::reencrypt()`).
+///
+/// There is no domain tag, to stay compatible with the existing implementations.
+fn metadata_to_scalar(metadata: &[u8]) -> CurveScalar {
+    ScalarDigest::new().chain_bytes(metadata).finalize()
+}
+
+/// The secret information allowing one to reveal the metadata hidden in a capsule fragment
+/// created by [`reencrypt_with_metadata_commitment()`](`crate::reencrypt_with_metadata_commitment()`)
+/// (see [`CapsuleFrag::open_metadata()`]).
+///
+/// The metadata is hashed to a scalar `m` and committed to with a Pedersen commitment
+/// `C = m * g + r * u`, where `g` is the curve generator, `u` is the second generator
+/// from [`Parameters`], and `r` is a random blinding factor.
+/// The capsule fragment's correctness proof binds the hash of `C`
+/// instead of the hash of the metadata itself, so the receiver of the fragment
+/// learns nothing about the metadata, while the proxy cannot later claim different metadata
+/// (since the discrete logarithm of `u` with respect to `g` is unknown).
+/// The hash of `C` uses its own domain tag instead of the common prefix
+/// of the hash of plain metadata, so no plain metadata produces the same scalar.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MetadataOpening {
+    metadata: Box<[u8]>,
+    blinding: CurveScalar,
+}
+
+impl MetadataOpening {
+    /// Creates an opening from the metadata and the blinding factor
+    /// (e.g. after they were transmitted to an auditor).
+    pub fn new(metadata: &[u8], blinding: &CurveScalar) -> Self {
+        Self {
+            metadata: metadata.into(),
+            blinding: *blinding,
+        }
+    }
+
+    /// Returns the committed metadata.
+    pub fn metadata(&self) -> &[u8] {
+        &self.metadata
+    }
+
+    /// Returns the blinding factor of the commitment.
+    pub fn blinding(&self) -> CurveScalar {
+        self.blinding
+    }
+
+    fn commitment_scalar(&self, params: &Parameters) -> CurveScalar {
+        let g = CurvePoint::generator();
+        let commitment =
+            &(&g * &metadata_to_scalar(&self.metadata)) + &(&params.u * &self.blinding);
+        ScalarDigest::new_with_dst(METADATA_COMMITMENT)
+            .chain_point(&commitment)
+            .finalize()
+    }
+}
+
+/// A capsule fragment with the signature of its key fragment stripped,
+/// obtained with [`CapsuleFrag::to_slim()`].
+///
+/// This saves the size of a signature per fragment, but the fragment is no longer bound
+/// to the delegating, receiving and signing keys:
+/// [`verify()`](`Self::verify()`) only checks that the capsule was correctly reencrypted
+/// with *some* key fragment, not that the key fragment was issued by the delegating party
+/// for the receiving party.
+/// Only use it if the key fragments were verified beforehand
+/// (e.g. with [`KeyFrag::verify()`](`crate::KeyFrag::verify()`) by the proxies,
+/// which are trusted to use only them).
+#[derive(Clone, Debug, PartialEq)]
+pub struct SlimCapsuleFrag {
+    point_e1: CurvePoint,
+    point_v1: CurvePoint,
+    kfrag_id: CurveScalar,
+    precursor: CurvePoint,
+    point_e2: CurvePoint,
+    point_v2: CurvePoint,
+    kfrag_commitment: CurvePoint,
+    kfrag_pok: CurvePoint,
+    signature: CurveScalar,
+    metadata: CurveScalar,
+}
+
+type SlimCapsuleFragSize = op!(CapsuleFragSize - SignatureSize);
+
+impl SerializableToArray for SlimCapsuleFrag {
+    type Size = SlimCapsuleFragSize;
+
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.point_e1
+            .to_array()
+            .concat(self.point_v1.to_array())
+            .concat(self.kfrag_id.to_array())
+            .concat(self.precursor.to_array())
+            .concat(self.point_e2.to_array())
+            .concat(self.point_v2.to_array())
+            .concat(self.kfrag_commitment.to_array())
+            .concat(self.kfrag_pok.to_array())
+            .concat(self.signature.to_array())
+            .concat(self.metadata.to_array())
+    }
+
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
+        let (point_e1, rest) = CurvePoint::take(*arr)?;
+        let (point_v1, rest) = CurvePoint::take(rest)?;
+        let (kfrag_id, rest) = CurveScalar::take(rest)?;
+        let (precursor, rest) = CurvePoint::take(rest)?;
+        let (point_e2, rest) = CurvePoint::take(rest)?;
+        let (point_v2, rest) = CurvePoint::take(rest)?;
+        let (kfrag_commitment, rest) = CurvePoint::take(rest)?;
+        let (kfrag_pok, rest) = CurvePoint::take(rest)?;
+        let (signature, rest) = CurveScalar::take(rest)?;
+        let metadata = CurveScalar::take_last(rest)?;
+        Some(Self {
+            point_e1,
+            point_v1,
+            kfrag_id,
+            precursor,
+            point_e2,
+            point_v2,
+            kfrag_commitment,
+            kfrag_pok,
+            signature,
+            metadata,
+        })
+    }
+}
+
+impl SlimCapsuleFrag {
+    /// Verifies that the fragment is a correct reencryption of `capsule`.
+    ///
+    /// Unlike [`CapsuleFrag::verify()`], this does not check which keys
+    /// the key fragment used for the reencryption was created for.
+    ///
+    /// **Warning:** always returns `true`
+    /// if the crate is built with `--cfg umbral_insecure_no_proofs`.
+    #[must_use = "the verification result must be checked"]
+    pub fn verify(&self, capsule: &Capsule) -> bool {
+        if cfg!(umbral_insecure_no_proofs) {
+            return true;
+        }
+
         let params = capsule.params;
 
         // Here are the formulaic constituents shared with
@@ -192,40 +624,179 @@ impl CapsuleFrag {
         let v1 = self.point_v1;
 
         let u = params.u;
-        let u1 = self.proof.kfrag_commitment;
+        let u1 = self.kfrag_commitment;
 
-        let e2 = self.proof.point_e2;
-        let v2 = self.proof.point_v2;
-        let u2 = self.proof.kfrag_pok;
+        let e2 = self.point_e2;
+        let v2 = self.point_v2;
+        let u2 = self.kfrag_pok;
 
         // TODO (#2): original uses ExtendedKeccak here
         let h = ScalarDigest::new()
             .chain_points(&[e, e1, e2, v, v1, v2, u, u1, u2])
-            .chain_scalar(&self.proof.metadata)
+            .chain_scalar(&self.metadata)
             .finalize();
 
         ///////
 
-        let precursor = self.precursor;
-        let kfrag_id = self.kfrag_id;
-
-        let valid_kfrag_signature = SignatureDigest::new()
-            .chain_scalar(&kfrag_id)
-            .chain_pubkey(delegating_pk)
-            .chain_pubkey(receiving_pk)
-            .chain_point(&u1)
-            .chain_point(&precursor)
-            .verify(signing_pk, &self.proof.kfrag_signature);
-
-        let z3 = self.proof.signature;
+        let z3 = self.signature;
         let correct_reencryption_of_e = &e * &z3 == &e2 + &(&e1 * &h);
         let correct_reencryption_of_v = &v * &z3 == &v2 + &(&v1 * &h);
         let correct_rk_commitment = &u * &z3 == &u2 + &(&u1 * &h);
 
-        valid_kfrag_signature
-            & correct_reencryption_of_e
-            & correct_reencryption_of_v
-            & correct_rk_commitment
+        correct_reencryption_of_e & correct_reencryption_of_v & correct_rk_commitment
+    }
+}
+
+/// The part of a capsule fragment used to open the capsule.
+pub(crate) trait ReencryptedShare {
+    fn point_e1(&self) -> CurvePoint;
+    fn point_v1(&self) -> CurvePoint;
+    fn kfrag_id(&self) -> CurveScalar;
+    fn precursor(&self) -> CurvePoint;
+}
+
+impl ReencryptedShare for CapsuleFrag {
+    fn point_e1(&self) -> CurvePoint {
+        self.point_e1
+    }
+
+    fn point_v1(&self) -> CurvePoint {
+        self.point_v1
+    }
+
+    fn kfrag_id(&self) -> CurveScalar {
+        self.kfrag_id
+    }
+
+    fn precursor(&self) -> CurvePoint {
+        self.precursor
+    }
+}
+
+impl ReencryptedShare for SlimCapsuleFrag {
+    fn point_e1(&self) -> CurvePoint {
+        self.point_e1
+    }
+
+    fn point_v1(&self) -> CurvePoint {
+        self.point_v1
+    }
+
+    fn kfrag_id(&self) -> CurveScalar {
+        self.kfrag_id
+    }
+
+    fn precursor(&self) -> CurvePoint {
+        self.precursor
+    }
+}
+
+/// Errors that can happen when verifying a [`CapsuleFrag`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CapsuleFragVerificationError {
+    /// The capsule fragment failed verification.
+    VerificationFailed,
+}
+
+impl fmt::Display for CapsuleFragVerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::VerificationFailed => write!(f, "Capsule fragment verification failed"),
+        }
+    }
+}
+
+/// Errors that can happen when assembling a [`CfragSet`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CfragSetError {
+    /// The capsule fragment was produced by a different set of key fragments
+    /// (its precursor differs from the ones already in the set).
+    MismatchedPrecursors,
+    /// A capsule fragment with the same key fragment ID is already in the set.
+    RepeatingKfragIds,
+}
+
+impl fmt::Display for CfragSetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MismatchedPrecursors => write!(f, "Capsule fragments have different precursors"),
+            Self::RepeatingKfragIds => {
+                write!(f, "Capsule fragments have repeating key fragment IDs")
+            }
+        }
+    }
+}
+
+/// A collection of capsule fragments that is known to be structurally consistent:
+/// all the fragments originate from the same set of key fragments,
+/// and no two fragments were made with the same key fragment.
+///
+/// Can be used anywhere a slice of [`CapsuleFrag`] is expected
+/// (e.g. in [`decrypt_reencrypted()`](`crate::decrypt_reencrypted()`)).
+/// Note that this does not verify the fragments themselves,
+/// use [`CapsuleFrag::verify()`] for that.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CfragSet {
+    cfrags: Vec<CapsuleFrag>,
+}
+
+impl CfragSet {
+    /// Creates an empty set.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a capsule fragment to the set,
+    /// checking that it is consistent with the fragments already added.
+    pub fn push(&mut self, cfrag: CapsuleFrag) -> Result<(), CfragSetError> {
+        if let Some(first) = self.cfrags.first() {
+            if first.precursor != cfrag.precursor {
+                return Err(CfragSetError::MismatchedPrecursors);
+            }
+        }
+        if self
+            .cfrags
+            .iter()
+            .any(|existing| existing.kfrag_id == cfrag.kfrag_id)
+        {
+            return Err(CfragSetError::RepeatingKfragIds);
+        }
+        self.cfrags.push(cfrag);
+        Ok(())
+    }
+
+    /// Assembles a set from a sequence of capsule fragments,
+    /// stopping at the first inconsistent one.
+    pub fn try_from_iter(
+        iter: impl IntoIterator<Item = CapsuleFrag>,
+    ) -> Result<Self, CfragSetError> {
+        let mut set = Self::new();
+        for cfrag in iter {
+            set.push(cfrag)?;
+        }
+        Ok(set)
+    }
+
+    /// Returns the number of capsule fragments in the set.
+    pub fn len(&self) -> usize {
+        self.cfrags.len()
+    }
+
+    /// Returns `true` if the set contains no capsule fragments.
+    pub fn is_empty(&self) -> bool {
+        self.cfrags.is_empty()
+    }
+}
+
+impl AsRef<[CapsuleFrag]> for CfragSet {
+    fn as_ref(&self) -> &[CapsuleFrag] {
+        &self.cfrags
+    }
+}
+
+impl FromIterator<CapsuleFrag> for Result<CfragSet, CfragSetError> {
+    fn from_iter<I: IntoIterator<Item = CapsuleFrag>>(iter: I) -> Self {
+        CfragSet::try_from_iter(iter)
     }
 }
 
@@ -235,13 +806,45 @@ mod tests {
     use alloc::boxed::Box;
     use alloc::vec::Vec;
 
-    use super::CapsuleFrag;
+    use digest::Digest;
+    use ecdsa::VerifyingKey;
+    use generic_array::typenum::Unsigned;
+    use k256::Secp256k1;
+    use sha3::Sha3_256;
+    use signature::{DigestVerifier, Signature as _};
+
+    use super::{
+        metadata_to_scalar, CapsuleFrag, CfragSet, CfragSetError, MetadataOpening, SlimCapsuleFrag,
+        CAPSULE_FRAG_SIZE,
+    };
+    use crate::curve::{CurvePoint, CurveScalar, PointError, Signature, SignatureError};
+    use crate::domain::METADATA_COMMITMENT;
+    use crate::traits::{DeserializationError, SerializableToFixedBytes};
     use crate::{
-        encrypt, generate_kfrags, reencrypt, Capsule, Parameters, PublicKey, SecretKey,
-        SerializableToArray,
+        decrypt_reencrypted, decrypt_reencrypted_slim, encrypt, generate_kfrags,
+        generate_kfrags_with_context, reencrypt, reencrypt_with_metadata_commitment, Capsule,
+        KeyFrag, Parameters, PublicKey, SecretKey, SerializableToArray,
     };
 
-    fn prepare_cfrags() -> (PublicKey, PublicKey, PublicKey, Capsule, Box<[CapsuleFrag]>) {
+    const PLAINTEXT: &[u8] = b"peace at dawn";
+
+    /// The keys of a 2-of-3 delegation, an encrypted [`PLAINTEXT`],
+    /// and the fragments reencrypting its capsule.
+    struct Reencryption {
+        params: Parameters,
+        delegating_sk: SecretKey,
+        delegating_pk: PublicKey,
+        signing_sk: SecretKey,
+        signing_pk: PublicKey,
+        receiving_sk: SecretKey,
+        receiving_pk: PublicKey,
+        capsule: Capsule,
+        ciphertext: Box<[u8]>,
+        kfrags: Box<[KeyFrag]>,
+        cfrags: Box<[CapsuleFrag]>,
+    }
+
+    fn prepare_cfrags() -> Reencryption {
         let params = Parameters::new();
 
         let delegating_sk = SecretKey::random();
@@ -253,8 +856,7 @@ mod tests {
         let receiving_sk = SecretKey::random();
         let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
 
-        let plaintext = b"peace at dawn";
-        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, PLAINTEXT).unwrap();
 
         let kfrags = generate_kfrags(
             &params,
@@ -272,26 +874,172 @@ mod tests {
             .map(|kfrag| reencrypt(&capsule, &kfrag, None))
             .collect();
 
-        (
+        Reencryption {
+            params,
+            delegating_sk,
             delegating_pk,
-            receiving_pk,
+            signing_sk,
             signing_pk,
+            receiving_sk,
+            receiving_pk,
             capsule,
-            cfrags.into_boxed_slice(),
-        )
+            ciphertext,
+            kfrags,
+            cfrags: cfrags.into_boxed_slice(),
+        }
+    }
+
+    #[test]
+    fn test_ordering() {
+        let Reencryption { cfrags, .. } = prepare_cfrags();
+
+        let mut sorted = cfrags.to_vec();
+        sorted.sort();
+        assert!(sorted
+            .windows(2)
+            .all(|pair| pair[0].kfrag_id().to_array() < pair[1].kfrag_id().to_array()));
+
+        // The order does not depend on the initial one
+        let mut shuffled: Vec<CapsuleFrag> = cfrags.iter().rev().cloned().collect();
+        shuffled.rotate_left(1);
+        shuffled.sort();
+        assert_eq!(shuffled, sorted);
     }
 
     #[test]
     fn test_serialize() {
-        let (_, _, _, _, cfrags) = prepare_cfrags();
+        let Reencryption { cfrags, .. } = prepare_cfrags();
         let cfrag_arr = cfrags[0].to_array();
         let cfrag_back = CapsuleFrag::from_array(&cfrag_arr).unwrap();
         assert_eq!(cfrags[0], cfrag_back);
+
+        let cfrag_bytes: [u8; CAPSULE_FRAG_SIZE] = cfrags[0].to_fixed_bytes();
+        assert_eq!(&cfrag_bytes[..], cfrag_arr.as_slice());
+        assert_eq!(
+            CapsuleFrag::from_fixed_bytes(&cfrag_bytes).unwrap(),
+            cfrags[0]
+        );
+
+        // The proof survives the round trip intact
+        let proof = cfrags[0].proof;
+        let proof_back = cfrag_back.proof;
+        assert_eq!(proof_back.point_e2, proof.point_e2);
+        assert_eq!(proof_back.point_v2, proof.point_v2);
+        assert_eq!(proof_back.kfrag_commitment, proof.kfrag_commitment);
+        assert_eq!(proof_back.kfrag_pok, proof.kfrag_pok);
+        assert_eq!(proof_back.signature, proof.signature);
+        assert_eq!(proof_back.kfrag_signature, proof.kfrag_signature);
+        assert_eq!(proof_back.metadata, proof.metadata);
+    }
+
+    #[test]
+    fn test_deserialize_invalid_components() {
+        let Reencryption { cfrags, .. } = prepare_cfrags();
+        let cfrag_bytes = cfrags[0].to_array();
+
+        // An x coordinate that does not correspond to a point on the curve
+        let mut off_curve = [0u8; 33];
+        off_curve[0] = 2;
+        while CurvePoint::try_from_bytes(off_curve).is_ok() {
+            off_curve[32] += 1;
+        }
+
+        // Offsets of the embedded points: `e1`, `v1`, the precursor,
+        // and `e2`, `v2`, the commitment and the proof of knowledge from the proof.
+        let point_offsets = [0, 33, 98, 131, 164, 197, 230];
+        for &offset in point_offsets.iter() {
+            let mut bad_bytes = cfrag_bytes;
+            bad_bytes[offset..offset + 33].copy_from_slice(&off_curve);
+            assert!(CapsuleFrag::from_bytes(bad_bytes).is_none());
+            assert_eq!(
+                CapsuleFrag::try_from_bytes(bad_bytes),
+                Err(DeserializationError::InvalidPoint(PointError::NotOnCurve))
+            );
+
+            // An invalid SEC1 tag
+            let mut bad_bytes = cfrag_bytes;
+            bad_bytes[offset] = 5;
+            assert!(CapsuleFrag::from_bytes(bad_bytes).is_none());
+            assert_eq!(
+                CapsuleFrag::try_from_bytes(bad_bytes),
+                Err(DeserializationError::InvalidPoint(
+                    PointError::MalformedEncoding
+                ))
+            );
+        }
+
+        // Offsets of the embedded scalars: the kfrag ID,
+        // and the signature and the metadata from the proof.
+        // A scalar with all bits set is greater than the curve order.
+        let scalar_offsets = [66, 263, 359];
+        for &offset in scalar_offsets.iter() {
+            let mut bad_bytes = cfrag_bytes;
+            bad_bytes[offset..offset + 32].copy_from_slice(&[0xff; 32]);
+            assert!(CapsuleFrag::from_bytes(bad_bytes).is_none());
+            assert_eq!(
+                CapsuleFrag::try_from_bytes(bad_bytes),
+                Err(DeserializationError::InvalidScalar)
+            );
+        }
+
+        // The kfrag signature, with a zero `r` or an out-of-range `s`
+        let mut bad_bytes = cfrag_bytes;
+        bad_bytes[295..327].copy_from_slice(&[0; 32]);
+        assert!(CapsuleFrag::from_bytes(bad_bytes).is_none());
+        assert_eq!(
+            CapsuleFrag::try_from_bytes(bad_bytes),
+            Err(DeserializationError::InvalidSignature(
+                SignatureError::MalformedEncoding
+            ))
+        );
+        let mut bad_bytes = cfrag_bytes;
+        bad_bytes[327..359].copy_from_slice(&[0xff; 32]);
+        assert!(CapsuleFrag::from_bytes(bad_bytes).is_none());
+
+        assert_eq!(
+            CapsuleFrag::try_from_bytes(&cfrag_bytes[1..]),
+            Err(DeserializationError::WrongLength)
+        );
+
+        // The unmodified bytes still deserialize
+        assert_eq!(CapsuleFrag::from_bytes(cfrag_bytes).unwrap(), cfrags[0]);
+        assert_eq!(
+            CapsuleFrag::try_from_bytes(cfrag_bytes),
+            Ok(cfrags[0].clone())
+        );
+    }
+
+    #[cfg(not(umbral_insecure_no_proofs))]
+    #[test]
+    fn test_self_check() {
+        let Reencryption {
+            capsule,
+            kfrags,
+            cfrags,
+            ..
+        } = prepare_cfrags();
+        for cfrag in cfrags.iter() {
+            assert!(cfrag.to_slim().verify(&capsule));
+        }
+
+        // An inconsistent key fragment does not trigger the self-check in debug builds,
+        // it only produces a fragment that fails verification
+        let mut kfrag = kfrags[0].clone();
+        kfrag.key = &kfrag.key + &CurveScalar::from_u64(1);
+        let cfrag = reencrypt(&capsule, &kfrag, None);
+        assert!(!cfrag.to_slim().verify(&capsule));
     }
 
     #[test]
     fn test_verify() {
-        let (delegating_pk, receiving_pk, signing_pk, capsule, cfrags) = prepare_cfrags();
+        let Reencryption {
+            delegating_pk,
+            receiving_pk,
+            signing_pk,
+            capsule,
+            cfrags,
+            ..
+        } = prepare_cfrags();
         assert!(cfrags.iter().all(|cfrag| cfrag.verify(
             &capsule,
             &delegating_pk,
@@ -299,4 +1047,366 @@ mod tests {
             &signing_pk,
         )));
     }
+
+    #[test]
+    fn test_verify_and_extract() {
+        let Reencryption {
+            delegating_pk,
+            signing_pk,
+            receiving_pk,
+            capsule,
+            kfrags,
+            cfrags,
+            ..
+        } = prepare_cfrags();
+
+        for (kfrag, cfrag) in kfrags.iter().zip(cfrags.iter()) {
+            let commitment = cfrag
+                .verify_and_extract(&capsule, &delegating_pk, &receiving_pk, &signing_pk)
+                .unwrap();
+            assert_eq!(commitment, kfrag.proof.commitment);
+        }
+
+        #[cfg(not(umbral_insecure_no_proofs))]
+        {
+            use super::CapsuleFragVerificationError;
+
+            assert_eq!(
+                cfrags[0].verify_and_extract(&capsule, &receiving_pk, &delegating_pk, &signing_pk),
+                Err(CapsuleFragVerificationError::VerificationFailed)
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify_with_context() {
+        let Reencryption {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_pk,
+            capsule,
+            ..
+        } = prepare_cfrags();
+
+        let context = b"expires:2030-01-01";
+        let kfrags = generate_kfrags_with_context(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+            Some(context),
+        );
+        let cfrag = reencrypt(&capsule, &kfrags[0], None);
+
+        assert!(cfrag.verify_with_context(
+            &capsule,
+            &delegating_pk,
+            &receiving_pk,
+            &signing_pk,
+            Some(context)
+        ));
+        #[cfg(not(umbral_insecure_no_proofs))]
+        {
+            assert!(!cfrag.verify_with_context(
+                &capsule,
+                &delegating_pk,
+                &receiving_pk,
+                &signing_pk,
+                Some(b"expires:2031-01-01")
+            ));
+            assert!(!cfrag.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+        }
+    }
+
+    #[test]
+    fn test_kfrag_id() {
+        let Reencryption {
+            capsule, kfrags, ..
+        } = prepare_cfrags();
+        assert!(kfrags[0].id() != kfrags[1].id());
+
+        let cfrag1 = reencrypt(&capsule, &kfrags[0], None);
+        let cfrag2 = reencrypt(&capsule, &kfrags[0], Some(b"metadata"));
+        let cfrag3 = reencrypt(&capsule, &kfrags[1], None);
+
+        assert_eq!(cfrag1.kfrag_id(), kfrags[0].id());
+        assert_eq!(cfrag1.kfrag_id(), cfrag2.kfrag_id());
+        assert!(cfrag1.kfrag_id() != cfrag3.kfrag_id());
+    }
+
+    #[test]
+    fn test_same_source() {
+        let Reencryption {
+            params,
+            delegating_pk,
+            capsule,
+            kfrags,
+            ..
+        } = prepare_cfrags();
+
+        let cfrag1 = reencrypt(&capsule, &kfrags[0], None);
+        let cfrag2 = reencrypt(&capsule, &kfrags[0], None);
+        assert!(cfrag1.same_source(&cfrag2));
+        // The proofs are randomized
+        #[cfg(not(umbral_insecure_no_proofs))]
+        assert!(cfrag1.to_array() != cfrag2.to_array());
+
+        // A different kfrag
+        let cfrag3 = reencrypt(&capsule, &kfrags[1], None);
+        assert!(!cfrag1.same_source(&cfrag3));
+
+        // A different capsule
+        let (capsule2, _ciphertext) = encrypt(&params, &delegating_pk, PLAINTEXT).unwrap();
+        let cfrag4 = reencrypt(&capsule2, &kfrags[0], None);
+        assert!(!cfrag1.same_source(&cfrag4));
+    }
+
+    #[test]
+    fn test_kfrag_validity_message() {
+        let Reencryption {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_pk,
+            capsule,
+            ..
+        } = prepare_cfrags();
+
+        // Verify the signature with the backend directly, bypassing the library code
+        let verify_externally = |message: &[u8], signature: &Signature| {
+            let verifier =
+                VerifyingKey::<Secp256k1>::from_sec1_bytes(&signing_pk.to_array()).unwrap();
+            let signature =
+                ecdsa::Signature::<Secp256k1>::from_bytes(&signature.to_array()).unwrap();
+            verifier
+                .verify_digest(Digest::chain(Sha3_256::new(), message), &signature)
+                .is_ok()
+        };
+
+        let context = b"some context";
+        for &ctx in [None, Some(&context[..])].iter() {
+            let kfrags = generate_kfrags_with_context(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                2,
+                3,
+                true,
+                true,
+                ctx,
+            );
+            let cfrag = reencrypt(&capsule, &kfrags[0], None);
+
+            let message = cfrag.kfrag_validity_message(&delegating_pk, &receiving_pk, ctx);
+
+            // Check the documented layout
+            let mut expected = Vec::new();
+            expected.extend_from_slice(&cfrag.kfrag_id().to_array());
+            expected.extend_from_slice(&delegating_pk.to_array());
+            expected.extend_from_slice(&receiving_pk.to_array());
+            expected.extend_from_slice(&cfrag.proof.kfrag_commitment.to_array());
+            expected.extend_from_slice(&cfrag.precursor.to_array());
+            if let Some(ctx) = ctx {
+                expected.extend_from_slice(&(ctx.len() as u64).to_be_bytes());
+                expected.extend_from_slice(ctx);
+            }
+            assert_eq!(message, expected);
+            assert_eq!(message.len(), 32 + 33 * 4 + ctx.map_or(0, |c| 8 + c.len()));
+
+            assert!(verify_externally(&message, &cfrag.kfrag_signature()));
+
+            // The message for different keys does not verify
+            let wrong_message = cfrag.kfrag_validity_message(&receiving_pk, &delegating_pk, ctx);
+            assert!(!verify_externally(&wrong_message, &cfrag.kfrag_signature()));
+        }
+    }
+
+    #[test]
+    fn test_metadata_commitment() {
+        let Reencryption {
+            params,
+            delegating_pk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            capsule,
+            ciphertext,
+            kfrags,
+            ..
+        } = prepare_cfrags();
+
+        let metadata = b"billing period 2021-03";
+        let (cfrags, openings): (Vec<CapsuleFrag>, Vec<MetadataOpening>) = kfrags
+            .iter()
+            .map(|kfrag| reencrypt_with_metadata_commitment(&capsule, kfrag, metadata))
+            .unzip();
+
+        // The fragments are verified and used as usual
+        assert!(cfrags.iter().all(|cfrag| cfrag.verify(
+            &capsule,
+            &delegating_pk,
+            &receiving_pk,
+            &signing_pk
+        )));
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], PLAINTEXT);
+
+        // The metadata is hidden: the fragment does not contain its plain hash
+        let plain_cfrag = reencrypt(&capsule, &kfrags[0], Some(metadata));
+        assert!(cfrags[0].proof.metadata != plain_cfrag.proof.metadata);
+
+        // The auditor receives the opening and checks it
+        let opening = MetadataOpening::new(openings[0].metadata(), &openings[0].blinding());
+        assert_eq!(opening.metadata(), metadata);
+        assert!(cfrags[0].open_metadata(&params, &opening));
+
+        // Wrong openings fail
+        assert!(!cfrags[1].open_metadata(&params, &opening));
+        let wrong_metadata = MetadataOpening::new(b"billing period 2021-04", &opening.blinding());
+        assert!(!cfrags[0].open_metadata(&params, &wrong_metadata));
+        let wrong_blinding = MetadataOpening::new(metadata, &CurveScalar::random_nonzero());
+        assert!(!cfrags[0].open_metadata(&params, &wrong_blinding));
+
+        // Plain metadata crafted from the commitment does not open
+        let g = CurvePoint::generator();
+        let commitment = &(&g * &metadata_to_scalar(metadata)) + &(&params.u * &opening.blinding());
+        let mut crafted = METADATA_COMMITMENT.to_vec();
+        crafted.extend(commitment.to_array());
+        let crafted_cfrag = reencrypt(&capsule, &kfrags[0], Some(&crafted));
+        assert!(!crafted_cfrag.open_metadata(&params, &opening));
+    }
+
+    #[test]
+    fn test_slim_cfrags() {
+        let Reencryption {
+            params,
+            delegating_pk,
+            receiving_sk,
+            capsule,
+            ciphertext,
+            cfrags,
+            ..
+        } = prepare_cfrags();
+
+        let slim_cfrags: Vec<SlimCapsuleFrag> = cfrags.iter().map(CapsuleFrag::to_slim).collect();
+
+        // Serialization round-trip, one signature shorter than a full fragment
+        let slim_arr = slim_cfrags[0].to_array();
+        assert_eq!(
+            slim_arr.len() + <Signature as SerializableToArray>::Size::to_usize(),
+            <CapsuleFrag as SerializableToArray>::Size::to_usize()
+        );
+        let slim_back = SlimCapsuleFrag::from_array(&slim_arr).unwrap();
+        assert_eq!(slim_cfrags[0], slim_back);
+
+        assert!(slim_cfrags.iter().all(|cfrag| cfrag.verify(&capsule)));
+
+        // Still bound to the capsule
+        #[cfg(not(umbral_insecure_no_proofs))]
+        {
+            let (capsule2, _ciphertext) = encrypt(&params, &delegating_pk, PLAINTEXT).unwrap();
+            assert!(!slim_cfrags[0].verify(&capsule2));
+        }
+
+        let plaintext_back = decrypt_reencrypted_slim(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &slim_cfrags[1..],
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_back as &[u8], PLAINTEXT);
+
+        // Not enough fragments
+        assert!(decrypt_reencrypted_slim(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &slim_cfrags[..1],
+            &ciphertext,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_cfrag_set() {
+        let Reencryption { cfrags, .. } = prepare_cfrags();
+
+        let set: Result<CfragSet, _> = cfrags.iter().cloned().collect();
+        let set = set.unwrap();
+        assert_eq!(set.len(), cfrags.len());
+        assert_eq!(set.as_ref(), &cfrags as &[CapsuleFrag]);
+
+        // Repeating kfrag IDs
+        let repeating = [cfrags[0].clone(), cfrags[1].clone(), cfrags[0].clone()];
+        assert_eq!(
+            CfragSet::try_from_iter(repeating.iter().cloned()),
+            Err(CfragSetError::RepeatingKfragIds)
+        );
+
+        // Fragments made from two different sets of kfrags
+        let Reencryption {
+            cfrags: other_cfrags,
+            ..
+        } = prepare_cfrags();
+        let other_kfrag_cfrag = other_cfrags[0].clone();
+        let mixed = [cfrags[0].clone(), other_kfrag_cfrag];
+        assert_eq!(
+            CfragSet::try_from_iter(mixed.iter().cloned()),
+            Err(CfragSetError::MismatchedPrecursors)
+        );
+
+        // A failed push leaves the set unchanged
+        let mut set = CfragSet::new();
+        set.push(cfrags[0].clone()).unwrap();
+        assert!(set.push(cfrags[0].clone()).is_err());
+        assert_eq!(set.len(), 1);
+    }
+
+    #[cfg(umbral_insecure_no_proofs)]
+    #[test]
+    fn test_no_proofs() {
+        let Reencryption {
+            delegating_pk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            capsule,
+            ciphertext,
+            cfrags,
+            ..
+        } = prepare_cfrags();
+        let cfrags = &cfrags[..2];
+
+        // The placeholder proofs survive serialization
+        for cfrag in cfrags.iter() {
+            assert_eq!(&CapsuleFrag::from_array(&cfrag.to_array()).unwrap(), cfrag);
+        }
+
+        // Verification is a no-op: even the wrong keys are accepted
+        assert!(cfrags[0].verify(&capsule, &receiving_pk, &delegating_pk, &signing_pk));
+
+        let plaintext_bob =
+            decrypt_reencrypted(&receiving_sk, &delegating_pk, &capsule, cfrags, &ciphertext)
+                .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], PLAINTEXT);
+    }
 }
@@ -0,0 +1,262 @@
+//! A compact binary codec with explicit versioning, writing into caller-provided buffers.
+//!
+//! Every encoded object has the following layout:
+//!
+//! | Offset | Size | Field                                          |
+//! |--------|------|------------------------------------------------|
+//! | 0      | 1    | codec version ([`CODEC_VERSION`])              |
+//! | 1      | 1    | type tag (see [`Encodable::TYPE_TAG`])         |
+//! | 2      | N    | the object in the [`wire`](`crate::wire`) format |
+//!
+//! The object is written with [`SerializableToArray::to_array()`], which produces the wire format,
+//! so encoding does not allocate.
+//!
+//! The type tags are `0x01` for [`Capsule`], `0x02` for [`KeyFrag`],
+//! and `0x03` for [`CapsuleFrag`].
+//! Since the objects are fixed-size, there is no length prefix;
+//! the total size of an encoding is given by [`encoded_size()`].
+
+use core::fmt;
+
+use typenum::Unsigned;
+
+use crate::capsule::Capsule;
+use crate::capsule_frag::CapsuleFrag;
+use crate::key_frag::KeyFrag;
+use crate::traits::SerializableToArray;
+
+/// The current version of the codec, written in the first byte of every encoding.
+pub const CODEC_VERSION: u8 = 1;
+
+const HEADER_SIZE: usize = 2;
+
+/// Errors that can happen during encoding or decoding.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CodecError {
+    /// The buffer is too small for the encoded object.
+    BufferTooSmall,
+    /// The encoding has a codec version this implementation does not support.
+    UnknownVersion(u8),
+    /// The encoding has a type tag different from the one of the requested type.
+    UnexpectedTypeTag(u8),
+    /// The encoding has a wrong length for the requested type.
+    InvalidLength,
+    /// The object could not be decoded from its fields.
+    InvalidObject,
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::BufferTooSmall => write!(f, "Buffer is too small for the encoded object"),
+            Self::UnknownVersion(version) => write!(f, "Unknown codec version: {}", version),
+            Self::UnexpectedTypeTag(tag) => write!(f, "Unexpected type tag: {}", tag),
+            Self::InvalidLength => write!(f, "Invalid length of the encoded object"),
+            Self::InvalidObject => write!(f, "Invalid encoded object"),
+        }
+    }
+}
+
+/// An object that can be encoded with this codec.
+pub trait Encodable: SerializableToArray {
+    /// The tag identifying the type of the object in the encoding.
+    const TYPE_TAG: u8;
+}
+
+impl Encodable for Capsule {
+    const TYPE_TAG: u8 = 1;
+}
+
+impl Encodable for KeyFrag {
+    const TYPE_TAG: u8 = 2;
+}
+
+impl Encodable for CapsuleFrag {
+    const TYPE_TAG: u8 = 3;
+}
+
+/// Returns the size of the encoding of an object of type `T`, including the header.
+pub fn encoded_size<T: Encodable>() -> usize {
+    HEADER_SIZE + T::Size::to_usize()
+}
+
+/// Encodes `obj` into the beginning of `buf`, returning the number of bytes written.
+pub fn encode<T: Encodable>(obj: &T, buf: &mut [u8]) -> Result<usize, CodecError> {
+    let size = encoded_size::<T>();
+    if buf.len() < size {
+        return Err(CodecError::BufferTooSmall);
+    }
+    buf[0] = CODEC_VERSION;
+    buf[1] = T::TYPE_TAG;
+    buf[HEADER_SIZE..size].copy_from_slice(&obj.to_array());
+    Ok(size)
+}
+
+/// Decodes an object of type `T` from `bytes`, which must contain exactly one encoding.
+pub fn decode<T: Encodable>(bytes: &[u8]) -> Result<T, CodecError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(CodecError::InvalidLength);
+    }
+    if bytes[0] != CODEC_VERSION {
+        return Err(CodecError::UnknownVersion(bytes[0]));
+    }
+    if bytes[1] != T::TYPE_TAG {
+        return Err(CodecError::UnexpectedTypeTag(bytes[1]));
+    }
+    if bytes.len() != encoded_size::<T>() {
+        return Err(CodecError::InvalidLength);
+    }
+    T::from_bytes(&bytes[HEADER_SIZE..]).ok_or(CodecError::InvalidObject)
+}
+
+/// Encodes a [`Capsule`] (see [`encode()`]).
+pub fn encode_capsule(capsule: &Capsule, buf: &mut [u8]) -> Result<usize, CodecError> {
+    encode(capsule, buf)
+}
+
+/// Decodes a [`Capsule`] (see [`decode()`]).
+pub fn decode_capsule(bytes: &[u8]) -> Result<Capsule, CodecError> {
+    decode(bytes)
+}
+
+/// Encodes a [`KeyFrag`] (see [`encode()`]).
+pub fn encode_kfrag(kfrag: &KeyFrag, buf: &mut [u8]) -> Result<usize, CodecError> {
+    encode(kfrag, buf)
+}
+
+/// Decodes a [`KeyFrag`] (see [`decode()`]).
+pub fn decode_kfrag(bytes: &[u8]) -> Result<KeyFrag, CodecError> {
+    decode(bytes)
+}
+
+/// Encodes a [`CapsuleFrag`] (see [`encode()`]).
+pub fn encode_cfrag(cfrag: &CapsuleFrag, buf: &mut [u8]) -> Result<usize, CodecError> {
+    encode(cfrag, buf)
+}
+
+/// Decodes a [`CapsuleFrag`] (see [`decode()`]).
+pub fn decode_cfrag(bytes: &[u8]) -> Result<CapsuleFrag, CodecError> {
+    decode(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec;
+
+    use super::{
+        decode, decode_capsule, decode_cfrag, decode_kfrag, encode, encode_capsule, encode_cfrag,
+        encode_kfrag, encoded_size, CodecError, Encodable, CODEC_VERSION,
+    };
+    use crate::{
+        encrypt, generate_kfrags, reencrypt, Capsule, CapsuleFrag, KeyFrag, Parameters, PublicKey,
+        SecretKey,
+    };
+
+    fn check_round_trip<T>(obj: &T)
+    where
+        T: Encodable + PartialEq + core::fmt::Debug,
+    {
+        let mut buf = [0u8; 400];
+        let size = encode(obj, &mut buf).unwrap();
+        assert_eq!(size, encoded_size::<T>());
+        assert_eq!(buf[0], CODEC_VERSION);
+        assert_eq!(buf[1], T::TYPE_TAG);
+        assert_eq!(&decode::<T>(&buf[..size]).unwrap(), obj);
+
+        // Wrong lengths
+        assert_eq!(
+            decode::<T>(&buf[..size - 1]),
+            Err(CodecError::InvalidLength)
+        );
+        assert_eq!(
+            decode::<T>(&buf[..size + 1]),
+            Err(CodecError::InvalidLength)
+        );
+        assert_eq!(decode::<T>(&buf[..1]), Err(CodecError::InvalidLength));
+
+        // Not enough room
+        let mut small_buf = vec![0u8; size - 1];
+        assert_eq!(encode(obj, &mut small_buf), Err(CodecError::BufferTooSmall));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+        let cfrag = reencrypt(&capsule, &kfrags[0], None);
+
+        check_round_trip(&capsule);
+        check_round_trip(&kfrags[0]);
+        check_round_trip(&cfrag);
+
+        // Type-specific functions
+        let mut buf = [0u8; 400];
+        let size = encode_capsule(&capsule, &mut buf).unwrap();
+        assert_eq!(decode_capsule(&buf[..size]).unwrap(), capsule);
+        let size = encode_kfrag(&kfrags[0], &mut buf).unwrap();
+        assert_eq!(decode_kfrag(&buf[..size]).unwrap(), kfrags[0]);
+        let size = encode_cfrag(&cfrag, &mut buf).unwrap();
+        assert_eq!(decode_cfrag(&buf[..size]).unwrap(), cfrag);
+    }
+
+    #[test]
+    fn test_header_errors() {
+        let params = Parameters::new();
+        let pk = PublicKey::from_secret_key(&SecretKey::random());
+        let (capsule, _ciphertext) = encrypt(&params, &pk, b"peace at dawn").unwrap();
+
+        let mut buf = [0u8; 400];
+        let size = encode(&capsule, &mut buf).unwrap();
+        let encoded = &buf[..size];
+
+        let mut bad_version = encoded.to_vec();
+        bad_version[0] = CODEC_VERSION + 1;
+        assert_eq!(
+            decode::<Capsule>(&bad_version),
+            Err(CodecError::UnknownVersion(CODEC_VERSION + 1))
+        );
+
+        // A capsule cannot be decoded as another type
+        assert_eq!(
+            decode::<KeyFrag>(encoded),
+            Err(CodecError::UnexpectedTypeTag(Capsule::TYPE_TAG))
+        );
+        assert_eq!(
+            decode::<CapsuleFrag>(encoded),
+            Err(CodecError::UnexpectedTypeTag(Capsule::TYPE_TAG))
+        );
+
+        let mut bad_tag = encoded.to_vec();
+        bad_tag[1] = 0xff;
+        assert_eq!(
+            decode::<Capsule>(&bad_tag),
+            Err(CodecError::UnexpectedTypeTag(0xff))
+        );
+
+        // The fields are still validated
+        let mut bad_object = encoded.to_vec();
+        bad_object[size - 1] ^= 1;
+        assert_eq!(
+            decode::<Capsule>(&bad_object),
+            Err(CodecError::InvalidObject)
+        );
+    }
+}
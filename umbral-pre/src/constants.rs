@@ -1,3 +0,0 @@
-pub(crate) const NON_INTERACTIVE: &[u8] = b"NON_INTERACTIVE";
-
-pub(crate) const X_COORDINATE: &[u8] = b"X_COORDINATE";
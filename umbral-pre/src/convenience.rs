@@ -0,0 +1,143 @@
+//! Wrappers of the main API for UTF-8 text payloads.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use core::fmt;
+use core::str::Utf8Error;
+
+use crate::capsule::Capsule;
+use crate::capsule_frag::CapsuleFrag;
+use crate::curve::{PublicKey, SecretKey};
+use crate::params::Parameters;
+use crate::pre::{decrypt_original, decrypt_reencrypted, encrypt, EncryptionError};
+
+/// Errors that can happen when decrypting a text message.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StrDecryptionError {
+    /// The decryption failed (wrong key, or corrupted capsule or ciphertext).
+    DecryptionFailed,
+    /// The decrypted plaintext is not a valid UTF-8 string.
+    InvalidUtf8(Utf8Error),
+}
+
+impl fmt::Display for StrDecryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DecryptionFailed => write!(f, "Decryption failed"),
+            Self::InvalidUtf8(err) => write!(f, "Plaintext is not a valid UTF-8 string: {}", err),
+        }
+    }
+}
+
+fn into_string(plaintext: Option<Box<[u8]>>) -> Result<String, StrDecryptionError> {
+    let plaintext = plaintext.ok_or(StrDecryptionError::DecryptionFailed)?;
+    String::from_utf8(plaintext.into_vec())
+        .map_err(|err| StrDecryptionError::InvalidUtf8(err.utf8_error()))
+}
+
+/// Same as [`encrypt()`](`crate::encrypt()`), for a text message.
+pub fn encrypt_str(
+    params: &Parameters,
+    pk: &PublicKey,
+    plaintext: &str,
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    encrypt(params, pk, plaintext.as_bytes())
+}
+
+/// Same as [`decrypt_original()`](`crate::decrypt_original()`),
+/// additionally checking that the plaintext is a valid UTF-8 string.
+pub fn decrypt_original_str(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<String, StrDecryptionError> {
+    into_string(decrypt_original(decrypting_sk, capsule, ciphertext))
+}
+
+/// Same as [`decrypt_reencrypted()`](`crate::decrypt_reencrypted()`),
+/// additionally checking that the plaintext is a valid UTF-8 string.
+pub fn decrypt_reencrypted_str(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<String, StrDecryptionError> {
+    into_string(decrypt_reencrypted(
+        decrypting_sk,
+        delegating_pk,
+        capsule,
+        cfrags,
+        ciphertext,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use super::{decrypt_original_str, decrypt_reencrypted_str, encrypt_str, StrDecryptionError};
+    use crate::{
+        encrypt, generate_kfrags, reencrypt, CapsuleFrag, Parameters, PublicKey, SecretKey,
+    };
+
+    #[test]
+    fn test_round_trip() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let plaintext = "Мир на рассвете — 黎明时的和平 🕊";
+        let (capsule, ciphertext) = encrypt_str(&params, &delegating_pk, plaintext).unwrap();
+
+        let plaintext_alice = decrypt_original_str(&delegating_sk, &capsule, &ciphertext).unwrap();
+        assert_eq!(plaintext_alice, plaintext);
+
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+        let cfrags: Vec<CapsuleFrag> = kfrags[..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+        let plaintext_bob = decrypt_reencrypted_str(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(plaintext_bob, plaintext);
+
+        assert_eq!(
+            decrypt_original_str(&receiving_sk, &capsule, &ciphertext),
+            Err(StrDecryptionError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn test_invalid_utf8() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        let (capsule, ciphertext) = encrypt(&params, &pk, b"valid prefix \xff\xfe").unwrap();
+        match decrypt_original_str(&sk, &capsule, &ciphertext) {
+            Err(StrDecryptionError::InvalidUtf8(err)) => assert_eq!(err.valid_up_to(), 13),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -2,8 +2,12 @@
 //! `elliptic_curves` has a somewhat unstable API,
 //! and we isolate all the related logic here.
 
+use core::convert::TryFrom;
 use core::default::Default;
+use core::fmt;
+use core::iter::Sum;
 use core::ops::{Add, Mul, Sub};
+use core::sync::atomic;
 use digest::{BlockInput, Digest, FixedOutput, Reset, Update};
 use ecdsa::{Signature as BackendSignature, SignatureSize, SigningKey, VerifyingKey};
 use elliptic_curve::ff::PrimeField;
@@ -13,14 +17,18 @@ use elliptic_curve::{
     Curve, FromDigest, ProjectiveArithmetic, PublicKey as BackendPublicKey, Scalar,
     SecretKey as BackendSecretKey,
 };
-use generic_array::typenum::U32;
+use generic_array::typenum::{Unsigned, U32, U65};
 use generic_array::GenericArray;
+use k256::ecdsa::{recoverable, SigningKey as BackendRecoverableSigningKey};
 use k256::Secp256k1;
-use rand_core::OsRng;
+use rand_core::{CryptoRng, OsRng, RngCore};
+use sha3::Sha3_256;
 use signature::{DigestVerifier, RandomizedDigestSigner, Signature as SignatureTrait};
 use subtle::CtOption;
+use zeroize::{Zeroize, Zeroizing};
 
-use crate::traits::SerializableToArray;
+use crate::key_derivation::derive_secret_scalar;
+use crate::traits::{SerializableToArray, SerializableToFixedBytes};
 
 type CurveType = Secp256k1;
 
@@ -35,6 +43,11 @@ type BackendNonZeroScalar = NonZeroScalar<CurveType>;
 //     type PointSize = <Point as SerializableToArray>::Size;
 // isn't leaking the `Point` (probably because type aliases are just inlined).
 
+/// An element of the scalar field of the elliptic curve used by the scheme.
+///
+/// Can be serialized with [`SerializableToArray`] (as 32 big-endian bytes).
+/// Deserialization only accepts the canonical encoding,
+/// rejecting values greater than or equal to the curve order.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CurveScalar(BackendScalar);
 
@@ -52,8 +65,13 @@ impl CurveScalar {
     }
 
     /// Generates a random non-zero scalar (in nearly constant-time).
-    pub(crate) fn random_nonzero() -> CurveScalar {
-        Self(*BackendNonZeroScalar::random(&mut OsRng))
+    pub fn random_nonzero() -> CurveScalar {
+        Self::random_nonzero_with_rng(&mut OsRng)
+    }
+
+    /// Generates a random non-zero scalar using the given RNG.
+    pub(crate) fn random_nonzero_with_rng(rng: &mut (impl CryptoRng + RngCore)) -> CurveScalar {
+        Self(*BackendNonZeroScalar::random(rng))
     }
 
     pub(crate) fn from_digest(
@@ -61,6 +79,35 @@ impl CurveScalar {
     ) -> Self {
         Self(BackendScalar::from_digest(d))
     }
+
+    /// Creates a scalar from an integer.
+    pub fn from_u64(x: u64) -> Self {
+        Self(BackendScalar::from(x))
+    }
+
+    /// Creates a scalar by reducing a 512-bit big-endian integer modulo the curve order.
+    ///
+    /// If the bytes are uniformly random, the resulting scalar is uniformly distributed
+    /// (with a negligible bias of order 2^(-256)).
+    pub fn from_bytes_wide(bytes: &[u8; 64]) -> Self {
+        // 2^256 mod n, where n is the curve order
+        const TWO_POW_256: [u8; 32] = [
+            0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, 0x45, 0x51, 0x23, 0x19, 0x50, 0xb7,
+            0x5f, 0xc4, 0x40, 0x2d, 0xa1, 0x73, 0x2f, 0xc9, 0xbe, 0xbf,
+        ];
+
+        // Since 2^256 < 2n, each half can be reduced with a single subtraction.
+        let hi = BackendScalar::from_bytes_reduced(GenericArray::from_slice(&bytes[..32]));
+        let lo = BackendScalar::from_bytes_reduced(GenericArray::from_slice(&bytes[32..]));
+        let two_pow_256 = BackendScalar::from_bytes_reduced(&TWO_POW_256.into());
+        Self(hi * two_pow_256 + lo)
+    }
+}
+
+impl Zeroize for CurveScalar {
+    fn zeroize(&mut self) {
+        self.0.zeroize()
+    }
 }
 
 impl Default for CurveScalar {
@@ -85,17 +132,87 @@ impl SerializableToArray for CurveScalar {
 
 type BackendPoint = <CurveType as ProjectiveArithmetic>::ProjectivePoint;
 
+/// A point on the elliptic curve used by the scheme.
+///
+/// Can be serialized with [`SerializableToArray`] (in the compressed SEC1 form).
+/// Deserialization rejects bytes that do not encode a point on the curve.
+/// Since the cofactor of secp256k1 is 1, every point on the curve
+/// belongs to the prime-order group, so no separate subgroup check is needed.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct CurvePoint(BackendPoint);
 
 impl CurvePoint {
-    pub(crate) fn generator() -> Self {
+    /// Returns the generator point of the curve group.
+    pub fn generator() -> Self {
         Self(BackendPoint::generator())
     }
 
-    pub(crate) fn identity() -> Self {
+    /// Returns the identity element of the curve group (the point at infinity).
+    pub fn identity() -> Self {
         Self(BackendPoint::identity())
     }
+
+    /// Returns the sum of two points.
+    pub fn add(&self, other: &CurvePoint) -> CurvePoint {
+        self + other
+    }
+
+    /// Returns the product of the point and a scalar.
+    pub fn mul(&self, scalar: &CurveScalar) -> CurvePoint {
+        self * scalar
+    }
+}
+
+/// Errors that can happen when deserializing a [`CurvePoint`] or a [`PublicKey`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PointError {
+    /// The bytes are not a compressed SEC1 encoding
+    /// (wrong length or wrong prefix byte).
+    MalformedEncoding,
+    /// The encoding is well-formed, but the x coordinate is out of range,
+    /// or there is no point on the curve with this x coordinate.
+    NotOnCurve,
+}
+
+impl fmt::Display for PointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedEncoding => write!(f, "Malformed compressed point encoding"),
+            Self::NotOnCurve => write!(f, "The encoded point is not on the curve"),
+        }
+    }
+}
+
+impl CurvePoint {
+    /// Deserializes a point from its compressed SEC1 representation,
+    /// reporting the reason of a failure.
+    ///
+    /// [`SerializableToArray::from_bytes()`] is equivalent, but discards the error.
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, PointError> {
+        let bytes = bytes.as_ref();
+        if bytes.len() != <Self as SerializableToArray>::Size::to_usize() {
+            return Err(PointError::MalformedEncoding);
+        }
+        let ep = EncodedPoint::<CurveType>::from_bytes(bytes)
+            .map_err(|_| PointError::MalformedEncoding)?;
+        if !ep.is_compressed() {
+            return Err(PointError::MalformedEncoding);
+        }
+        let cp_opt: Option<BackendPoint> = BackendPoint::from_encoded_point(&ep);
+        cp_opt.map(Self).ok_or(PointError::NotOnCurve)
+    }
+}
+
+impl Zeroize for CurvePoint {
+    fn zeroize(&mut self) {
+        // The backend point does not implement `Zeroize`, and its coordinates are private,
+        // so this is only a best-effort plain overwrite. The fence does not prevent
+        // the compiler from removing the store as dead when the point is dropped right after
+        // (which is how `Zeroizing` uses it), so there is no guarantee that the memory is wiped,
+        // unlike for `CurveScalar`, where the backend zeroizes with volatile writes.
+        self.0 = BackendPoint::identity();
+        atomic::compiler_fence(atomic::Ordering::SeqCst);
+    }
 }
 
 impl Add<&CurveScalar> for &CurveScalar {
@@ -114,6 +231,19 @@ impl Add<&CurvePoint> for &CurvePoint {
     }
 }
 
+/// Sums the points starting from the identity, so an empty iterator gives the identity.
+impl Sum for CurvePoint {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::identity(), |sum, point| &sum + &point)
+    }
+}
+
+impl<'a> Sum<&'a CurvePoint> for CurvePoint {
+    fn sum<I: Iterator<Item = &'a CurvePoint>>(iter: I) -> Self {
+        iter.fold(Self::identity(), |sum, point| &sum + point)
+    }
+}
+
 impl Sub<&CurveScalar> for &CurveScalar {
     type Output = CurveScalar;
 
@@ -150,15 +280,64 @@ impl SerializableToArray for CurvePoint {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        let ep = EncodedPoint::<CurveType>::from_bytes(arr.as_slice()).ok()?;
-        let cp_opt: Option<BackendPoint> = BackendPoint::from_encoded_point(&ep);
-        cp_opt.map(Self)
+        Self::try_from_bytes(arr).ok()
     }
 }
 
-#[derive(Clone, Debug, PartialEq)]
+/// An ECDSA signature.
+///
+/// Serialized with [`SerializableToArray`] as 64 bytes:
+/// the big-endian `r` and `s` components, 32 bytes each.
+/// Deserialization rejects components that are zero or not less than the curve order.
+///
+/// Signatures are always in the canonical low-S form (as in BIP 0062):
+/// the ones created by this crate are normalized on signing,
+/// and deserialization rejects signatures with a high `s`
+/// (which would not pass the verification anyway).
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Signature(BackendSignature<CurveType>);
 
+/// Errors that can happen when deserializing a [`Signature`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The bytes have a wrong length, or one of the components
+    /// is zero or not less than the curve order.
+    MalformedEncoding,
+    /// The `s` component is greater than half the curve order
+    /// (the signature is not in the canonical low-S form).
+    HighS,
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MalformedEncoding => write!(f, "Malformed signature encoding"),
+            Self::HighS => write!(f, "The signature is not in the low-S form"),
+        }
+    }
+}
+
+impl Signature {
+    /// Deserializes a signature from the 64-byte `r || s` representation,
+    /// reporting the reason of a failure.
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, SignatureError> {
+        let signature = BackendSignature::<CurveType>::from_bytes(bytes.as_ref())
+            .map_err(|_| SignatureError::MalformedEncoding)?;
+        if signature.s().is_high().into() {
+            return Err(SignatureError::HighS);
+        }
+        Ok(Self(signature))
+    }
+}
+
+impl TryFrom<&[u8]> for Signature {
+    type Error = SignatureError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::try_from_bytes(bytes)
+    }
+}
+
 impl SerializableToArray for Signature {
     type Size = SignatureSize<CurveType>;
 
@@ -167,7 +346,30 @@ impl SerializableToArray for Signature {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        BackendSignature::<CurveType>::from_bytes(arr.as_slice())
+        Self::try_from_bytes(arr).ok()
+    }
+}
+
+/// The size of a serialized [`Signature`] in bytes.
+pub const SIGNATURE_SIZE: usize = 64;
+
+impl SerializableToFixedBytes<SIGNATURE_SIZE> for Signature {}
+const _: [(); SIGNATURE_SIZE] = [(); <<Signature as SerializableToArray>::Size as Unsigned>::USIZE];
+
+/// An ECDSA signature that allows one to recover the public key of the signer
+/// (see [`PublicKey::recover()`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RecoverableSignature(recoverable::Signature);
+
+impl SerializableToArray for RecoverableSignature {
+    type Size = U65;
+
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        *GenericArray::<u8, Self::Size>::from_slice(self.0.as_ref())
+    }
+
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
+        recoverable::Signature::from_bytes(arr.as_slice())
             .ok()
             .map(Self)
     }
@@ -175,7 +377,11 @@ impl SerializableToArray for Signature {
 
 /// A secret key.
 #[derive(Clone, Debug)]
-pub struct SecretKey(BackendSecretKey<CurveType>);
+pub struct SecretKey {
+    secret_key: BackendSecretKey<CurveType>,
+    // Cached, since deriving it takes a scalar multiplication.
+    public_key: PublicKey,
+}
 
 impl PartialEq for SecretKey {
     fn eq(&self, other: &Self) -> bool {
@@ -184,10 +390,65 @@ impl PartialEq for SecretKey {
 }
 
 impl SecretKey {
+    fn new(secret_key: BackendSecretKey<CurveType>) -> Self {
+        let public_key = PublicKey(secret_key.public_key());
+        Self {
+            secret_key,
+            public_key,
+        }
+    }
+
     /// Generates a secret key using the default RNG and returns it.
     pub fn random() -> Self {
         let secret_key = BackendSecretKey::<CurveType>::random(&mut OsRng);
-        Self(secret_key)
+        Self::new(secret_key)
+    }
+
+    /// Returns the public key corresponding to this secret key.
+    ///
+    /// The key is derived once when the secret key is created,
+    /// so this does not involve any curve arithmetic.
+    pub fn public_key(&self) -> &PublicKey {
+        &self.public_key
+    }
+
+    /// Consumes the secret key and returns it along with the corresponding public key.
+    ///
+    /// Useful for a one-shot key generation, e.g. `SecretKey::random().into_keypair()`.
+    pub fn into_keypair(self) -> (SecretKey, PublicKey) {
+        let public_key = self.public_key;
+        (self, public_key)
+    }
+
+    /// Deterministically derives a secret key from a master `seed`
+    /// along a derivation `path` like `m/44'/60'/0'/0/1`
+    /// (hardened components can be marked with `'` or `h`),
+    /// using SLIP-0010 (BIP32-compatible) derivation for secp256k1.
+    ///
+    /// Returns `None` if the path cannot be parsed.
+    pub fn from_seed(seed: &[u8], path: &str) -> Option<Self> {
+        let scalar = derive_secret_scalar(seed, path)?;
+        Self::from_array(&scalar.to_array())
+    }
+
+    /// Returns the sum of the given secret keys, which is the secret key
+    /// for the sum of their public keys (see [`PublicKey::aggregate()`]).
+    ///
+    /// Returns `None` if `keys` is empty, or if the sum is zero.
+    pub fn aggregate(keys: &[SecretKey]) -> Option<Self> {
+        let mut sum = Zeroizing::new(CurveScalar::default());
+        for key in keys {
+            *sum = &*sum + &key.to_secret_scalar();
+        }
+        Self::from_array(&sum.to_array())
+    }
+
+    /// Signs a message (hashed with SHA3-256) using the default RNG,
+    /// producing a signature from which the signer's public key can be recovered.
+    pub fn sign_recoverable(&self, message: &[u8]) -> RecoverableSignature {
+        let signer = BackendRecoverableSigningKey::from(&self.secret_key);
+        let digest = Digest::chain(Sha3_256::new(), message);
+        RecoverableSignature(signer.sign_digest_with_rng(OsRng, digest))
     }
 
     /// Returns a reference to the underlying scalar of the secret key.
@@ -198,7 +459,7 @@ impl SecretKey {
         // But we use this secret scalar to multiply not only points, but other scalars too.
         // So there's no point in hiding the actual value here as long as
         // it is going to be effectively dereferenced in other places.
-        CurveScalar(*self.0.secret_scalar())
+        CurveScalar(*self.secret_key.secret_scalar())
     }
 
     /// Signs a message using the default RNG.
@@ -206,7 +467,7 @@ impl SecretKey {
         &self,
         digest: impl BlockInput + FixedOutput<OutputSize = U32> + Clone + Default + Reset + Update,
     ) -> Signature {
-        let signer = SigningKey::<CurveType>::from(self.0.clone());
+        let signer = SigningKey::<CurveType>::from(self.secret_key.clone());
         Signature(signer.sign_digest_with_rng(OsRng, digest))
     }
 }
@@ -215,24 +476,81 @@ impl SerializableToArray for SecretKey {
     type Size = <CurveScalar as SerializableToArray>::Size;
 
     fn to_array(&self) -> GenericArray<u8, Self::Size> {
-        self.0.to_bytes()
+        self.secret_key.to_bytes()
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
         BackendSecretKey::<CurveType>::from_bytes(arr.as_slice())
             .ok()
-            .map(Self)
+            .map(Self::new)
     }
 }
 
+/// The size of a serialized [`SecretKey`] in bytes.
+pub const SECRET_KEY_SIZE: usize = 32;
+
+impl SerializableToFixedBytes<SECRET_KEY_SIZE> for SecretKey {}
+const _: [(); SECRET_KEY_SIZE] =
+    [(); <<SecretKey as SerializableToArray>::Size as Unsigned>::USIZE];
+
 /// A public key.
+///
+/// Deserialization with [`SerializableToArray`] performs the same validation
+/// as for [`CurvePoint`], so a deserialized key is always a point of the prime-order group.
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub struct PublicKey(BackendPublicKey<CurveType>);
 
 impl PublicKey {
     /// Creates a public key from a secret key.
+    ///
+    /// Equivalent to [`SecretKey::public_key()`].
     pub fn from_secret_key(secret_key: &SecretKey) -> Self {
-        Self(secret_key.0.public_key())
+        *secret_key.public_key()
+    }
+
+    /// Deserializes a public key from its compressed SEC1 representation,
+    /// reporting the reason of a failure (see [`CurvePoint::try_from_bytes()`]).
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, PointError> {
+        CurvePoint::try_from_bytes(bytes)
+            .map(|cp| Self(BackendPublicKey::<CurveType>::from_affine(cp.0.to_affine())))
+    }
+
+    /// Returns the sum of the given public keys,
+    /// so that a capsule can be encapsulated to all of them at once.
+    ///
+    /// This is key aggregation, not a threshold scheme:
+    /// the matching secret key is the sum of all the secret keys
+    /// (see [`SecretKey::aggregate()`]), so decryption with the original key
+    /// requires every key holder to contribute.
+    /// If some of the keys come from untrusted parties, one of them can pick its key
+    /// as its own key minus the sum of the others and control the aggregate alone
+    /// (a rogue key attack), so every key must come with a proof of possession
+    /// of the matching secret key (e.g. a signature created with it).
+    ///
+    /// Returns `None` if `keys` is empty, or if the sum is the identity point.
+    pub fn aggregate(keys: &[PublicKey]) -> Option<Self> {
+        let sum: CurvePoint = keys.iter().map(PublicKey::to_point).sum();
+        if sum == CurvePoint::identity() {
+            return None;
+        }
+        Some(Self(BackendPublicKey::<CurveType>::from_affine(
+            sum.0.to_affine(),
+        )))
+    }
+
+    /// Recovers the public key of the signer from a message and a signature on it
+    /// created by [`SecretKey::sign_recoverable()`].
+    ///
+    /// Returns `None` if the recovery fails, or if the signature does not verify
+    /// with the recovered key.
+    pub fn recover(message: &[u8], signature: &RecoverableSignature) -> Option<Self> {
+        let digest = Digest::chain(Sha3_256::new(), message);
+        let verifying_key = signature
+            .0
+            .recover_verify_key_from_digest(digest.clone())
+            .ok()?;
+        verifying_key.verify_digest(digest, &signature.0).ok()?;
+        Self::from_bytes(verifying_key.to_bytes())
     }
 
     /// Returns the underlying curve point of the public key.
@@ -259,8 +577,39 @@ impl SerializableToArray for PublicKey {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        CurvePoint::from_array(&arr)
-            .map(|cp| Self(BackendPublicKey::<CurveType>::from_affine(cp.0.to_affine())))
+        Self::try_from_bytes(arr).ok()
+    }
+}
+
+/// The size of a serialized [`PublicKey`] in bytes.
+pub const PUBLIC_KEY_SIZE: usize = 33;
+
+impl SerializableToFixedBytes<PUBLIC_KEY_SIZE> for PublicKey {}
+const _: [(); PUBLIC_KEY_SIZE] =
+    [(); <<PublicKey as SerializableToArray>::Size as Unsigned>::USIZE];
+
+/// The encoding format of a serialized public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyFormat {
+    /// SEC1 compressed point: `0x02` or `0x03` prefix followed by the x coordinate.
+    Compressed,
+    /// SEC1 uncompressed point: `0x04` prefix followed by the x and y coordinates.
+    Uncompressed,
+    /// Neither of the above.
+    Invalid,
+}
+
+/// Determines the format of a serialized public key from its SEC1 prefix byte and length.
+///
+/// Only the framing is checked; the coordinates are not validated,
+/// so a blob reported as `Compressed` or `Uncompressed` may still be rejected
+/// by [`PublicKey::from_bytes`](crate::SerializableToArray::from_bytes).
+pub fn inspect_public_key_bytes(bytes: &[u8]) -> KeyFormat {
+    const COORDINATE_SIZE: usize = 32;
+    match bytes.first() {
+        Some(0x02) | Some(0x03) if bytes.len() == 1 + COORDINATE_SIZE => KeyFormat::Compressed,
+        Some(0x04) if bytes.len() == 1 + 2 * COORDINATE_SIZE => KeyFormat::Uncompressed,
+        _ => KeyFormat::Invalid,
     }
 }
 
@@ -270,8 +619,19 @@ mod tests {
     use sha3::Sha3_256;
     use signature::digest::Digest;
 
-    use super::{PublicKey, SecretKey};
-    use crate::SerializableToArray;
+    use elliptic_curve::sec1::ToEncodedPoint;
+
+    use super::{
+        inspect_public_key_bytes, CurvePoint, CurveScalar, KeyFormat, PointError, PublicKey,
+        RecoverableSignature, SecretKey, Signature, SignatureError,
+    };
+    use super::{PUBLIC_KEY_SIZE, SECRET_KEY_SIZE};
+    use crate::traits::SerializableToFixedBytes;
+    use crate::{decrypt_original, encrypt, Parameters, SerializableToArray};
+    use alloc::vec::Vec;
+    use core::convert::TryFrom;
+    use generic_array::GenericArray;
+    use zeroize::{Zeroize, Zeroizing};
 
     #[test]
     fn test_serialize_secret_key() {
@@ -279,6 +639,77 @@ mod tests {
         let sk_arr = sk.to_array();
         let sk_back = SecretKey::from_array(&sk_arr).unwrap();
         assert_eq!(sk, sk_back);
+
+        // Keys are not tied to parameters, so the bytes are enough,
+        // and the cached public key is recomputed
+        assert_eq!(sk_back.public_key(), sk.public_key());
+        let sk_back = SecretKey::from_bytes(&sk_arr[..]).unwrap();
+        assert_eq!(sk_back.public_key(), &PublicKey::from_secret_key(&sk));
+        assert!(SecretKey::from_bytes(&sk_arr[1..]).is_none());
+
+        let sk_bytes: [u8; SECRET_KEY_SIZE] = sk.to_fixed_bytes();
+        assert_eq!(&sk_bytes[..], sk_arr.as_slice());
+        assert_eq!(SecretKey::from_fixed_bytes(&sk_bytes).unwrap(), sk);
+    }
+
+    #[test]
+    fn test_zeroize() {
+        fn assert_zeroize<T: Zeroize>() {}
+        assert_zeroize::<CurveScalar>();
+        assert_zeroize::<CurvePoint>();
+
+        let mut scalar = CurveScalar::random_nonzero();
+        scalar.zeroize();
+        assert!(scalar.is_zero());
+
+        let mut point = CurvePoint::generator().mul(&CurveScalar::random_nonzero());
+        point.zeroize();
+        assert_eq!(point, CurvePoint::identity());
+
+        // The wrapper is transparent for arithmetic
+        let s = CurveScalar::random_nonzero();
+        let wrapped = Zeroizing::new(s);
+        assert_eq!(
+            &CurvePoint::generator() * &wrapped,
+            &CurvePoint::generator() * &s
+        );
+    }
+
+    #[test]
+    fn test_cached_public_key() {
+        let sk = SecretKey::random();
+        let fresh = &CurvePoint::generator() * &sk.to_secret_scalar();
+        assert_eq!(sk.public_key().to_point(), fresh);
+
+        // The cache is restored on deserialization
+        let sk_back = SecretKey::from_array(&sk.to_array()).unwrap();
+        assert_eq!(sk_back.public_key(), sk.public_key());
+        assert_eq!(PublicKey::from_secret_key(&sk_back), *sk.public_key());
+    }
+
+    #[test]
+    fn test_into_keypair() {
+        let (sk, pk) = SecretKey::random().into_keypair();
+        assert_eq!(pk, PublicKey::from_secret_key(&sk));
+        assert_eq!(
+            pk.to_point(),
+            &CurvePoint::generator() * &sk.to_secret_scalar()
+        );
+    }
+
+    #[test]
+    fn test_secret_key_from_seed() {
+        let seed = b"an extremely random seed";
+
+        let sk1 = SecretKey::from_seed(seed, "m/44'/0'/0'/0/0").unwrap();
+        let sk2 = SecretKey::from_seed(seed, "m/44'/0'/0'/0/1").unwrap();
+        assert!(sk1 != sk2);
+
+        // The derivation is reproducible.
+        assert_eq!(sk1, SecretKey::from_seed(seed, "m/44'/0'/0'/0/0").unwrap());
+        assert_eq!(sk2, SecretKey::from_seed(seed, "m/44h/0h/0h/0/1").unwrap());
+
+        assert!(SecretKey::from_seed(seed, "44'/0'").is_none());
     }
 
     #[test]
@@ -288,17 +719,278 @@ mod tests {
         let pk_arr = pk.to_array();
         let pk_back = PublicKey::from_array(&pk_arr).unwrap();
         assert_eq!(pk, pk_back);
+
+        let pk_bytes: [u8; PUBLIC_KEY_SIZE] = pk.to_fixed_bytes();
+        assert_eq!(&pk_bytes[..], pk_arr.as_slice());
+        assert_eq!(PublicKey::from_fixed_bytes(&pk_bytes).unwrap(), pk);
+    }
+
+    #[test]
+    fn test_reject_off_curve_points() {
+        // x = 5 is a valid field element, but x^3 + 7 is not a quadratic residue,
+        // so there is no point with this x coordinate.
+        for &prefix in [2u8, 3u8].iter() {
+            let mut bytes = [0u8; 33];
+            bytes[0] = prefix;
+            bytes[32] = 5;
+            assert!(CurvePoint::from_bytes(bytes).is_none());
+            assert!(PublicKey::from_bytes(bytes).is_none());
+
+            // x = 4 is on the curve, as a sanity check
+            bytes[32] = 4;
+            assert!(CurvePoint::from_bytes(bytes).is_some());
+            assert!(PublicKey::from_bytes(bytes).is_some());
+        }
+
+        // x equal to the field modulus is out of range
+        let mut bytes = [0xffu8; 33];
+        bytes[0] = 2;
+        bytes[28] = 0xfe;
+        bytes[31] = 0xfc;
+        bytes[32] = 0x2f;
+        assert!(CurvePoint::from_bytes(bytes).is_none());
+        assert!(PublicKey::from_bytes(bytes).is_none());
+
+        // Not a compressed point prefix
+        let mut bytes = CurvePoint::generator().to_array();
+        bytes[0] = 4;
+        assert!(CurvePoint::from_array(&bytes).is_none());
+        assert!(PublicKey::from_array(&bytes).is_none());
+    }
+
+    #[test]
+    fn test_point_errors() {
+        let valid = CurvePoint::generator().to_array();
+        assert_eq!(
+            CurvePoint::try_from_bytes(valid),
+            Ok(CurvePoint::generator())
+        );
+        assert!(PublicKey::try_from_bytes(valid).is_ok());
+
+        // Wrong length
+        assert_eq!(
+            CurvePoint::try_from_bytes(&valid[..32]),
+            Err(PointError::MalformedEncoding)
+        );
+        assert_eq!(
+            PublicKey::try_from_bytes(&valid[..32]),
+            Err(PointError::MalformedEncoding)
+        );
+        assert_eq!(
+            CurvePoint::try_from_bytes(b""),
+            Err(PointError::MalformedEncoding)
+        );
+
+        // Wrong prefix
+        let mut bytes = valid;
+        bytes[0] = 5;
+        assert_eq!(
+            CurvePoint::try_from_bytes(bytes),
+            Err(PointError::MalformedEncoding)
+        );
+
+        // Well-formed, but x^3 + 7 is not a square for x = 5
+        let mut bytes = [0u8; 33];
+        bytes[0] = 2;
+        bytes[32] = 5;
+        assert_eq!(
+            CurvePoint::try_from_bytes(bytes),
+            Err(PointError::NotOnCurve)
+        );
+        assert_eq!(
+            PublicKey::try_from_bytes(bytes),
+            Err(PointError::NotOnCurve)
+        );
     }
 
     #[test]
     fn test_sign_and_verify() {
         let sk = SecretKey::random();
         let message = b"asdafdahsfdasdfasd";
-        let digest = Sha3_256::new().chain(message);
+        let digest = Digest::chain(Sha3_256::new(), message);
         let signature = sk.sign_digest(digest);
 
         let pk = PublicKey::from_secret_key(&sk);
-        let digest = Sha3_256::new().chain(message);
+        let digest = Digest::chain(Sha3_256::new(), message);
         assert!(pk.verify_digest(digest, &signature));
     }
+
+    #[test]
+    fn test_serialize_signature() {
+        let sk = SecretKey::random();
+        let digest = Digest::chain(Sha3_256::new(), b"peace at dawn");
+        let signature = sk.sign_digest(digest);
+
+        let bytes: [u8; 64] = signature.to_fixed_bytes();
+        assert_eq!(Signature::try_from_bytes(bytes), Ok(signature));
+        assert_eq!(Signature::try_from(&bytes[..]), Ok(signature));
+        assert_eq!(Signature::from_fixed_bytes(&bytes), Some(signature));
+
+        // Wrong length
+        assert_eq!(
+            Signature::try_from(&bytes[1..]),
+            Err(SignatureError::MalformedEncoding)
+        );
+
+        // A zero `r`
+        let mut zero_r = bytes;
+        zero_r[..32].copy_from_slice(&[0; 32]);
+        assert_eq!(
+            Signature::try_from_bytes(zero_r),
+            Err(SignatureError::MalformedEncoding)
+        );
+
+        // The same signature with `s` replaced by `n - s`, which is also valid ECDSA,
+        // but not canonical
+        let s = CurveScalar::from_array(GenericArray::from_slice(&bytes[32..])).unwrap();
+        let high_s = &CurveScalar::default() - &s;
+        let mut high_s_bytes = bytes;
+        high_s_bytes[32..].copy_from_slice(&high_s.to_array());
+        assert_eq!(
+            Signature::try_from_bytes(high_s_bytes),
+            Err(SignatureError::HighS)
+        );
+        assert!(Signature::from_fixed_bytes(&high_s_bytes).is_none());
+    }
+
+    #[test]
+    fn test_inspect_public_key_bytes() {
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        let compressed = pk.to_array();
+        assert_eq!(inspect_public_key_bytes(&compressed), KeyFormat::Compressed);
+
+        let uncompressed = pk.to_point().0.to_affine().to_encoded_point(false);
+        assert_eq!(uncompressed.as_bytes()[0], 0x04);
+        assert_eq!(
+            inspect_public_key_bytes(uncompressed.as_bytes()),
+            KeyFormat::Uncompressed
+        );
+
+        // Wrong length for the prefix
+        assert_eq!(
+            inspect_public_key_bytes(&uncompressed.as_bytes()[..33]),
+            KeyFormat::Invalid
+        );
+        assert_eq!(
+            inspect_public_key_bytes(&compressed[..32]),
+            KeyFormat::Invalid
+        );
+
+        // Unknown prefix
+        let mut bad_prefix = compressed;
+        bad_prefix[0] = 0x05;
+        assert_eq!(inspect_public_key_bytes(&bad_prefix), KeyFormat::Invalid);
+
+        assert_eq!(inspect_public_key_bytes(&[]), KeyFormat::Invalid);
+    }
+
+    #[test]
+    fn test_recover_public_key() {
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+        let message = b"asdafdahsfdasdfasd";
+
+        let signature = sk.sign_recoverable(message);
+        assert_eq!(PublicKey::recover(message, &signature), Some(pk));
+
+        let signature_back = RecoverableSignature::from_array(&signature.to_array()).unwrap();
+        assert_eq!(PublicKey::recover(message, &signature_back), Some(pk));
+
+        // A different message recovers a different key (or none at all)
+        assert!(PublicKey::recover(b"another message", &signature) != Some(pk));
+    }
+
+    #[test]
+    fn test_aggregate_keys() {
+        let sks: Vec<SecretKey> = (0..3).map(|_| SecretKey::random()).collect();
+        let pks: Vec<PublicKey> = sks.iter().map(PublicKey::from_secret_key).collect();
+
+        let aggregate_sk = SecretKey::aggregate(&sks).unwrap();
+        let aggregate_pk = PublicKey::aggregate(&pks).unwrap();
+        assert_eq!(PublicKey::from_secret_key(&aggregate_sk), aggregate_pk);
+
+        let params = Parameters::new();
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &aggregate_pk, plaintext).unwrap();
+        let plaintext_back = decrypt_original(&aggregate_sk, &capsule, &ciphertext).unwrap();
+        assert_eq!(&plaintext_back as &[u8], plaintext);
+
+        // None of the individual keys can decrypt
+        for sk in sks.iter() {
+            assert!(decrypt_original(sk, &capsule, &ciphertext).is_none());
+        }
+
+        // A single key aggregates to itself
+        assert_eq!(SecretKey::aggregate(&sks[..1]).unwrap(), sks[0]);
+        assert_eq!(PublicKey::aggregate(&pks[..1]).unwrap(), pks[0]);
+
+        // Empty sets and sums to zero are rejected
+        assert!(SecretKey::aggregate(&[]).is_none());
+        assert!(PublicKey::aggregate(&[]).is_none());
+        let negated_sk = SecretKey::from_array(
+            &(&CurveScalar::default() - &sks[0].to_secret_scalar()).to_array(),
+        )
+        .unwrap();
+        let negated_pk = PublicKey::from_secret_key(&negated_sk);
+        assert!(SecretKey::aggregate(&[sks[0].clone(), negated_sk]).is_none());
+        assert!(PublicKey::aggregate(&[pks[0], negated_pk]).is_none());
+    }
+
+    #[test]
+    fn test_scalar_from_u64() {
+        assert_eq!(CurveScalar::from_u64(1), CurveScalar::one());
+        assert_eq!(CurveScalar::from_u64(0), CurveScalar::default());
+        assert_eq!(
+            &CurveScalar::from_u64(2) * &CurveScalar::from_u64(3),
+            CurveScalar::from_u64(6)
+        );
+
+        let s = CurveScalar::from_u64(0x0102030405060708);
+        let s_arr = s.to_array();
+        assert!(s_arr[..24].iter().all(|b| *b == 0));
+        assert_eq!(&s_arr[24..], &[1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_scalar_from_bytes_wide() {
+        // A value that fits into the lower half is not changed
+        let mut bytes = [0u8; 64];
+        bytes[63] = 42;
+        assert_eq!(
+            CurveScalar::from_bytes_wide(&bytes),
+            CurveScalar::from_u64(42)
+        );
+
+        // 2^256 mod n, and (2^256 + 1) mod n
+        let mut bytes = [0u8; 64];
+        bytes[31] = 1;
+        let two_pow_256 = CurveScalar::from_bytes_wide(&bytes);
+        bytes[63] = 1;
+        assert_eq!(
+            CurveScalar::from_bytes_wide(&bytes),
+            &two_pow_256 + &CurveScalar::one()
+        );
+
+        // Reference values computed with arbitrary-precision integers
+        let vectors: [(u8, &str); 2] = [
+            (
+                0xff,
+                "9d671cd581c69bc5e697f5e45bcd07c6741496c20e7cf878896cf21467d7d13f",
+            ),
+            (
+                0xab,
+                "badebf019f4ef906373ebfff88dacf0028c3d8710e31a651bd3a902e5d9bef5a",
+            ),
+        ];
+        for (byte, expected) in vectors.iter() {
+            let s = CurveScalar::from_bytes_wide(&[*byte; 64]);
+            let s_arr = s.to_array();
+            for i in 0..32 {
+                let expected_byte = u8::from_str_radix(&expected[2 * i..2 * i + 2], 16).unwrap();
+                assert_eq!(s_arr[i], expected_byte);
+            }
+        }
+    }
 }
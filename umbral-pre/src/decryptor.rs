@@ -0,0 +1,278 @@
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::capsule::Capsule;
+use crate::capsule_frag::CapsuleFrag;
+use crate::curve::{PublicKey, SecretKey};
+use crate::dem::UmbralDEM;
+use crate::traits::SerializableToArray;
+
+/// Errors returned by [`ReencryptedDecryptor::feed()`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecryptorError {
+    /// The capsule fragment failed the verification and was discarded.
+    InvalidCfrag,
+    /// The symmetric key was reconstructed, but the ciphertext could not be decrypted with it
+    /// (it was modified, or does not correspond to the capsule).
+    /// This failure is final.
+    DecryptionFailed,
+}
+
+impl fmt::Display for DecryptorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidCfrag => write!(f, "Capsule fragment verification failed"),
+            Self::DecryptionFailed => write!(f, "Decryption of the ciphertext failed"),
+        }
+    }
+}
+
+/// A stateful version of [`decrypt_reencrypted()`](`crate::decrypt_reencrypted()`)
+/// for receiving capsule fragments one at a time,
+/// which decrypts the ciphertext as soon as enough of them have been received.
+///
+/// Since the threshold is not encoded in the capsule fragments,
+/// the decryption is attempted every time a new valid fragment is fed.
+///
+/// Fragments produced by different sets of key fragments for the same delegation
+/// (e.g. when the key fragments were regenerated) cannot be combined,
+/// so they are kept in separate groups, and the decryption succeeds
+/// as soon as any of the groups reaches the threshold.
+pub struct ReencryptedDecryptor {
+    decrypting_sk: SecretKey,
+    delegating_pk: PublicKey,
+    signing_pk: PublicKey,
+    capsule: Capsule,
+    ciphertext: Box<[u8]>,
+    // Groups of fragments with the same precursor (that is, from the same set of key fragments).
+    cfrag_groups: Vec<Vec<CapsuleFrag>>,
+    result: Option<Result<Box<[u8]>, DecryptorError>>,
+}
+
+impl ReencryptedDecryptor {
+    /// Creates a decryptor for the given ciphertext and capsule.
+    ///
+    /// `signing_pk` is used to verify the fed capsule fragments
+    /// (see [`CapsuleFrag::verify()`]).
+    pub fn new(
+        decrypting_sk: &SecretKey,
+        delegating_pk: &PublicKey,
+        signing_pk: &PublicKey,
+        capsule: &Capsule,
+        ciphertext: impl AsRef<[u8]>,
+    ) -> Self {
+        Self {
+            decrypting_sk: decrypting_sk.clone(),
+            delegating_pk: *delegating_pk,
+            signing_pk: *signing_pk,
+            capsule: *capsule,
+            ciphertext: ciphertext.as_ref().into(),
+            cfrag_groups: Vec::new(),
+            result: None,
+        }
+    }
+
+    /// Adds a capsule fragment and attempts the decryption.
+    ///
+    /// Returns `None` if more fragments are needed (including the case when
+    /// the fragment was already fed before), and `Some(Ok(plaintext))` if the decryption succeeded.
+    ///
+    /// An invalid fragment is reported as an error, but is otherwise ignored,
+    /// and the feeding can continue.
+    /// [`DecryptorError::DecryptionFailed`] is final.
+    /// Once the decryptor has finished, it returns the same result for all subsequent calls.
+    #[must_use]
+    pub fn feed(&mut self, cfrag: &CapsuleFrag) -> Option<Result<Box<[u8]>, DecryptorError>> {
+        if self.result.is_some() {
+            return self.result.clone();
+        }
+
+        let receiving_pk = self.decrypting_sk.public_key();
+        if !cfrag.verify(
+            &self.capsule,
+            &self.delegating_pk,
+            receiving_pk,
+            &self.signing_pk,
+        ) {
+            return Some(Err(DecryptorError::InvalidCfrag));
+        }
+
+        let group_idx = match self
+            .cfrag_groups
+            .iter()
+            .position(|group| group[0].precursor == cfrag.precursor)
+        {
+            Some(idx) => idx,
+            None => {
+                self.cfrag_groups.push(Vec::new());
+                self.cfrag_groups.len() - 1
+            }
+        };
+        let group = &mut self.cfrag_groups[group_idx];
+
+        if group.iter().any(|other| other.kfrag_id == cfrag.kfrag_id) {
+            return None;
+        }
+
+        group.push(cfrag.clone());
+
+        let key_seed =
+            self.capsule
+                .open_reencrypted(&self.decrypting_sk, &self.delegating_pk, group)?;
+
+        let dem = UmbralDEM::new(&key_seed.to_array());
+        let result = dem
+            .decrypt(&self.ciphertext, &self.capsule.to_array())
+            .ok_or(DecryptorError::DecryptionFailed);
+        self.result = Some(result);
+        self.result.clone()
+    }
+
+    /// Returns the number of distinct valid capsule fragments fed so far
+    /// (from all the sets of key fragments).
+    pub fn num_cfrags(&self) -> usize {
+        self.cfrag_groups.iter().map(|group| group.len()).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use super::{DecryptorError, ReencryptedDecryptor};
+    use crate::{
+        encrypt, generate_kfrags, reencrypt, CapsuleFrag, Parameters, PublicKey, SecretKey,
+    };
+
+    #[test]
+    fn test_incremental_decryption() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let signing_pk = PublicKey::from_secret_key(&signing_sk);
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        let threshold = 3;
+        let make_cfrags = || {
+            let kfrags = generate_kfrags(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                threshold,
+                5,
+                true,
+                true,
+            );
+            kfrags
+                .iter()
+                .map(|kfrag| reencrypt(&capsule, kfrag, None))
+                .collect::<Vec<CapsuleFrag>>()
+        };
+        let cfrags = make_cfrags();
+
+        let mut decryptor = ReencryptedDecryptor::new(
+            &receiving_sk,
+            &delegating_pk,
+            &signing_pk,
+            &capsule,
+            &ciphertext,
+        );
+
+        assert_eq!(decryptor.feed(&cfrags[0]), None);
+
+        // Repeating and invalid fragments do not count
+        assert_eq!(decryptor.feed(&cfrags[0]), None);
+        let (other_capsule, _ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let foreign_cfrag = reencrypt(
+            &other_capsule,
+            &generate_kfrags(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                threshold,
+                1,
+                true,
+                true,
+            )[0],
+            None,
+        );
+        #[cfg(not(umbral_insecure_no_proofs))]
+        assert_eq!(
+            decryptor.feed(&foreign_cfrag),
+            Some(Err(DecryptorError::InvalidCfrag))
+        );
+        #[cfg(umbral_insecure_no_proofs)]
+        let _ = foreign_cfrag;
+        assert_eq!(decryptor.num_cfrags(), 1);
+
+        for (i, cfrag) in cfrags[1..].iter().enumerate() {
+            let result = decryptor.feed(cfrag);
+            if i + 2 < threshold {
+                assert_eq!(result, None);
+            } else {
+                assert_eq!(result.unwrap().unwrap().as_ref(), plaintext);
+                break;
+            }
+        }
+        assert_eq!(decryptor.num_cfrags(), threshold);
+
+        // The result is kept
+        assert_eq!(
+            decryptor.feed(&cfrags[4]).unwrap().unwrap().as_ref(),
+            plaintext
+        );
+
+        // Fragments from another set of key fragments are kept separately,
+        // and any set reaching the threshold is enough
+        let other_cfrags = make_cfrags();
+        let mut decryptor = ReencryptedDecryptor::new(
+            &receiving_sk,
+            &delegating_pk,
+            &signing_pk,
+            &capsule,
+            &ciphertext,
+        );
+        assert_eq!(decryptor.feed(&cfrags[0]), None);
+        assert_eq!(decryptor.feed(&cfrags[1]), None);
+        assert_eq!(decryptor.feed(&other_cfrags[0]), None);
+        assert_eq!(decryptor.feed(&other_cfrags[1]), None);
+        assert_eq!(decryptor.num_cfrags(), 4);
+        assert_eq!(
+            decryptor.feed(&other_cfrags[2]).unwrap().unwrap().as_ref(),
+            plaintext
+        );
+
+        // A corrupted ciphertext is a final failure
+        let mut corrupted = ciphertext.to_vec();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 1;
+        let mut decryptor = ReencryptedDecryptor::new(
+            &receiving_sk,
+            &delegating_pk,
+            &signing_pk,
+            &capsule,
+            &corrupted,
+        );
+        for cfrag in cfrags[..threshold - 1].iter() {
+            assert_eq!(decryptor.feed(cfrag), None);
+        }
+        assert_eq!(
+            decryptor.feed(&cfrags[threshold - 1]),
+            Some(Err(DecryptorError::DecryptionFailed))
+        );
+        assert_eq!(
+            decryptor.feed(&cfrags[threshold]),
+            Some(Err(DecryptorError::DecryptionFailed))
+        );
+    }
+}
@@ -1,44 +1,278 @@
 use alloc::boxed::Box;
+use alloc::vec;
+use core::fmt;
 
 use aead::{Aead, AeadInPlace, Payload};
 use blake2::Blake2b;
 use chacha20poly1305::aead::NewAead;
-use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
-use generic_array::{typenum::Unsigned, GenericArray};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce, Tag};
+use digest::{BlockInput, Digest, FixedOutput, Reset, Update};
+use generic_array::sequence::Concat;
+use generic_array::{typenum::Unsigned, ArrayLength, GenericArray};
 use hkdf::Hkdf;
 use rand_core::OsRng;
 use rand_core::RngCore;
+use sha3::Sha3_256;
+use typenum::{op, U32};
+use zeroize::{Zeroize, Zeroizing};
+
+use crate::traits::SerializableToArray;
 
 type KdfSize = <ChaCha20Poly1305 as NewAead>::KeySize;
 
-fn kdf(seed: &[u8], salt: Option<&[u8]>, info: Option<&[u8]>) -> GenericArray<u8, KdfSize> {
-    let hk = Hkdf::<Blake2b>::new(salt, &seed);
+/// Fills `okm` with HKDF over the hash `D`.
+///
+/// Returns `None` if `okm` is longer than 255 times the output size of `D`.
+fn hkdf_expand<D>(
+    seed: &[u8],
+    salt: Option<&[u8]>,
+    info: Option<&[u8]>,
+    okm: &mut [u8],
+) -> Option<()>
+where
+    D: Update + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
+    let hk = Hkdf::<D>::new(salt, seed);
+    hk.expand(info.unwrap_or(&[]), okm).ok()
+}
 
+/// Derives the DEM key from the KEM shared secret with HKDF over the hash `D`.
+///
+/// The choice of `D` only affects the DEM key: [`derive_key()`] always uses BLAKE2b,
+/// and the other hash-based derivations (hashing to scalars in the proofs
+/// and the non-interactive secret `d`, see `hashing.rs`,
+/// and the SLIP-0010 derivation of secret keys, see `key_derivation.rs`)
+/// are independent of it.
+fn kdf_with<D>(seed: &[u8], salt: Option<&[u8]>, info: Option<&[u8]>) -> GenericArray<u8, KdfSize>
+where
+    D: Update + BlockInput + FixedOutput + Reset + Default + Clone,
+    D::BlockSize: ArrayLength<u8>,
+    D::OutputSize: ArrayLength<u8>,
+{
     let mut okm = GenericArray::<u8, KdfSize>::default();
 
-    let def_info = match info {
-        Some(x) => x,
-        None => &[],
-    };
-
     // We can only get an error here if `KdfSize` is too large, and it's known at compile-time.
-    hk.expand(&def_info, &mut okm).unwrap();
+    hkdf_expand::<D>(seed, salt, info, &mut okm).unwrap();
 
     okm
 }
 
+/// Derives the DEM key with the default hash (BLAKE2b).
+fn kdf(seed: &[u8], salt: Option<&[u8]>, info: Option<&[u8]>) -> GenericArray<u8, KdfSize> {
+    kdf_with::<Blake2b>(seed, salt, info)
+}
+
+/// Derives a key of `length` bytes from `shared_secret` with HKDF over BLAKE2b.
+///
+/// `shared_secret` is the key material known to all the parties deriving the keys.
+/// To tie the derived keys to a capsule, pass its serialized form
+/// (`capsule.to_array()`) as `salt` or as a part of `info`.
+/// Keys derived from the same `shared_secret` with distinct `info` strings are independent:
+/// knowing some of them reveals nothing about the others.
+///
+/// The result is zeroized when dropped.
+/// Returns `None` if `length` is greater than 255 times the hash size (16320 bytes).
+pub fn derive_key(
+    shared_secret: &[u8],
+    length: usize,
+    salt: Option<&[u8]>,
+    info: Option<&[u8]>,
+) -> Option<Zeroizing<Box<[u8]>>> {
+    let mut okm = Zeroizing::new(vec![0u8; length].into_boxed_slice());
+    hkdf_expand::<Blake2b>(shared_secret, salt, info, &mut okm)?;
+    Some(okm)
+}
+
+/// Hashes a sequence of authenticated data chunks as a single stream,
+/// so that the result only depends on their concatenation.
+fn digest_aad_chunks(aad_chunks: &[&[u8]]) -> GenericArray<u8, U32> {
+    let mut digest = Sha3_256::new();
+    for chunk in aad_chunks {
+        Digest::update(&mut digest, chunk);
+    }
+    digest.finalize()
+}
+
+type NonceSize = <ChaCha20Poly1305 as AeadInPlace>::NonceSize;
+type TagSize = <ChaCha20Poly1305 as AeadInPlace>::TagSize;
+
+/// Builds the nonce for a chunk of a stream: the big-endian chunk index,
+/// followed by zeros and the flag marking the last chunk.
+#[cfg(feature = "std")]
+fn chunk_nonce(index: u64, last: bool) -> Nonce {
+    let mut nonce = Nonce::default();
+    nonce[..8].copy_from_slice(&index.to_be_bytes());
+    nonce[NonceSize::to_usize() - 1] = last as u8;
+    nonce
+}
+
+/// The nonce and the authentication tag of a ciphertext,
+/// for the cases when they are stored separately from it
+/// (see [`encrypt_detached()`](`crate::encrypt_detached()`)).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DetachedTag {
+    nonce: Nonce,
+    tag: Tag,
+}
+
+impl SerializableToArray for DetachedTag {
+    type Size = op!(NonceSize + TagSize);
+
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.nonce.concat(self.tag)
+    }
+
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
+        let nonce_size = NonceSize::to_usize();
+        Some(Self {
+            nonce: *Nonce::from_slice(&arr[..nonce_size]),
+            tag: *Tag::from_slice(&arr[nonce_size..]),
+        })
+    }
+}
+
+/// The nonce of a ciphertext, for the cases when it is stored separately from it
+/// (see [`encrypt_in_place_external_nonce()`](`crate::encrypt_in_place_external_nonce()`)).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ExternalNonce(Nonce);
+
+impl SerializableToArray for ExternalNonce {
+    type Size = NonceSize;
+
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.0
+    }
+
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
+        Some(Self(*arr))
+    }
+}
+
+/// The symmetric key a plaintext is encrypted with,
+/// for the cases when it has to be stored separately
+/// (see [`encrypt_with_key_export()`](`crate::encrypt_with_key_export()`)).
+///
+/// The key is zeroized when the object is dropped.
+#[derive(Clone)]
+pub struct DemKey(GenericArray<u8, KdfSize>);
+
+impl DemKey {
+    pub(crate) fn from_key_seed(key_seed: &[u8]) -> Self {
+        Self(kdf(key_seed, None, None))
+    }
+
+    /// Derives the key from the KEM shared secret with HKDF over the hash `D`
+    /// (without a salt and with an empty `info`).
+    ///
+    /// The built-in derivation uses BLAKE2b;
+    /// this allows one to match an external implementation using a different hash
+    /// (see [`SymmetricBackend::derive_dem_key()`]).
+    pub fn derive_with<D>(key_seed: &[u8]) -> Self
+    where
+        D: Update + BlockInput + FixedOutput + Reset + Default + Clone,
+        D::BlockSize: ArrayLength<u8>,
+        D::OutputSize: ArrayLength<u8>,
+    {
+        Self(kdf_with::<D>(key_seed, None, None))
+    }
+}
+
+impl Drop for DemKey {
+    fn drop(&mut self) {
+        self.0.as_mut_slice().zeroize();
+    }
+}
+
+impl fmt::Debug for DemKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DemKey(...)")
+    }
+}
+
+impl SerializableToArray for DemKey {
+    type Size = KdfSize;
+
+    fn to_array(&self) -> GenericArray<u8, Self::Size> {
+        self.0
+    }
+
+    fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
+        Some(Self(*arr))
+    }
+}
+
+/// An authenticated symmetric encryption scheme (DEM) used to encrypt plaintexts
+/// with the keys encapsulated in capsules.
+///
+/// Allows one to replace the built-in [`ChaCha20Poly1305Backend`]
+/// (see [`encrypt_with_backend()`](`crate::encrypt_with_backend()`));
+/// the KEM part of the scheme stays the same.
+pub trait SymmetricBackend {
+    /// Derives the symmetric key from the KEM shared secret.
+    ///
+    /// The default implementation uses HKDF over BLAKE2b, same as [`encrypt()`](`crate::encrypt()`).
+    /// A backend can override it to use a different hash (see [`DemKey::derive_with()`]).
+    fn derive_dem_key(&self, key_seed: &[u8]) -> DemKey {
+        DemKey::from_key_seed(key_seed)
+    }
+
+    /// Encrypts `plaintext` with `key`, authenticating `aad` along with it.
+    ///
+    /// Returns `None` if the encryption fails.
+    fn encrypt(&self, key: &DemKey, plaintext: &[u8], aad: &[u8]) -> Option<Box<[u8]>>;
+
+    /// Decrypts `ciphertext` produced by [`encrypt()`](`Self::encrypt()`)
+    /// with the same `key` and `aad`.
+    ///
+    /// Returns `None` if the ciphertext or the authenticated data were modified,
+    /// or the key is wrong.
+    fn decrypt(&self, key: &DemKey, ciphertext: &[u8], aad: &[u8]) -> Option<Box<[u8]>>;
+}
+
+/// The built-in [`SymmetricBackend`] used by [`encrypt()`](`crate::encrypt()`)
+/// and the other functions without a backend parameter:
+/// ChaCha20-Poly1305 with a random nonce prepended to the ciphertext.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ChaCha20Poly1305Backend;
+
+impl SymmetricBackend for ChaCha20Poly1305Backend {
+    fn encrypt(&self, key: &DemKey, plaintext: &[u8], aad: &[u8]) -> Option<Box<[u8]>> {
+        UmbralDEM::from_key(key).encrypt(plaintext, aad)
+    }
+
+    fn decrypt(&self, key: &DemKey, ciphertext: &[u8], aad: &[u8]) -> Option<Box<[u8]>> {
+        UmbralDEM::from_key(key).decrypt(ciphertext, aad)
+    }
+}
+
 pub(crate) struct UmbralDEM {
     cipher: ChaCha20Poly1305,
 }
 
 impl UmbralDEM {
     pub fn new(key_seed: &[u8]) -> Self {
-        let key_bytes = kdf(&key_seed, None, None);
-        let key = Key::from_slice(&key_bytes);
-        let cipher = ChaCha20Poly1305::new(key);
+        Self::from_key(&DemKey::from_key_seed(key_seed))
+    }
+
+    pub fn from_key(key: &DemKey) -> Self {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&key.0));
         Self { cipher }
     }
 
+    /// The size of the shortest valid ciphertext (for an empty plaintext):
+    /// the nonce and the authentication tag.
+    pub fn min_ciphertext_size() -> usize {
+        NonceSize::to_usize() + TagSize::to_usize()
+    }
+
+    /// Encrypts `data`, prepending a fresh random nonce to the result.
+    ///
+    /// Since every call draws a new 96-bit nonce from the OS RNG,
+    /// the same `UmbralDEM` object can be safely reused for multiple messages
+    /// (as long as their number stays far below 2^32,
+    /// where random nonce collisions become a concern).
     pub fn encrypt(&self, data: &[u8], authenticated_data: &[u8]) -> Option<Box<[u8]>> {
         type NonceSize = <ChaCha20Poly1305 as AeadInPlace>::NonceSize;
         let mut nonce = GenericArray::<u8, NonceSize>::default();
@@ -57,6 +291,247 @@ impl UmbralDEM {
         Some(result.into_boxed_slice())
     }
 
+    /// Encrypts `data` authenticating the concatenation of `aad_chunks`.
+    ///
+    /// The chunks are hashed one by one and the AEAD only authenticates the digest,
+    /// so a large authenticated header does not have to be assembled in a single buffer.
+    /// Consequently, the ciphertext must be decrypted with
+    /// [`decrypt_with_aad_chunks()`](`Self::decrypt_with_aad_chunks()`)
+    /// (with any split of the same authenticated data into chunks).
+    pub fn encrypt_with_aad_chunks(&self, data: &[u8], aad_chunks: &[&[u8]]) -> Option<Box<[u8]>> {
+        self.encrypt(data, &digest_aad_chunks(aad_chunks))
+    }
+
+    /// Encrypts `buf[..len]` in place, without allocating.
+    ///
+    /// On success, `buf` contains the nonce, the ciphertext and the authentication tag,
+    /// and the total length of these is returned.
+    /// Returns `None` if `buf` does not have enough room for the nonce and the tag.
+    pub fn encrypt_in_place(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        authenticated_data: &[u8],
+    ) -> Option<usize> {
+        let nonce_size = <<ChaCha20Poly1305 as AeadInPlace>::NonceSize as Unsigned>::to_usize();
+        let tag_size = <<ChaCha20Poly1305 as AeadInPlace>::TagSize as Unsigned>::to_usize();
+        let result_len = len.checked_add(nonce_size + tag_size)?;
+
+        if buf.len() < result_len {
+            return None;
+        }
+
+        buf.copy_within(0..len, nonce_size);
+
+        let (nonce_bytes, rest) = buf.split_at_mut(nonce_size);
+        OsRng.fill_bytes(nonce_bytes);
+        let nonce = Nonce::from_slice(nonce_bytes);
+
+        let (data, tag_bytes) = rest.split_at_mut(len);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(nonce, authenticated_data, data)
+            .ok()?;
+        tag_bytes[..tag_size].copy_from_slice(&tag);
+
+        Some(result_len)
+    }
+
+    /// Encrypts `buf[..len]` in place, appending the authentication tag,
+    /// and returns the nonce separately along with the length of the result.
+    /// Returns `None` if `buf` does not have enough room for the tag.
+    pub fn encrypt_in_place_external_nonce(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        authenticated_data: &[u8],
+    ) -> Option<(ExternalNonce, usize)> {
+        let tag_size = TagSize::to_usize();
+        let result_len = len.checked_add(tag_size)?;
+
+        if buf.len() < result_len {
+            return None;
+        }
+
+        let mut nonce = Nonce::default();
+        OsRng.fill_bytes(&mut nonce);
+
+        let (data, tag_bytes) = buf.split_at_mut(len);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce, authenticated_data, data)
+            .ok()?;
+        tag_bytes[..tag_size].copy_from_slice(&tag);
+
+        Some((ExternalNonce(nonce), result_len))
+    }
+
+    /// Decrypts `buf[..len]` (produced by
+    /// [`encrypt_in_place_external_nonce()`](`Self::encrypt_in_place_external_nonce()`))
+    /// in place, returning the length of the plaintext.
+    pub fn decrypt_in_place_external_nonce(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        authenticated_data: &[u8],
+        nonce: &ExternalNonce,
+    ) -> Option<usize> {
+        let tag_size = TagSize::to_usize();
+
+        if len > buf.len() || len < tag_size {
+            return None;
+        }
+
+        let plaintext_len = len - tag_size;
+        let (data, tag_bytes) = buf[..len].split_at_mut(plaintext_len);
+        let tag = Tag::from_slice(tag_bytes);
+        self.cipher
+            .decrypt_in_place_detached(&nonce.0, authenticated_data, data, tag)
+            .ok()?;
+
+        Some(plaintext_len)
+    }
+
+    /// The size of the authentication tag appended to every ciphertext.
+    pub fn tag_size() -> usize {
+        TagSize::to_usize()
+    }
+
+    /// Encrypts `buf[..len]` in place as the chunk number `index` of a chunked stream,
+    /// appending the authentication tag and returning the length of the result.
+    ///
+    /// Instead of being random, the nonce is made of the chunk index and the `last` flag
+    /// (the STREAM construction), so the chunks cannot be reordered, dropped,
+    /// or have the stream truncated after them without the decryption noticing.
+    /// This is only safe if the key is never used for more than one stream.
+    #[cfg(feature = "std")]
+    pub fn encrypt_chunk_in_place(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        authenticated_data: &[u8],
+        index: u64,
+        last: bool,
+    ) -> Option<usize> {
+        let tag_size = TagSize::to_usize();
+        let result_len = len.checked_add(tag_size)?;
+
+        if buf.len() < result_len {
+            return None;
+        }
+
+        let nonce = chunk_nonce(index, last);
+        let (data, tag_bytes) = buf.split_at_mut(len);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce, authenticated_data, data)
+            .ok()?;
+        tag_bytes[..tag_size].copy_from_slice(&tag);
+
+        Some(result_len)
+    }
+
+    /// Decrypts `buf[..len]` (produced by
+    /// [`encrypt_chunk_in_place()`](`Self::encrypt_chunk_in_place()`)
+    /// with the same `index` and `last`) in place, returning the length of the plaintext.
+    #[cfg(feature = "std")]
+    pub fn decrypt_chunk_in_place(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        authenticated_data: &[u8],
+        index: u64,
+        last: bool,
+    ) -> Option<usize> {
+        let tag_size = TagSize::to_usize();
+
+        if len > buf.len() || len < tag_size {
+            return None;
+        }
+
+        let nonce = chunk_nonce(index, last);
+        let plaintext_len = len - tag_size;
+        let (data, tag_bytes) = buf[..len].split_at_mut(plaintext_len);
+        let tag = Tag::from_slice(tag_bytes);
+        self.cipher
+            .decrypt_in_place_detached(&nonce, authenticated_data, data, tag)
+            .ok()?;
+
+        Some(plaintext_len)
+    }
+
+    /// Encrypts `data` in place, keeping its length,
+    /// and returns the nonce and the authentication tag separately.
+    pub fn encrypt_detached(
+        &self,
+        data: &mut [u8],
+        authenticated_data: &[u8],
+    ) -> Option<DetachedTag> {
+        let mut nonce = Nonce::default();
+        OsRng.fill_bytes(&mut nonce);
+        let tag = self
+            .cipher
+            .encrypt_in_place_detached(&nonce, authenticated_data, data)
+            .ok()?;
+        Some(DetachedTag { nonce, tag })
+    }
+
+    /// Decrypts `data` (produced by [`encrypt_detached()`](`Self::encrypt_detached()`)) in place.
+    ///
+    /// Returns `None` if the authentication fails, in which case `data` is left unchanged.
+    pub fn decrypt_detached(
+        &self,
+        data: &mut [u8],
+        authenticated_data: &[u8],
+        tag: &DetachedTag,
+    ) -> Option<()> {
+        self.cipher
+            .decrypt_in_place_detached(&tag.nonce, authenticated_data, data, &tag.tag)
+            .ok()
+    }
+
+    /// Decrypts `buf[..len]` (produced by [`encrypt_in_place()`](`Self::encrypt_in_place()`))
+    /// in place, without allocating.
+    ///
+    /// On success, the plaintext is moved to the beginning of `buf`,
+    /// and its length is returned.
+    pub fn decrypt_in_place(
+        &self,
+        buf: &mut [u8],
+        len: usize,
+        authenticated_data: &[u8],
+    ) -> Option<usize> {
+        let nonce_size = <<ChaCha20Poly1305 as AeadInPlace>::NonceSize as Unsigned>::to_usize();
+        let tag_size = <<ChaCha20Poly1305 as AeadInPlace>::TagSize as Unsigned>::to_usize();
+
+        if len > buf.len() || len < nonce_size + tag_size {
+            return None;
+        }
+
+        let plaintext_len = len - nonce_size - tag_size;
+
+        let (nonce_bytes, rest) = buf[..len].split_at_mut(nonce_size);
+        let nonce = Nonce::from_slice(nonce_bytes);
+        let (data, tag_bytes) = rest.split_at_mut(plaintext_len);
+        let tag = Tag::from_slice(tag_bytes);
+        self.cipher
+            .decrypt_in_place_detached(nonce, authenticated_data, data, tag)
+            .ok()?;
+
+        buf.copy_within(nonce_size..nonce_size + plaintext_len, 0);
+        Some(plaintext_len)
+    }
+
+    /// Decrypts a ciphertext produced by
+    /// [`encrypt_with_aad_chunks()`](`Self::encrypt_with_aad_chunks()`).
+    pub fn decrypt_with_aad_chunks(
+        &self,
+        ciphertext: impl AsRef<[u8]>,
+        aad_chunks: &[&[u8]],
+    ) -> Option<Box<[u8]>> {
+        self.decrypt(ciphertext, &digest_aad_chunks(aad_chunks))
+    }
+
     pub fn decrypt(
         &self,
         ciphertext: impl AsRef<[u8]>,
@@ -84,9 +559,94 @@ impl UmbralDEM {
 #[cfg(test)]
 mod tests {
 
-    use super::kdf;
+    use blake2::Blake2b;
+    use sha2::Sha256;
+
+    use alloc::boxed::Box;
+
+    use super::{
+        derive_key, kdf, kdf_with, ChaCha20Poly1305Backend, DemKey, DetachedTag, ExternalNonce,
+        SymmetricBackend, UmbralDEM,
+    };
     use crate::curve::CurvePoint;
-    use crate::SerializableToArray;
+    use crate::{
+        decrypt_original, decrypt_original_with_backend, encrypt_with_backend, Parameters,
+        PublicKey, SecretKey, SerializableToArray,
+    };
+
+    /// The built-in cipher with the key derived by HKDF over SHA-256 instead of BLAKE2b.
+    struct Sha256KdfBackend;
+
+    impl SymmetricBackend for Sha256KdfBackend {
+        fn derive_dem_key(&self, key_seed: &[u8]) -> DemKey {
+            DemKey::derive_with::<Sha256>(key_seed)
+        }
+
+        fn encrypt(&self, key: &DemKey, plaintext: &[u8], aad: &[u8]) -> Option<Box<[u8]>> {
+            ChaCha20Poly1305Backend.encrypt(key, plaintext, aad)
+        }
+
+        fn decrypt(&self, key: &DemKey, ciphertext: &[u8], aad: &[u8]) -> Option<Box<[u8]>> {
+            ChaCha20Poly1305Backend.decrypt(key, ciphertext, aad)
+        }
+    }
+
+    #[test]
+    fn test_kdf_hash() {
+        let seed = CurvePoint::generator().to_array();
+
+        let key_blake2b = kdf_with::<Blake2b>(&seed, None, None);
+        let key_sha256 = kdf_with::<Sha256>(&seed, None, None);
+        assert_eq!(kdf(&seed, None, None), key_blake2b);
+        assert_ne!(key_blake2b, key_sha256);
+
+        // RFC 5869, test case 1 (the first 32 bytes of the 42-byte output)
+        let ikm = [0x0bu8; 22];
+        let salt: [u8; 13] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12];
+        let info: [u8; 10] = [0xf0, 0xf1, 0xf2, 0xf3, 0xf4, 0xf5, 0xf6, 0xf7, 0xf8, 0xf9];
+        let expected: [u8; 32] = [
+            0x3c, 0xb2, 0x5f, 0x25, 0xfa, 0xac, 0xd5, 0x7a, 0x90, 0x43, 0x4f, 0x64, 0xd0, 0x36,
+            0x2f, 0x2a, 0x2d, 0x2d, 0x0a, 0x90, 0xcf, 0x1a, 0x5a, 0x4c, 0x5d, 0xb0, 0x2d, 0x56,
+            0xec, 0xc4, 0xc5, 0xbf,
+        ];
+        let key = kdf_with::<Sha256>(&ikm, Some(&salt), Some(&info));
+        assert_eq!(key.as_slice(), &expected);
+
+        // The DEM key is derived without a salt and `info`,
+        // and with BLAKE2b unless the backend says otherwise
+        let seed = b"some key seed";
+        assert_eq!(
+            DemKey::derive_with::<Blake2b>(seed).to_array(),
+            ChaCha20Poly1305Backend.derive_dem_key(seed).to_array()
+        );
+        assert_ne!(
+            DemKey::derive_with::<Sha256>(seed).to_array(),
+            DemKey::derive_with::<Blake2b>(seed).to_array()
+        );
+
+        // RFC 5869, test case 3 (HKDF-SHA256 without a salt and `info`),
+        // the first 32 bytes of the output
+        let expected: [u8; 32] = [
+            0x8d, 0xa4, 0xe7, 0x75, 0xa5, 0x63, 0xc1, 0x8f, 0x71, 0x5f, 0x80, 0x2a, 0x06, 0x3c,
+            0x5a, 0x31, 0xb8, 0xa1, 0x1f, 0x5c, 0x5e, 0xe1, 0x87, 0x9e, 0xc3, 0x45, 0x4e, 0x5f,
+            0x3c, 0x73, 0x8d, 0x2d,
+        ];
+        let key = DemKey::derive_with::<Sha256>(&[0x0b; 22]);
+        assert_eq!(key.to_array().as_slice(), &expected);
+
+        // The backend's derivation is used for the encryption
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+        let plaintext = b"peace at dawn";
+
+        let (capsule, ciphertext) =
+            encrypt_with_backend(&Sha256KdfBackend, &params, &pk, plaintext).unwrap();
+        let decrypted =
+            decrypt_original_with_backend(&Sha256KdfBackend, &sk, &capsule, &ciphertext).unwrap();
+        assert_eq!(&decrypted as &[u8], plaintext);
+        assert!(decrypt_original(&sk, &capsule, &ciphertext).is_none());
+    }
 
     #[test]
     fn test_kdf() {
@@ -100,4 +660,172 @@ mod tests {
         let key_diff = kdf(&p1.to_array(), None, Some(&info[..]));
         assert_ne!(key, key_diff);
     }
+
+    #[test]
+    fn test_detached() {
+        let dem = UmbralDEM::new(b"some key seed");
+        let aad = b"authenticated";
+        let plaintext = b"peace at dawn";
+
+        let mut data = *plaintext;
+        let tag = dem.encrypt_detached(&mut data, aad).unwrap();
+        assert!(&data != plaintext);
+
+        let tag_back = DetachedTag::from_array(&tag.to_array()).unwrap();
+        assert_eq!(tag, tag_back);
+
+        // A flipped tag byte
+        let mut tag_arr = tag.to_array();
+        let last = tag_arr.len() - 1;
+        tag_arr[last] ^= 1;
+        let bad_tag = DetachedTag::from_array(&tag_arr).unwrap();
+        let ciphertext = data;
+        assert!(dem.decrypt_detached(&mut data, aad, &bad_tag).is_none());
+        assert_eq!(data, ciphertext);
+
+        // Wrong authenticated data
+        assert!(dem.decrypt_detached(&mut data, b"other", &tag).is_none());
+
+        dem.decrypt_detached(&mut data, aad, &tag).unwrap();
+        assert_eq!(&data, plaintext);
+    }
+
+    #[test]
+    fn test_derive_key() {
+        let secret = b"shared secret";
+
+        let key1 = derive_key(secret, 32, None, Some(b"encryption")).unwrap();
+        let key2 = derive_key(secret, 32, None, Some(b"authentication")).unwrap();
+        assert_eq!(key1.len(), 32);
+        assert_ne!(key1, key2);
+
+        // Deterministic
+        assert_eq!(
+            derive_key(secret, 32, None, Some(b"encryption")).unwrap(),
+            key1
+        );
+
+        // A salt changes the result
+        assert_ne!(
+            derive_key(secret, 32, Some(b"salt"), Some(b"encryption")).unwrap(),
+            key1
+        );
+
+        // A shorter key is a prefix of the longer one with the same `info` only
+        let short_key = derive_key(secret, 16, None, Some(b"authentication")).unwrap();
+        assert_eq!(&short_key[..], &key2[..16]);
+        assert_ne!(&short_key[..], &key1[..16]);
+
+        // Without a salt and `info` the result is the DEM key
+        let dem_key = derive_key(secret, 32, None, None).unwrap();
+        assert_eq!(&dem_key[..], kdf(secret, None, None).as_slice());
+
+        // Too long
+        assert!(derive_key(secret, 255 * 64, None, None).is_some());
+        assert!(derive_key(secret, 255 * 64 + 1, None, None).is_none());
+    }
+
+    #[test]
+    fn test_aad_chunks() {
+        let dem = UmbralDEM::new(b"some key seed");
+        let plaintext = b"peace at dawn";
+        let header = b"a large authenticated header";
+
+        let ciphertext = dem
+            .encrypt_with_aad_chunks(plaintext, &[&header[..10], &header[10..]])
+            .unwrap();
+
+        // Any split of the same data works
+        let decrypted = dem.decrypt_with_aad_chunks(&ciphertext, &[header]).unwrap();
+        assert_eq!(&decrypted as &[u8], plaintext);
+        let decrypted = dem
+            .decrypt_with_aad_chunks(&ciphertext, &[b"", &header[..3], &header[3..]])
+            .unwrap();
+        assert_eq!(&decrypted as &[u8], plaintext);
+
+        // Different data fails
+        assert!(dem
+            .decrypt_with_aad_chunks(&ciphertext, &[&header[1..]])
+            .is_none());
+        assert!(dem.decrypt_with_aad_chunks(&ciphertext, &[]).is_none());
+    }
+
+    #[test]
+    fn test_external_nonce() {
+        let dem = UmbralDEM::new(b"some key seed");
+        let aad = b"authenticated";
+        let plaintext = b"peace at dawn";
+
+        let mut buf = [0u8; 64];
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+        let (nonce, len) = dem
+            .encrypt_in_place_external_nonce(&mut buf, plaintext.len(), aad)
+            .unwrap();
+        // Only the tag is appended
+        assert_eq!(len, plaintext.len() + 16);
+
+        // Store the nonce out-of-band
+        let stored_nonce = nonce.to_array();
+        let nonce_back = ExternalNonce::from_array(&stored_nonce).unwrap();
+
+        // A different nonce
+        let mut other_nonce = stored_nonce;
+        other_nonce[0] ^= 1;
+        let other_nonce = ExternalNonce::from_array(&other_nonce).unwrap();
+        assert!(dem
+            .decrypt_in_place_external_nonce(&mut buf, len, aad, &other_nonce)
+            .is_none());
+
+        // Too short for a tag
+        assert!(dem
+            .decrypt_in_place_external_nonce(&mut buf, 15, aad, &nonce_back)
+            .is_none());
+
+        let plaintext_len = dem
+            .decrypt_in_place_external_nonce(&mut buf, len, aad, &nonce_back)
+            .unwrap();
+        assert_eq!(&buf[..plaintext_len], plaintext);
+
+        // Not enough room for the tag
+        let mut small_buf = [0u8; 20];
+        assert!(dem
+            .encrypt_in_place_external_nonce(&mut small_buf, 10, aad)
+            .is_none());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_chunks() {
+        let dem = UmbralDEM::new(b"some key seed");
+        let aad = b"authenticated";
+        let plaintext = b"peace at dawn";
+
+        let mut buf = [0u8; 64];
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+        let len = dem
+            .encrypt_chunk_in_place(&mut buf, plaintext.len(), aad, 1, false)
+            .unwrap();
+        assert_eq!(len, plaintext.len() + UmbralDEM::tag_size());
+
+        // The chunk cannot be moved to another position or marked as the last one
+        let mut moved = buf;
+        assert!(dem
+            .decrypt_chunk_in_place(&mut moved, len, aad, 0, false)
+            .is_none());
+        let mut moved = buf;
+        assert!(dem
+            .decrypt_chunk_in_place(&mut moved, len, aad, 1, true)
+            .is_none());
+
+        let plaintext_len = dem
+            .decrypt_chunk_in_place(&mut buf, len, aad, 1, false)
+            .unwrap();
+        assert_eq!(&buf[..plaintext_len], plaintext);
+
+        // Not enough room for the tag
+        let mut small_buf = [0u8; 20];
+        assert!(dem
+            .encrypt_chunk_in_place(&mut small_buf, 10, aad, 0, true)
+            .is_none());
+    }
 }
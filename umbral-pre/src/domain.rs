@@ -0,0 +1,69 @@
+//! Domain separation tags for all the hashing in the scheme.
+//!
+//! The tags are not all used the same way:
+//! most of them are prepended to the hashed data (after the common [`HASH_TO_SCALAR`] prefix
+//! for the hashes to a scalar), [`METADATA_COMMITMENT`] replaces that prefix,
+//! [`NON_INTERACTIVE`] and [`X_COORDINATE`] are chained
+//! after the hashed points, [`PARAMETERS_U`] is the label of a hash to a point,
+//! and [`SLIP10_MASTER_KEY`] is an HMAC key rather than a hash input.
+//! The values are part of the serialization format and must not be changed.
+
+/// Prefix of every hash to a scalar (`ScalarDigest` in `hashing.rs`).
+pub(crate) const HASH_TO_SCALAR: &[u8] = b"hash_to_curvebn";
+
+/// Label of the hash to a point producing the second generator `u`
+/// of [`Parameters`](`crate::Parameters`).
+pub(crate) const PARAMETERS_U: &[u8] = b"NuCypher/UmbralParameters/u";
+
+/// Tag of the non-interactive secret shared by the delegating and the receiving parties,
+/// chained after the precursor, the receiving key and the Diffie-Hellman point.
+pub(crate) const NON_INTERACTIVE: &[u8] = b"NON_INTERACTIVE";
+
+/// Tag of the hash producing the x-coordinates of the key fragment shares,
+/// chained after the same points as [`NON_INTERACTIVE`] and before the key fragment ID.
+pub(crate) const X_COORDINATE: &[u8] = b"X_COORDINATE";
+
+/// Tag of the message signed by the sender of a capsule
+/// (see [`encrypt_signed()`](`crate::encrypt_signed()`)).
+pub(crate) const CAPSULE_SENDER: &[u8] = b"CAPSULE_SENDER";
+
+/// Tag of the hash of the metadata committed to in a capsule fragment,
+/// used instead of [`HASH_TO_SCALAR`] to keep it apart from the hash of plain metadata.
+pub(crate) const METADATA_COMMITMENT: &[u8] = b"METADATA_COMMITMENT";
+
+/// Tag of the hash binding a capsule to its ciphertext
+/// (see [`pairing_tag()`](`crate::pairing_tag()`)).
+pub(crate) const PAIRING_TAG: &[u8] = b"PAIRING_TAG";
+
+/// HMAC key for the master key derivation, fixed by SLIP-0010 for secp256k1.
+pub(crate) const SLIP10_MASTER_KEY: &[u8] = b"Bitcoin seed";
+
+#[cfg(test)]
+mod tests {
+
+    use super::{
+        CAPSULE_SENDER, HASH_TO_SCALAR, METADATA_COMMITMENT, NON_INTERACTIVE, PAIRING_TAG,
+        PARAMETERS_U, SLIP10_MASTER_KEY, X_COORDINATE,
+    };
+
+    #[test]
+    fn test_distinct_tags() {
+        let tags = [
+            HASH_TO_SCALAR,
+            PARAMETERS_U,
+            NON_INTERACTIVE,
+            X_COORDINATE,
+            CAPSULE_SENDER,
+            METADATA_COMMITMENT,
+            PAIRING_TAG,
+            SLIP10_MASTER_KEY,
+        ];
+
+        for (i, tag) in tags.iter().enumerate() {
+            assert!(!tag.is_empty());
+            for other in tags[i + 1..].iter() {
+                assert_ne!(tag, other);
+            }
+        }
+    }
+}
@@ -5,6 +5,7 @@ use generic_array::GenericArray;
 use sha3::Sha3_256;
 
 use crate::curve::{CurvePoint, CurveScalar, PublicKey, SecretKey, Signature};
+use crate::domain::HASH_TO_SCALAR;
 use crate::traits::SerializableToArray;
 
 /// Hashes arbitrary data into a valid EC point of the specified curve,
@@ -54,7 +55,7 @@ pub fn unsafe_hash_to_point(data: &[u8], label: &[u8]) -> Option<CurvePoint> {
     }
 
     // Each iteration can fail with probability 2^(-32), so we probably never reach this point.
-    // And even if we do, it's only called once in Parameters::new(), and is easy to notice.
+    // And even if we do, `Parameters::new()` retries with a different label.
     None
 }
 
@@ -63,7 +64,13 @@ pub(crate) struct ScalarDigest(Sha3_256);
 // TODO (#2): original uses ExtendedKeccak here
 impl ScalarDigest {
     pub fn new() -> Self {
-        Self(Sha3_256::new()).chain_bytes(b"hash_to_curvebn")
+        Self::new_with_dst(HASH_TO_SCALAR)
+    }
+
+    /// Starts the hash with the given domain tag in place of the common [`HASH_TO_SCALAR`] prefix,
+    /// so that its outputs are unrelated to the ones of any [`ScalarDigest::new()`] hash.
+    pub fn new_with_dst(dst: &[u8]) -> Self {
+        Self(Sha3_256::new()).chain_bytes(dst)
     }
 
     fn chain_impl(self, bytes: &[u8]) -> Self {
@@ -122,6 +129,18 @@ impl SignatureDigest {
         self.chain_impl(&[val as u8])
     }
 
+    /// Binds an optional delegation context to the signature.
+    /// Nothing is hashed if the context is `None`,
+    /// so that signatures without a context stay the same.
+    pub fn chain_context(self, context: Option<&[u8]>) -> Self {
+        match context {
+            Some(context) => self
+                .chain_impl(&(context.len() as u64).to_be_bytes())
+                .chain_impl(context),
+            None => self,
+        }
+    }
+
     pub fn sign(self, sk: &SecretKey) -> Signature {
         sk.sign_digest(self.0)
     }
@@ -134,8 +153,36 @@ impl SignatureDigest {
 #[cfg(test)]
 mod tests {
 
+    use alloc::vec::Vec;
+
+    use blake2::VarBlake2b;
+    use digest::{Update, VariableOutput};
+
     use super::{unsafe_hash_to_point, ScalarDigest, SignatureDigest};
     use crate::curve::{CurvePoint, CurveScalar, PublicKey, SecretKey};
+    use crate::SerializableToArray;
+
+    /// A straightforward version of `unsafe_hash_to_point()` which hashes the concatenation
+    /// `len(label) || label || len(data) || data || counter` in one go.
+    fn one_shot_hash_to_point(data: &[u8], label: &[u8]) -> CurvePoint {
+        (0u32..)
+            .find_map(|counter| {
+                let mut input = Vec::new();
+                input.extend_from_slice(&(label.len() as u32).to_be_bytes());
+                input.extend_from_slice(label);
+                input.extend_from_slice(&(data.len() as u32).to_be_bytes());
+                input.extend_from_slice(data);
+                input.extend_from_slice(&counter.to_be_bytes());
+
+                let mut digest = VarBlake2b::new(33).unwrap();
+                digest.update(&input);
+                let mut bytes = [0u8; 33];
+                digest.finalize_variable(|buf| bytes.copy_from_slice(buf));
+                bytes[0] = if bytes[0] & 1 == 0 { 2 } else { 3 };
+                CurvePoint::from_bytes(&bytes[..])
+            })
+            .unwrap()
+    }
 
     #[test]
     fn test_unsafe_hash_to_point() {
@@ -154,6 +201,45 @@ mod tests {
         assert_ne!(p, p_label2);
     }
 
+    #[test]
+    fn test_unsafe_hash_to_point_vectors() {
+        // The incremental hashing must produce the same points as the one-shot reference
+        let vectors: [(&[u8], &[u8], &str); 3] = [
+            (
+                b"",
+                b"",
+                "02549c91a7aacfe03f7df66d0fa065d15deff75fc477bb24dde6fe3952e5d98d26",
+            ),
+            (
+                b"abcdefg",
+                b"sdasdasd",
+                "02ad12758f0b52ad9ffef780011d061e0f1685f4bd7666562bce5ae350b1ce6aaa",
+            ),
+            (
+                // The compressed generator, as used in `Parameters::new()`
+                &[
+                    0x02, 0x79, 0xbe, 0x66, 0x7e, 0xf9, 0xdc, 0xbb, 0xac, 0x55, 0xa0, 0x62, 0x95,
+                    0xce, 0x87, 0x0b, 0x07, 0x02, 0x9b, 0xfc, 0xdb, 0x2d, 0xce, 0x28, 0xd9, 0x59,
+                    0xf2, 0x81, 0x5b, 0x16, 0xf8, 0x17, 0x98,
+                ],
+                b"NuCypher/UmbralParameters/u",
+                "03f202ddc76ecf32773597ee9a7380da1c0f02756c0856a1f2d97bddd10ed1c44a",
+            ),
+        ];
+
+        for (data, label, expected) in vectors.iter() {
+            let point = unsafe_hash_to_point(data, label).unwrap();
+            assert_eq!(point, one_shot_hash_to_point(data, label));
+            let point_arr = point.to_array();
+            let expected_bytes: Vec<u8> = (0..expected.len() / 2)
+                .map(|i| u8::from_str_radix(&expected[2 * i..2 * i + 2], 16).unwrap())
+                .collect();
+            assert_eq!(point_arr.as_slice(), expected_bytes.as_slice());
+        }
+
+        assert_eq!(CurvePoint::generator().to_array().as_slice(), vectors[2].0);
+    }
+
     #[test]
     fn test_scalar_digest() {
         let p1 = CurvePoint::generator();
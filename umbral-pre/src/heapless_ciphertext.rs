@@ -0,0 +1,186 @@
+use heapless::{ArrayLength, Vec};
+#[cfg(feature = "serde-support")]
+use serde::{Deserialize, Serialize};
+
+use crate::capsule::Capsule;
+use crate::curve::{PublicKey, SecretKey};
+use crate::params::Parameters;
+use crate::pre::{decrypt_original_in_place, encrypt_in_place, EncryptionError};
+
+/// Returns a copy of the first `len` elements of `buf`.
+///
+/// `heapless::Vec::truncate()` cannot be used: in `heapless` 0.5 it indexes past the new length.
+fn shortened<N: ArrayLength<u8>>(buf: &Vec<u8, N>, len: usize) -> Vec<u8, N> {
+    // Cannot fail, since the capacity is the same.
+    Vec::from_slice(&buf[..len]).unwrap_or_default()
+}
+
+/// A ciphertext produced by [`encrypt_in_place()`](`crate::encrypt_in_place()`),
+/// stored in a fixed-capacity buffer of `N` bytes, for `no_std` targets without an allocator.
+///
+/// The layout is the same as for the other ciphertexts of this crate:
+/// the 12-byte nonce, the encrypted plaintext, and the 16-byte authentication tag,
+/// so the capacity must be at least 28 bytes larger than the plaintext.
+///
+/// With the `serde-support` feature the ciphertext is serialized as a byte sequence
+/// (in particular, it can be sent as is with `postcard`).
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde-support", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde-support", serde(bound = ""))]
+pub struct HeaplessCiphertext<N: ArrayLength<u8>>(Vec<u8, N>);
+
+impl<N: ArrayLength<u8>> HeaplessCiphertext<N> {
+    /// Encrypts `plaintext` for the owner of `pk`,
+    /// returning the KEM [`Capsule`] and the ciphertext.
+    ///
+    /// Returns [`EncryptionError::BufferTooSmall`] if the ciphertext does not fit in `N` bytes.
+    pub fn encrypt(
+        params: &Parameters,
+        pk: &PublicKey,
+        plaintext: &[u8],
+    ) -> Result<(Capsule, Self), EncryptionError> {
+        let mut buf = Vec::<u8, N>::new();
+        buf.resize_default(N::to_usize())
+            .map_err(|_| EncryptionError::BufferTooSmall)?;
+        if plaintext.len() > buf.len() {
+            return Err(EncryptionError::BufferTooSmall);
+        }
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+
+        let (capsule, ciphertext_len) = encrypt_in_place(params, pk, &mut buf, plaintext.len())?;
+        Ok((capsule, Self(shortened(&buf, ciphertext_len))))
+    }
+
+    /// Decrypts the ciphertext with the original secret key.
+    ///
+    /// Returns `None` if the decryption fails.
+    #[must_use = "a failed decryption is only reported in the return value"]
+    pub fn decrypt_original(
+        &self,
+        decrypting_sk: &SecretKey,
+        capsule: &Capsule,
+    ) -> Option<Vec<u8, N>> {
+        let mut buf = self.0.clone();
+        let plaintext_len =
+            decrypt_original_in_place(decrypting_sk, capsule, &mut buf, self.len())?;
+        Some(shortened(&buf, plaintext_len))
+    }
+
+    /// Creates a ciphertext from its serialized form.
+    ///
+    /// Returns `None` if `bytes` does not fit in `N` bytes.
+    pub fn from_bytes(bytes: impl AsRef<[u8]>) -> Option<Self> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(bytes.as_ref()).ok()?;
+        Some(Self(buf))
+    }
+
+    /// Returns the serialized form of the ciphertext.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the length of the ciphertext in bytes.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if the ciphertext is empty (which means it is not a valid one).
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns the underlying buffer.
+    pub fn into_inner(self) -> Vec<u8, N> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use typenum::{U16, U64};
+
+    use super::HeaplessCiphertext;
+    use crate::{decrypt_original, EncryptionError, Parameters, PublicKey, SecretKey};
+
+    #[test]
+    fn test_round_trip() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+        let plaintext = b"peace at dawn";
+
+        let (capsule, ciphertext) =
+            HeaplessCiphertext::<U64>::encrypt(&params, &pk, plaintext).unwrap();
+        assert_eq!(ciphertext.len(), plaintext.len() + 28);
+
+        let decrypted = ciphertext.decrypt_original(&sk, &capsule).unwrap();
+        assert_eq!(&decrypted[..], plaintext);
+
+        // The layout is the same as the one of `encrypt()`
+        let decrypted = decrypt_original(&sk, &capsule, ciphertext.as_bytes()).unwrap();
+        assert_eq!(&decrypted[..], plaintext);
+
+        let ciphertext_back = HeaplessCiphertext::<U64>::from_bytes(ciphertext.as_bytes()).unwrap();
+        assert_eq!(ciphertext_back, ciphertext);
+        assert!(HeaplessCiphertext::<U16>::from_bytes(ciphertext.as_bytes()).is_none());
+
+        let other_sk = SecretKey::random();
+        assert!(ciphertext.decrypt_original(&other_sk, &capsule).is_none());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let params = Parameters::new();
+        let pk = PublicKey::from_secret_key(&SecretKey::random());
+
+        // Exactly enough room for the nonce and the tag
+        let plaintext = [1u8; 64 - 28];
+        assert!(HeaplessCiphertext::<U64>::encrypt(&params, &pk, &plaintext).is_ok());
+
+        let plaintext = [1u8; 64 - 27];
+        assert_eq!(
+            HeaplessCiphertext::<U64>::encrypt(&params, &pk, &plaintext),
+            Err(EncryptionError::BufferTooSmall)
+        );
+
+        let plaintext = [1u8; 100];
+        assert_eq!(
+            HeaplessCiphertext::<U64>::encrypt(&params, &pk, &plaintext),
+            Err(EncryptionError::BufferTooSmall)
+        );
+    }
+
+    #[cfg(feature = "serde-support")]
+    #[test]
+    fn test_serde_postcard() {
+        use typenum::U128;
+
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+        let plaintext = b"peace at dawn";
+
+        let (capsule, ciphertext) =
+            HeaplessCiphertext::<U64>::encrypt(&params, &pk, plaintext).unwrap();
+
+        // Serializing into a fixed buffer, without allocations
+        let mut buf = [0u8; 128];
+        let serialized = postcard::to_slice(&ciphertext, &mut buf).unwrap();
+
+        // A length prefix followed by the ciphertext bytes
+        assert_eq!(serialized[0] as usize, ciphertext.len());
+        assert_eq!(&serialized[1..], ciphertext.as_bytes());
+
+        let ciphertext_back: HeaplessCiphertext<U64> = postcard::from_bytes(serialized).unwrap();
+        assert_eq!(ciphertext_back, ciphertext);
+        let decrypted = ciphertext_back.decrypt_original(&sk, &capsule).unwrap();
+        assert_eq!(&decrypted[..], plaintext);
+
+        // A larger capacity is fine, a smaller one is rejected
+        let larger: HeaplessCiphertext<U128> = postcard::from_bytes(serialized).unwrap();
+        assert_eq!(larger.as_bytes(), ciphertext.as_bytes());
+        assert!(postcard::from_bytes::<HeaplessCiphertext<U16>>(serialized).is_err());
+    }
+}
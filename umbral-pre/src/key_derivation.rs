@@ -0,0 +1,174 @@
+//! Hierarchical deterministic derivation of secret keys
+//! following [SLIP-0010](https://github.com/satoshilabs/slips/blob/master/slip-0010.md)
+//! for secp256k1 (which, for valid intermediate keys, is identical to BIP32).
+
+use generic_array::GenericArray;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha512;
+
+use crate::curve::{CurvePoint, CurveScalar};
+use crate::domain::SLIP10_MASTER_KEY;
+use crate::traits::SerializableToArray;
+
+type HmacSha512 = Hmac<Sha512>;
+
+const HARDENED_OFFSET: u32 = 1 << 31;
+
+fn hmac_sha512(key: &[u8], parts: &[&[u8]]) -> [u8; 64] {
+    // HMAC accepts keys of any length, so this cannot fail.
+    let mut mac = HmacSha512::new_varkey(key).unwrap();
+    for part in parts {
+        mac.update(part);
+    }
+    let mut result = [0u8; 64];
+    result.copy_from_slice(&mac.finalize().into_bytes());
+    result
+}
+
+/// Splits the HMAC output into the left half interpreted as a scalar
+/// (`None` if it is not less than the curve order) and the right half.
+fn split_output(i: &[u8; 64]) -> (Option<CurveScalar>, [u8; 32]) {
+    let il = CurveScalar::from_array(GenericArray::from_slice(&i[..32]));
+    let mut ir = [0u8; 32];
+    ir.copy_from_slice(&i[32..]);
+    (il, ir)
+}
+
+fn master_key(seed: &[u8]) -> (CurveScalar, [u8; 32]) {
+    let mut i = hmac_sha512(SLIP10_MASTER_KEY, &[seed]);
+    loop {
+        match split_output(&i) {
+            (Some(key), chain_code) if !key.is_zero() => return (key, chain_code),
+            // SLIP-0010: if the key is invalid, hash the output again.
+            _ => i = hmac_sha512(SLIP10_MASTER_KEY, &[&i]),
+        }
+    }
+}
+
+fn child_key(key: &CurveScalar, chain_code: &[u8; 32], index: u32) -> (CurveScalar, [u8; 32]) {
+    let index_bytes = index.to_be_bytes();
+    let mut i = if index >= HARDENED_OFFSET {
+        hmac_sha512(chain_code, &[&[0u8], &key.to_array(), &index_bytes])
+    } else {
+        let point = &CurvePoint::generator() * key;
+        hmac_sha512(chain_code, &[&point.to_array(), &index_bytes])
+    };
+
+    loop {
+        let (il, ir) = split_output(&i);
+        if let Some(il) = il {
+            let child = &il + key;
+            if !child.is_zero() {
+                return (child, ir);
+            }
+        }
+        // SLIP-0010: if the key is invalid, derive again from the right half.
+        i = hmac_sha512(chain_code, &[&[1u8], &ir, &index_bytes]);
+    }
+}
+
+/// Parses a single path component like `44'`, `0h` or `7`.
+fn parse_index(component: &str) -> Option<u32> {
+    let (number, hardened) = match component.strip_suffix('\'') {
+        Some(number) => (number, true),
+        None => match component
+            .strip_suffix('h')
+            .or_else(|| component.strip_suffix('H'))
+        {
+            Some(number) => (number, true),
+            None => (component, false),
+        },
+    };
+
+    if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let index: u32 = number.parse().ok()?;
+    if index >= HARDENED_OFFSET {
+        return None;
+    }
+
+    Some(if hardened {
+        index + HARDENED_OFFSET
+    } else {
+        index
+    })
+}
+
+/// Derives a secret scalar from `seed` along a path like `m/44'/60'/0'/0/1`.
+/// Returns `None` if the path cannot be parsed.
+pub(crate) fn derive_secret_scalar(seed: &[u8], path: &str) -> Option<CurveScalar> {
+    let mut components = path.split('/');
+    if components.next()? != "m" {
+        return None;
+    }
+
+    let (mut key, mut chain_code) = master_key(seed);
+    for component in components {
+        let index = parse_index(component)?;
+        let (child, child_chain_code) = child_key(&key, &chain_code, index);
+        key = child;
+        chain_code = child_chain_code;
+    }
+
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::derive_secret_scalar;
+    use crate::SerializableToArray;
+
+    fn from_hex(s: &str) -> [u8; 32] {
+        let mut result = [0u8; 32];
+        for (i, byte) in result.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&s[2 * i..2 * i + 2], 16).unwrap();
+        }
+        result
+    }
+
+    #[test]
+    fn test_bip32_vector_1() {
+        let seed = [
+            0x00, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08, 0x09, 0x0a, 0x0b, 0x0c, 0x0d,
+            0x0e, 0x0f,
+        ];
+
+        let vectors = [
+            (
+                "m",
+                "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35",
+            ),
+            (
+                "m/0'",
+                "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea",
+            ),
+            (
+                "m/0'/1",
+                "3c6cb8d0f6a264c91ea8b5030fadaa8e538b020f0a387421a12de9319dc93368",
+            ),
+            (
+                "m/0h/1/2h",
+                "cbce0d719ecf7431d88e6a89fa1483e02e35092af60c042b1df2ff59fa424dca",
+            ),
+        ];
+
+        for (path, expected) in vectors.iter() {
+            let key = derive_secret_scalar(&seed, path).unwrap();
+            assert_eq!(key.to_array().as_slice(), &from_hex(expected));
+        }
+    }
+
+    #[test]
+    fn test_invalid_paths() {
+        let seed = b"seed";
+        assert!(derive_secret_scalar(seed, "").is_none());
+        assert!(derive_secret_scalar(seed, "0/1").is_none());
+        assert!(derive_secret_scalar(seed, "m/").is_none());
+        assert!(derive_secret_scalar(seed, "m/x").is_none());
+        assert!(derive_secret_scalar(seed, "m/-1").is_none());
+        assert!(derive_secret_scalar(seed, "m/2147483648").is_none());
+        assert!(derive_secret_scalar(seed, "m/1''").is_none());
+    }
+}
@@ -1,24 +1,27 @@
-use crate::constants::{NON_INTERACTIVE, X_COORDINATE};
 use crate::curve::{CurvePoint, CurveScalar};
 use crate::curve::{PublicKey, SecretKey, Signature};
+use crate::domain::{NON_INTERACTIVE, X_COORDINATE};
 use crate::hashing::{ScalarDigest, SignatureDigest};
 use crate::params::Parameters;
-use crate::traits::SerializableToArray;
+use crate::traits::{
+    DeserializationError, FieldReader, SerializableToArray, SerializableToFixedBytes,
+};
 
 use alloc::boxed::Box;
 use alloc::vec::Vec;
+use core::cmp::Ordering;
 
 use generic_array::sequence::Concat;
 use generic_array::GenericArray;
-use typenum::{op, U1};
+use typenum::{op, Unsigned, U1};
 
 #[derive(Clone, Debug, PartialEq)]
 pub(crate) struct KeyFragProof {
     pub(crate) commitment: CurvePoint,
-    signature_for_proxy: Signature,
-    signature_for_bob: Signature,
-    delegating_key_signed: bool,
-    receiving_key_signed: bool,
+    pub(crate) signature_for_proxy: Signature,
+    pub(crate) signature_for_bob: Signature,
+    pub(crate) delegating_key_signed: bool,
+    pub(crate) receiving_key_signed: bool,
 }
 
 type ParametersSize = <Parameters as SerializableToArray>::Size;
@@ -40,22 +43,22 @@ impl SerializableToArray for KeyFragProof {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        let (commitment, rest) = CurvePoint::take(*arr)?;
-        let (signature_for_proxy, rest) = Signature::take(rest)?;
-        let (signature_for_bob, rest) = Signature::take(rest)?;
-        let (delegating_key_signed, rest) = bool::take(rest)?;
-        let receiving_key_signed = bool::take_last(rest)?;
-        Some(Self {
-            commitment,
-            signature_for_proxy,
-            signature_for_bob,
-            delegating_key_signed,
-            receiving_key_signed,
-        })
+        let mut reader = FieldReader::new::<Self>(arr).ok()?;
+        Self::read(&mut reader).ok()
     }
 }
 
 impl KeyFragProof {
+    fn read(reader: &mut FieldReader<'_>) -> Result<Self, DeserializationError> {
+        Ok(Self {
+            commitment: reader.point()?,
+            signature_for_proxy: reader.signature()?,
+            signature_for_bob: reader.signature()?,
+            delegating_key_signed: reader.bool()?,
+            receiving_key_signed: reader.bool()?,
+        })
+    }
+
     #[allow(clippy::too_many_arguments)]
     fn new(
         params: &Parameters,
@@ -67,6 +70,7 @@ impl KeyFragProof {
         receiving_pk: &PublicKey,
         sign_delegating_key: bool,
         sign_receiving_key: bool,
+        context: Option<&[u8]>,
     ) -> Self {
         let commitment = &params.u * kfrag_key;
 
@@ -76,6 +80,7 @@ impl KeyFragProof {
             .chain_pubkey(receiving_pk)
             .chain_point(&commitment)
             .chain_point(kfrag_precursor)
+            .chain_context(context)
             .sign(signing_sk);
 
         let mut digest_for_proxy = SignatureDigest::new()
@@ -93,7 +98,7 @@ impl KeyFragProof {
             digest_for_proxy = digest_for_proxy.chain_pubkey(receiving_pk);
         }
 
-        let signature_for_proxy = digest_for_proxy.sign(&signing_sk);
+        let signature_for_proxy = digest_for_proxy.chain_context(context).sign(signing_sk);
 
         Self {
             commitment,
@@ -105,14 +110,27 @@ impl KeyFragProof {
     }
 
     pub(crate) fn signature_for_bob(&self) -> Signature {
-        self.signature_for_bob.clone()
+        self.signature_for_bob
     }
 }
 
 /// A fragment of the encrypting party's key used to create a [`CapsuleFrag`](`crate::CapsuleFrag`).
+///
+/// Fragments are ordered by their key fragment ID (see [`id()`](`Self::id()`)),
+/// so that a set of them can be put in a canonical order.
+/// The ordering has no cryptographic meaning.
+///
+/// A deserialized `KeyFrag` holds no references to its serialized form,
+/// so a proxy serving many reencryption requests can deserialize (and verify) it once
+/// and keep the object, instead of parsing the stored bytes for every request.
+///
+/// Deserialization checks that every embedded point (the parameters, the precursor
+/// and the commitment) is a valid curve point, that every scalar is canonical,
+/// and that the signatures have valid components.
+/// It does not check the signatures themselves, use [`verify()`](`Self::verify()`) for that.
 #[derive(Clone, Debug, PartialEq)]
 pub struct KeyFrag {
-    params: Parameters,
+    pub(crate) params: Parameters,
     pub(crate) id: CurveScalar,
     pub(crate) key: CurveScalar,
     pub(crate) precursor: CurvePoint,
@@ -134,26 +152,73 @@ impl SerializableToArray for KeyFrag {
     }
 
     fn from_array(arr: &GenericArray<u8, Self::Size>) -> Option<Self> {
-        let (params, rest) = Parameters::take(*arr)?;
-        let (id, rest) = CurveScalar::take(rest)?;
-        let (key, rest) = CurveScalar::take(rest)?;
-        let (precursor, rest) = CurvePoint::take(rest)?;
-        let proof = KeyFragProof::take_last(rest)?;
-        Some(Self {
-            params,
-            id,
-            key,
-            precursor,
-            proof,
-        })
+        Self::try_from_bytes(arr).ok()
+    }
+}
+
+/// The size of a serialized [`KeyFrag`] in bytes.
+pub const KEY_FRAG_SIZE: usize = 293;
+
+impl SerializableToFixedBytes<KEY_FRAG_SIZE> for KeyFrag {}
+const _: [(); KEY_FRAG_SIZE] = [(); <<KeyFrag as SerializableToArray>::Size as Unsigned>::USIZE];
+
+// Ties are broken by the serialized contents, to stay consistent with `Eq`.
+impl Eq for KeyFrag {}
+
+impl PartialOrd for KeyFrag {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for KeyFrag {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.id
+            .to_array()
+            .cmp(&other.id.to_array())
+            .then_with(|| self.to_array().cmp(&other.to_array()))
     }
 }
 
 impl KeyFrag {
-    fn new(factory: &KeyFragFactory, sign_delegating_key: bool, sign_receiving_key: bool) -> Self {
+    /// Deserializes a key fragment, reporting the reason of a failure.
+    ///
+    /// [`SerializableToArray::from_bytes()`] is equivalent, but discards the error.
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, DeserializationError> {
+        let mut reader = FieldReader::new::<Self>(bytes.as_ref())?;
+        Ok(Self {
+            params: Parameters { u: reader.point()? },
+            id: reader.scalar()?,
+            key: reader.scalar()?,
+            precursor: reader.point()?,
+            proof: KeyFragProof::read(&mut reader)?,
+        })
+    }
+
+    fn new(
+        factory: &KeyFragFactory,
+        sign_delegating_key: bool,
+        sign_receiving_key: bool,
+        context: Option<&[u8]>,
+    ) -> Self {
         // Was: `os.urandom(bn_size)`. But it seems we just want a scalar?
         let kfrag_id = CurveScalar::random_nonzero();
+        Self::with_id(
+            factory,
+            kfrag_id,
+            sign_delegating_key,
+            sign_receiving_key,
+            context,
+        )
+    }
 
+    fn with_id(
+        factory: &KeyFragFactory,
+        kfrag_id: CurveScalar,
+        sign_delegating_key: bool,
+        sign_receiving_key: bool,
+        context: Option<&[u8]>,
+    ) -> Self {
         // The index of the re-encryption key share (which in Shamir's Secret
         // Sharing corresponds to x in the tuple (x, f(x)), with f being the
         // generating polynomial), is used to prevent reconstruction of the
@@ -182,6 +247,7 @@ impl KeyFrag {
             &factory.receiving_pk,
             sign_delegating_key,
             sign_receiving_key,
+            context,
         );
 
         Self {
@@ -193,17 +259,48 @@ impl KeyFrag {
         }
     }
 
+    /// Returns the parameters the key fragment was created with.
+    pub fn params(&self) -> Parameters {
+        self.params
+    }
+
+    /// Returns the ID of the key fragment.
+    ///
+    /// Fragments produced by one call to [`generate_kfrags()`](`crate::generate_kfrags()`)
+    /// have distinct IDs, and the capsule fragments created from this key fragment
+    /// share it (see [`CapsuleFrag::kfrag_id()`](`crate::CapsuleFrag::kfrag_id()`)).
+    pub fn id(&self) -> CurveScalar {
+        self.id
+    }
+
     /// Verifies the integrity of the key fragment, given the signing key,
     /// and (optionally) the encrypting party's and decrypting party's keys.
     ///
     /// If [`generate_kfrags()`](`crate::generate_kfrags()`) was called with `true`
     /// for `sign_delegating_key` or `sign_receiving_key`, and the respective key
     /// is not provided, the verification fails.
+    #[must_use = "the verification result must be checked"]
     pub fn verify(
         &self,
         signing_pk: &PublicKey,
         delegating_pk: Option<&PublicKey>,
         receiving_pk: Option<&PublicKey>,
+    ) -> bool {
+        self.verify_with_context(signing_pk, delegating_pk, receiving_pk, None)
+    }
+
+    /// Same as [`verify()`](`Self::verify()`), for key fragments created by
+    /// [`generate_kfrags_with_context()`] with the given `context`.
+    ///
+    /// The verification fails if `context` differs from the one used during creation
+    /// (including the case when it was provided during creation but not here, or vice versa).
+    #[must_use = "the verification result must be checked"]
+    pub fn verify_with_context(
+        &self,
+        signing_pk: &PublicKey,
+        delegating_pk: Option<&PublicKey>,
+        receiving_pk: Option<&PublicKey>,
+        context: Option<&[u8]>,
     ) -> bool {
         if self.proof.delegating_key_signed && delegating_pk.is_none() {
             return false;
@@ -237,10 +334,46 @@ impl KeyFrag {
             // `receiving_pk` is guaranteed to be Some here.
             digest = digest.chain_pubkey(&receiving_pk.unwrap());
         }
-        let valid_kfrag_signature = digest.verify(&signing_pk, &self.proof.signature_for_proxy);
+        let valid_kfrag_signature = digest
+            .chain_context(context)
+            .verify(signing_pk, &self.proof.signature_for_proxy);
 
         correct_commitment & valid_kfrag_signature
     }
+
+    /// Verifies a set of key fragments, checking that each one is valid
+    /// (as in [`verify()`](`Self::verify()`)), and that they are consistent with each other:
+    /// they share the precursor and the parameters (that is, were produced by
+    /// the same call to [`generate_kfrags()`](`crate::generate_kfrags()`)),
+    /// and have distinct IDs (so that no fragment is repeated).
+    ///
+    /// On failure, returns the index of the first fragment that is invalid,
+    /// or inconsistent with the ones before it.
+    ///
+    /// Note that the threshold is not encoded in the fragments,
+    /// so this cannot check how many of them are necessary for decryption.
+    pub fn verify_consistent_set(
+        kfrags: &[KeyFrag],
+        signing_pk: &PublicKey,
+        delegating_pk: Option<&PublicKey>,
+        receiving_pk: Option<&PublicKey>,
+    ) -> Result<(), usize> {
+        for (i, kfrag) in kfrags.iter().enumerate() {
+            if !kfrag.verify(signing_pk, delegating_pk, receiving_pk) {
+                return Err(i);
+            }
+
+            let first = &kfrags[0];
+            if kfrag.precursor != first.precursor || kfrag.params != first.params {
+                return Err(i);
+            }
+
+            if kfrags[..i].iter().any(|other| other.id == kfrag.id) {
+                return Err(i);
+            }
+        }
+        Ok(())
+    }
 }
 
 struct KeyFragFactory {
@@ -264,7 +397,7 @@ impl KeyFragFactory {
     ) -> Self {
         let g = CurvePoint::generator();
 
-        let delegating_pk = PublicKey::from_secret_key(delegating_sk);
+        let delegating_pk = *delegating_sk.public_key();
 
         let bob_pubkey_point = receiving_pk.to_point();
 
@@ -321,6 +454,68 @@ fn poly_eval(coeffs: &[CurveScalar], x: &CurveScalar) -> CurveScalar {
     result
 }
 
+/// A mapping of reencrypting parties (proxies) to key fragments
+/// that keeps every proxy on the same fragment ID across re-delegations.
+///
+/// If the new fragments are created with [`generate_kfrags_with_ids()`]
+/// using the IDs of the old ones, each proxy gets the fragment with the same ID,
+/// so the proxy lists already handed out to clients stay valid.
+/// The fragments themselves (including their share indices) are new.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KfragAssignment {
+    assigned: Vec<(PublicKey, KeyFrag)>,
+    orphaned_proxies: Vec<PublicKey>,
+    unassigned_kfrags: Vec<KeyFrag>,
+}
+
+impl KfragAssignment {
+    /// Assigns each of `new_kfrags` to the proxy that held the old fragment with the same ID
+    /// (as given by the `old` proxy-fragment pairs).
+    ///
+    /// Proxies whose fragment ID is not among the new fragments
+    /// are reported by [`orphaned_proxies()`](`Self::orphaned_proxies()`),
+    /// and new fragments with an ID no proxy held
+    /// by [`unassigned_kfrags()`](`Self::unassigned_kfrags()`).
+    pub fn reassign(old: &[(PublicKey, KeyFrag)], new_kfrags: &[KeyFrag]) -> Self {
+        let mut assigned = Vec::with_capacity(old.len());
+        let mut orphaned_proxies = Vec::new();
+        for (proxy_pk, old_kfrag) in old.iter() {
+            match new_kfrags.iter().find(|kfrag| kfrag.id == old_kfrag.id) {
+                Some(kfrag) => assigned.push((*proxy_pk, kfrag.clone())),
+                None => orphaned_proxies.push(*proxy_pk),
+            }
+        }
+
+        let unassigned_kfrags = new_kfrags
+            .iter()
+            .filter(|kfrag| old.iter().all(|(_, old_kfrag)| old_kfrag.id != kfrag.id))
+            .cloned()
+            .collect();
+
+        Self {
+            assigned,
+            orphaned_proxies,
+            unassigned_kfrags,
+        }
+    }
+
+    /// Returns the proxies along with their new key fragments,
+    /// in the order of the old assignment.
+    pub fn assigned(&self) -> &[(PublicKey, KeyFrag)] {
+        &self.assigned
+    }
+
+    /// Returns the proxies whose fragment ID is not present in the new fragments.
+    pub fn orphaned_proxies(&self) -> &[PublicKey] {
+        &self.orphaned_proxies
+    }
+
+    /// Returns the new fragments whose ID was not held by any proxy.
+    pub fn unassigned_kfrags(&self) -> &[KeyFrag] {
+        &self.unassigned_kfrags
+    }
+}
+
 /// Creates `num_kfrags` fragments of `delegating_sk`,
 /// which will be possible to reencrypt to allow the creator of `receiving_pk`
 /// decrypt the ciphertext encrypted with `delegating_sk`.
@@ -338,6 +533,16 @@ fn poly_eval(coeffs: &[CurveScalar], x: &CurveScalar) -> CurveScalar {
 /// by supplying them to [`KeyFrag::verify()`].
 ///
 /// Returns a boxed slice of `num_kfrags` KeyFrags
+///
+/// To change the threshold or the number of fragments of a delegation,
+/// call this function again with the new values.
+/// Capsules do not depend on the key fragments, so the existing ciphertexts
+/// do not need to be reencrypted, and the keys stay the same.
+/// The existing fragments cannot be re-split instead:
+/// their share indices are derived from an ephemeral secret that is discarded here,
+/// so nobody holding only the fragments can reconstruct the shared key.
+/// Fragments from different calls have different precursors and cannot be mixed
+/// (see [`Capsule::can_open()`](`crate::Capsule::can_open()`)).
 #[allow(clippy::too_many_arguments)]
 pub fn generate_kfrags(
     params: &Parameters,
@@ -348,29 +553,124 @@ pub fn generate_kfrags(
     num_kfrags: usize,
     sign_delegating_key: bool,
     sign_receiving_key: bool,
+) -> Box<[KeyFrag]> {
+    generate_kfrags_with_context(
+        params,
+        delegating_sk,
+        receiving_pk,
+        signing_sk,
+        threshold,
+        num_kfrags,
+        sign_delegating_key,
+        sign_receiving_key,
+        None,
+    )
+}
+
+/// Same as [`generate_kfrags()`], but additionally binds an arbitrary delegation `context`
+/// (e.g. a serialized policy expiration time) to the signatures of the key fragments
+/// and of the capsule fragments created from them.
+///
+/// The same context must then be supplied to [`KeyFrag::verify_with_context()`]
+/// and [`CapsuleFrag::verify_with_context()`](`crate::CapsuleFrag::verify_with_context()`).
+/// Passing `None` is equivalent to calling [`generate_kfrags()`].
+#[allow(clippy::too_many_arguments)]
+pub fn generate_kfrags_with_context(
+    params: &Parameters,
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signing_sk: &SecretKey,
+    threshold: usize,
+    num_kfrags: usize,
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+    context: Option<&[u8]>,
 ) -> Box<[KeyFrag]> {
     let base = KeyFragFactory::new(params, delegating_sk, receiving_pk, signing_sk, threshold);
 
     let mut result = Vec::<KeyFrag>::new();
     for _ in 0..num_kfrags {
-        result.push(KeyFrag::new(&base, sign_delegating_key, sign_receiving_key));
+        result.push(KeyFrag::new(
+            &base,
+            sign_delegating_key,
+            sign_receiving_key,
+            context,
+        ));
     }
 
     result.into_boxed_slice()
 }
 
+/// Same as [`generate_kfrags_with_context()`], but uses the given `ids` as the IDs
+/// of the fragments (see [`KeyFrag::id()`]) instead of random ones,
+/// creating one fragment per ID.
+///
+/// Only the ID itself is stable across delegations, so it can serve as a label
+/// keeping a party on the same fragment (see [`KfragAssignment`]).
+/// The share index in the secret sharing is derived from the ID
+/// together with the ephemeral values of each call,
+/// so it is different every time, and so are the fragments.
+///
+/// Returns `None` if any of the IDs is zero, or if the IDs are not distinct.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_kfrags_with_ids(
+    params: &Parameters,
+    delegating_sk: &SecretKey,
+    receiving_pk: &PublicKey,
+    signing_sk: &SecretKey,
+    threshold: usize,
+    ids: &[CurveScalar],
+    sign_delegating_key: bool,
+    sign_receiving_key: bool,
+    context: Option<&[u8]>,
+) -> Option<Box<[KeyFrag]>> {
+    for (i, id) in ids.iter().enumerate() {
+        if id.is_zero() || ids[..i].contains(id) {
+            return None;
+        }
+    }
+
+    let base = KeyFragFactory::new(params, delegating_sk, receiving_pk, signing_sk, threshold);
+
+    let result: Vec<KeyFrag> = ids
+        .iter()
+        .map(|id| KeyFrag::with_id(&base, *id, sign_delegating_key, sign_receiving_key, context))
+        .collect();
+
+    Some(result.into_boxed_slice())
+}
+
 #[cfg(test)]
 mod tests {
 
     use alloc::boxed::Box;
 
-    use super::{generate_kfrags, KeyFrag};
-    use crate::{Parameters, PublicKey, SecretKey, SerializableToArray};
+    use alloc::vec::Vec;
 
-    fn prepare_kfrags(
-        sign_delegating_key: bool,
-        sign_receiving_key: bool,
-    ) -> (PublicKey, PublicKey, PublicKey, Box<[KeyFrag]>) {
+    use super::{
+        generate_kfrags, generate_kfrags_with_context, generate_kfrags_with_ids, KeyFrag,
+        KfragAssignment, KEY_FRAG_SIZE,
+    };
+    use crate::curve::{CurveScalar, PointError, SignatureError};
+    use crate::traits::{DeserializationError, SerializableToFixedBytes};
+    use crate::{
+        decrypt_reencrypted, encrypt, reencrypt, CapsuleFrag, Parameters, PublicKey, SecretKey,
+        SerializableToArray,
+    };
+
+    /// The keys of a 2-of-3 delegation and its key fragments.
+    struct Delegation {
+        params: Parameters,
+        delegating_sk: SecretKey,
+        delegating_pk: PublicKey,
+        signing_sk: SecretKey,
+        signing_pk: PublicKey,
+        receiving_sk: SecretKey,
+        receiving_pk: PublicKey,
+        kfrags: Box<[KeyFrag]>,
+    }
+
+    fn prepare_kfrags(sign_delegating_key: bool, sign_receiving_key: bool) -> Delegation {
         let params = Parameters::new();
 
         let delegating_sk = SecretKey::random();
@@ -393,36 +693,480 @@ mod tests {
             sign_receiving_key,
         );
 
-        (delegating_pk, receiving_pk, signing_pk, kfrags)
+        Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            kfrags,
+        }
+    }
+
+    #[test]
+    fn test_ordering() {
+        let Delegation { kfrags, .. } = prepare_kfrags(true, true);
+
+        let mut sorted = kfrags.to_vec();
+        sorted.sort();
+        assert!(sorted
+            .windows(2)
+            .all(|pair| pair[0].id().to_array() < pair[1].id().to_array()));
+
+        // The order does not depend on the initial one
+        let mut shuffled: Vec<KeyFrag> = kfrags.iter().rev().cloned().collect();
+        shuffled.rotate_left(1);
+        shuffled.sort();
+        assert_eq!(shuffled, sorted);
     }
 
     #[test]
     fn test_serialize() {
-        let (_, _, _, kfrags) = prepare_kfrags(true, true);
+        let Delegation { kfrags, .. } = prepare_kfrags(true, true);
         let kfrag_arr = kfrags[0].to_array();
         let kfrag_back = KeyFrag::from_array(&kfrag_arr).unwrap();
         assert_eq!(kfrags[0], kfrag_back);
+
+        let kfrag_bytes: [u8; KEY_FRAG_SIZE] = kfrags[0].to_fixed_bytes();
+        assert_eq!(&kfrag_bytes[..], kfrag_arr.as_slice());
+        assert_eq!(KeyFrag::from_fixed_bytes(&kfrag_bytes).unwrap(), kfrags[0]);
+    }
+
+    #[test]
+    fn test_deserialize_malformed() {
+        let Delegation { kfrags, .. } = prepare_kfrags(true, true);
+        let kfrag_arr = kfrags[0].to_array();
+
+        assert_eq!(
+            KeyFrag::try_from_bytes(&kfrag_arr[1..]),
+            Err(DeserializationError::WrongLength)
+        );
+
+        // The parameters have an invalid SEC1 prefix
+        let mut bad_bytes = kfrag_arr;
+        bad_bytes[0] = 5;
+        assert!(KeyFrag::from_array(&bad_bytes).is_none());
+        assert_eq!(
+            KeyFrag::try_from_bytes(bad_bytes),
+            Err(DeserializationError::InvalidPoint(
+                PointError::MalformedEncoding
+            ))
+        );
+
+        // The key is not less than the curve order
+        let mut bad_bytes = kfrag_arr;
+        bad_bytes[65..97].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            KeyFrag::try_from_bytes(bad_bytes),
+            Err(DeserializationError::InvalidScalar)
+        );
+
+        // The `s` of the signature for the proxy is not less than the curve order
+        let mut bad_bytes = kfrag_arr;
+        bad_bytes[195..227].copy_from_slice(&[0xff; 32]);
+        assert_eq!(
+            KeyFrag::try_from_bytes(bad_bytes),
+            Err(DeserializationError::InvalidSignature(
+                SignatureError::MalformedEncoding
+            ))
+        );
+
+        // The flags can only be 0 or 1
+        let mut bad_bytes = kfrag_arr;
+        bad_bytes[292] = 2;
+        assert!(KeyFrag::from_array(&bad_bytes).is_none());
+        assert_eq!(
+            KeyFrag::try_from_bytes(bad_bytes),
+            Err(DeserializationError::InvalidBool)
+        );
+
+        assert_eq!(KeyFrag::try_from_bytes(kfrag_arr), Ok(kfrags[0].clone()));
     }
 
     #[test]
     fn test_verify() {
-        let (delegating_pk, receiving_pk, signing_pk, kfrags) = prepare_kfrags(true, true);
+        let Delegation {
+            delegating_pk,
+            receiving_pk,
+            signing_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(true, true);
         assert!(kfrags[0].verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
         assert!(!kfrags[0].verify(&signing_pk, None, Some(&receiving_pk)));
 
-        let (delegating_pk, receiving_pk, signing_pk, kfrags) = prepare_kfrags(false, true);
+        let Delegation {
+            delegating_pk,
+            receiving_pk,
+            signing_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(false, true);
         assert!(kfrags[0].verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
         assert!(kfrags[0].verify(&signing_pk, None, Some(&receiving_pk)));
         assert!(!kfrags[0].verify(&signing_pk, Some(&delegating_pk), None));
 
-        let (delegating_pk, receiving_pk, signing_pk, kfrags) = prepare_kfrags(true, false);
+        let Delegation {
+            delegating_pk,
+            receiving_pk,
+            signing_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(true, false);
         assert!(kfrags[0].verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
         assert!(!kfrags[0].verify(&signing_pk, None, Some(&receiving_pk)));
         assert!(kfrags[0].verify(&signing_pk, Some(&delegating_pk), None));
 
-        let (delegating_pk, receiving_pk, signing_pk, kfrags) = prepare_kfrags(false, false);
+        let Delegation {
+            delegating_pk,
+            receiving_pk,
+            signing_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(false, false);
         assert!(kfrags[0].verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
         assert!(kfrags[0].verify(&signing_pk, None, None));
         assert!(!kfrags[0].verify(&delegating_pk, None, None));
     }
+
+    #[test]
+    fn test_verify_with_context() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_pk,
+            kfrags: kfrags_without_context,
+            ..
+        } = prepare_kfrags(true, true);
+
+        let context = b"expires:2030-01-01";
+        let kfrags = generate_kfrags_with_context(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+            Some(context),
+        );
+
+        let dpk = Some(&delegating_pk);
+        let rpk = Some(&receiving_pk);
+        assert!(kfrags[0].verify_with_context(&signing_pk, dpk, rpk, Some(context)));
+        assert!(!kfrags[0].verify_with_context(&signing_pk, dpk, rpk, Some(b"expires:2031-01-01")));
+        assert!(!kfrags[0].verify_with_context(&signing_pk, dpk, rpk, Some(b"")));
+        assert!(!kfrags[0].verify(&signing_pk, dpk, rpk));
+
+        // Kfrags without a context do not verify with one
+        let kfrag = &kfrags_without_context[0];
+        assert!(kfrag.verify_with_context(&signing_pk, dpk, rpk, None));
+        assert!(!kfrag.verify_with_context(&signing_pk, dpk, rpk, Some(context)));
+    }
+
+    #[test]
+    fn test_verify_consistent_set() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(true, true);
+
+        // Another set for the same keys
+        let kfrags2 = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+
+        let verify = |kfrags: &[KeyFrag]| {
+            KeyFrag::verify_consistent_set(
+                kfrags,
+                &signing_pk,
+                Some(&delegating_pk),
+                Some(&receiving_pk),
+            )
+        };
+
+        assert_eq!(verify(&kfrags), Ok(()));
+        assert_eq!(verify(&kfrags2), Ok(()));
+        assert_eq!(verify(&[]), Ok(()));
+
+        // Fragments from different calls
+        let mixed = [kfrags[0].clone(), kfrags[1].clone(), kfrags2[2].clone()];
+        assert_eq!(verify(&mixed), Err(2));
+
+        // Repeating fragments
+        let repeating = [kfrags[0].clone(), kfrags[1].clone(), kfrags[0].clone()];
+        assert_eq!(verify(&repeating), Err(2));
+
+        // A fragment that does not verify
+        assert_eq!(
+            KeyFrag::verify_consistent_set(&kfrags, &delegating_pk, None, None),
+            Err(0)
+        );
+    }
+
+    #[test]
+    fn test_generate_with_ids() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            ..
+        } = prepare_kfrags(true, true);
+
+        let ids: Vec<CurveScalar> = (0..3).map(|_| CurveScalar::random_nonzero()).collect();
+        let kfrags = generate_kfrags_with_ids(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            &ids,
+            true,
+            true,
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(kfrags.len(), ids.len());
+        for (kfrag, id) in kfrags.iter().zip(ids.iter()) {
+            assert_eq!(kfrag.id(), *id);
+            assert!(kfrag.verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
+        }
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let cfrags: Vec<CapsuleFrag> = kfrags[1..]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // Only the IDs are stable: another delegation with the same IDs
+        // produces different shares, which cannot be combined with the old ones
+        let kfrags2 = generate_kfrags_with_ids(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            &ids,
+            true,
+            true,
+            None,
+        )
+        .unwrap();
+        for (kfrag, kfrag2) in kfrags.iter().zip(kfrags2.iter()) {
+            assert_eq!(kfrag.id(), kfrag2.id());
+            assert!(kfrag.key != kfrag2.key);
+            assert!(kfrag.precursor != kfrag2.precursor);
+        }
+        let mixed_cfrags = [
+            reencrypt(&capsule, &kfrags[0], None),
+            reencrypt(&capsule, &kfrags2[1], None),
+        ];
+        assert!(decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &mixed_cfrags,
+            &ciphertext,
+        )
+        .is_none());
+
+        // The context is bound to the signatures, as in `generate_kfrags_with_context()`
+        let context = b"expires 2021-05-01";
+        let kfrags_with_context = generate_kfrags_with_ids(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            &ids,
+            true,
+            true,
+            Some(&context[..]),
+        )
+        .unwrap();
+        for (kfrag, id) in kfrags_with_context.iter().zip(ids.iter()) {
+            assert_eq!(kfrag.id(), *id);
+            assert!(kfrag.verify_with_context(
+                &signing_pk,
+                Some(&delegating_pk),
+                Some(&receiving_pk),
+                Some(&context[..])
+            ));
+            assert!(!kfrag.verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
+        }
+
+        // Zero and repeating IDs are rejected
+        let zero_ids = [ids[0], CurveScalar::default()];
+        let repeating_ids = [ids[0], ids[1], ids[0]];
+        for bad_ids in [&zero_ids[..], &repeating_ids[..]].iter() {
+            assert!(generate_kfrags_with_ids(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                2,
+                bad_ids,
+                true,
+                true,
+                None,
+            )
+            .is_none());
+        }
+    }
+
+    #[test]
+    fn test_reassign() {
+        let Delegation {
+            params,
+            delegating_sk,
+            signing_sk,
+            receiving_pk,
+            ..
+        } = prepare_kfrags(true, true);
+
+        let ids: Vec<CurveScalar> = (0..4).map(|_| CurveScalar::random_nonzero()).collect();
+        let proxy_pks: Vec<PublicKey> = (0..4)
+            .map(|_| PublicKey::from_secret_key(&SecretKey::random()))
+            .collect();
+
+        let make_kfrags = |ids: &[CurveScalar]| {
+            generate_kfrags_with_ids(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                2,
+                ids,
+                true,
+                true,
+                None,
+            )
+            .unwrap()
+        };
+
+        let old_kfrags = make_kfrags(&ids);
+        let old: Vec<(PublicKey, KeyFrag)> = proxy_pks
+            .iter()
+            .cloned()
+            .zip(old_kfrags.iter().cloned())
+            .collect();
+
+        // Re-delegation with the same IDs, in a different order
+        let mut new_ids = ids.clone();
+        new_ids.reverse();
+        let new_kfrags = make_kfrags(&new_ids);
+
+        let assignment = KfragAssignment::reassign(&old, &new_kfrags);
+        assert!(assignment.orphaned_proxies().is_empty());
+        assert!(assignment.unassigned_kfrags().is_empty());
+        assert_eq!(assignment.assigned().len(), old.len());
+        for ((proxy_pk, kfrag), (old_proxy_pk, old_kfrag)) in
+            assignment.assigned().iter().zip(old.iter())
+        {
+            assert_eq!(proxy_pk, old_proxy_pk);
+            assert_eq!(kfrag.id(), old_kfrag.id());
+            assert!(new_kfrags.contains(kfrag));
+        }
+
+        // One of the indices is dropped and replaced by a new one
+        let replacement_id = CurveScalar::random_nonzero();
+        let new_kfrags = make_kfrags(&[ids[0], ids[1], replacement_id, ids[3]]);
+
+        let assignment = KfragAssignment::reassign(&old, &new_kfrags);
+        assert_eq!(assignment.orphaned_proxies(), &[proxy_pks[2]]);
+        assert_eq!(assignment.unassigned_kfrags().len(), 1);
+        assert_eq!(assignment.unassigned_kfrags()[0].id(), replacement_id);
+        let assigned_pks: Vec<PublicKey> = assignment
+            .assigned()
+            .iter()
+            .map(|(proxy_pk, _)| *proxy_pk)
+            .collect();
+        assert_eq!(assigned_pks, [proxy_pks[0], proxy_pks[1], proxy_pks[3]]);
+    }
+
+    #[test]
+    fn test_change_threshold() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            receiving_sk,
+            receiving_pk,
+            ..
+        } = prepare_kfrags(true, true);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        let make_cfrags = |threshold, num_kfrags| -> Vec<CapsuleFrag> {
+            generate_kfrags(
+                &params,
+                &delegating_sk,
+                &receiving_pk,
+                &signing_sk,
+                threshold,
+                num_kfrags,
+                true,
+                true,
+            )
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect()
+        };
+
+        // 2-of-3, then 3-of-5 for the same keys and the same capsule
+        let old_cfrags = make_cfrags(2, 3);
+        let new_cfrags = make_cfrags(3, 5);
+
+        let decrypt = |cfrags: &[CapsuleFrag]| {
+            decrypt_reencrypted(&receiving_sk, &delegating_pk, &capsule, cfrags, &ciphertext)
+        };
+
+        assert_eq!(decrypt(&old_cfrags[..2]).unwrap().as_ref(), plaintext);
+        assert_eq!(decrypt(&new_cfrags[2..]).unwrap().as_ref(), plaintext);
+        assert!(decrypt(&new_cfrags[..2]).is_none());
+
+        // Fragments from the two sets do not mix
+        let mixed = [
+            old_cfrags[0].clone(),
+            new_cfrags[0].clone(),
+            new_cfrags[1].clone(),
+        ];
+        assert!(decrypt(&mixed).is_none());
+    }
 }
@@ -90,27 +90,86 @@
 
 extern crate alloc;
 
+#[cfg(any(test, feature = "std"))]
+extern crate std;
+
 #[macro_use]
 extern crate typenum;
 
 pub mod bench; // Re-export some internals for benchmarks.
+#[cfg(feature = "serde-support")]
+mod bundle;
 mod capsule;
 mod capsule_frag;
-mod constants;
+pub mod codec;
+pub mod convenience;
 mod curve;
+mod decryptor;
 mod dem;
+mod domain;
 mod hashing;
+mod heapless_ciphertext;
+mod key_derivation;
 mod key_frag;
+pub mod math;
 mod params;
+pub mod planning;
 mod pre;
+#[cfg(test)]
+mod randomized_tests;
+#[cfg(feature = "serde-support")]
+mod serde_support;
+#[cfg(feature = "std")]
+mod stream;
+#[cfg(test)]
+mod test_vectors;
 mod traits;
+pub mod wire;
+
+pub use key_frag::{generate_kfrags, generate_kfrags_with_context, generate_kfrags_with_ids};
+pub use pre::{
+    ciphertext_size, decrypt_original, decrypt_original_checked, decrypt_original_detached,
+    decrypt_original_in_place, decrypt_original_in_place_external_nonce, decrypt_original_with_aad,
+    decrypt_original_with_backend, decrypt_reencrypted, decrypt_reencrypted_best_effort,
+    decrypt_reencrypted_checked, decrypt_reencrypted_detached, decrypt_reencrypted_exact,
+    decrypt_reencrypted_from_bytes, decrypt_reencrypted_in_place,
+    decrypt_reencrypted_in_place_external_nonce, decrypt_reencrypted_slim,
+    decrypt_reencrypted_with_aad, decrypt_reencrypted_with_backend,
+    decrypt_reencrypted_with_capsule_check, decrypt_with_key, distribute_kfrags, encrypt,
+    encrypt_detached, encrypt_in_place, encrypt_in_place_external_nonce, encrypt_many,
+    encrypt_signed, encrypt_with_aad, encrypt_with_backend, encrypt_with_key_export,
+    encrypt_with_pairing_tag, open, pairing_tag, reencrypt, reencrypt_capsules, reencrypt_checked,
+    reencrypt_many, reencrypt_observed, reencrypt_with_metadata_commitment, reencrypt_with_rng,
+    seal, sealed_size, verify_pairing, verify_sender, DecryptionError, EncryptionError, PairingTag,
+    ReencryptionError, ReencryptionObserver, MAX_PLAINTEXT_SIZE,
+};
+
+#[cfg(feature = "parallel")]
+pub use pre::reencrypt_batch_parallel;
+
+#[cfg(feature = "std")]
+pub use stream::{decrypt_reader, encrypt_reader, StreamError};
+
+#[cfg(feature = "serde-support")]
+pub use bundle::DelegationBundle;
 
-pub use key_frag::generate_kfrags;
-pub use pre::{decrypt_original, decrypt_reencrypted, encrypt, reencrypt};
+#[cfg(feature = "debug-reconstruction")]
+pub use capsule::ReencryptedOpening;
 
-pub use capsule::Capsule;
-pub use capsule_frag::CapsuleFrag;
-pub use curve::{PublicKey, SecretKey};
-pub use key_frag::KeyFrag;
-pub use params::Parameters;
-pub use traits::SerializableToArray;
+pub use capsule::{Capsule, OpenReencryptedError, CAPSULE_SIZE};
+pub use capsule_frag::{
+    CapsuleFrag, CapsuleFragVerificationError, CfragSet, CfragSetError, MetadataOpening,
+    SlimCapsuleFrag, CAPSULE_FRAG_SIZE,
+};
+pub use curve::{
+    inspect_public_key_bytes, CurvePoint, KeyFormat, PointError, PublicKey, RecoverableSignature,
+    SecretKey, Signature, SignatureError, PUBLIC_KEY_SIZE, SECRET_KEY_SIZE, SIGNATURE_SIZE,
+};
+pub use decryptor::{DecryptorError, ReencryptedDecryptor};
+pub use dem::{
+    derive_key, ChaCha20Poly1305Backend, DemKey, DetachedTag, ExternalNonce, SymmetricBackend,
+};
+pub use heapless_ciphertext::HeaplessCiphertext;
+pub use key_frag::{KeyFrag, KfragAssignment, KEY_FRAG_SIZE};
+pub use params::{Parameters, PARAMETERS_SIZE, STANDARD_U};
+pub use traits::{DeserializationError, SerializableToArray, SerializableToFixedBytes};
@@ -0,0 +1,74 @@
+//! Elliptic curve arithmetic for building custom protocols
+//! on top of the objects used by the scheme (e.g. [`Parameters::u()`](`crate::Parameters::u()`)).
+//!
+//! The types here wrap the ECC backend, so that the downstream code does not depend
+//! on the backend's API, which may change between versions.
+//!
+//! [`CurvePoint`] implements [`Sum`](`core::iter::Sum`),
+//! so points can be accumulated with `iter.sum()`, starting from the identity.
+
+pub use crate::curve::{CurvePoint, CurveScalar};
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+
+    use super::{CurvePoint, CurveScalar};
+
+    #[test]
+    fn test_identity() {
+        let g = CurvePoint::generator();
+        let id = CurvePoint::identity();
+        assert_eq!(g.add(&id), g);
+        assert_eq!(id.add(&g), g);
+        assert_eq!(id.add(&id), id);
+
+        let s = CurveScalar::random_nonzero();
+        assert_eq!(id.mul(&s), id);
+    }
+
+    #[test]
+    fn test_associativity() {
+        let g = CurvePoint::generator();
+        let p1 = g.mul(&CurveScalar::random_nonzero());
+        let p2 = g.mul(&CurveScalar::random_nonzero());
+        let p3 = g.mul(&CurveScalar::random_nonzero());
+
+        assert_eq!(p1.add(&p2).add(&p3), p1.add(&p2.add(&p3)));
+        assert_eq!(p1.add(&p2), p2.add(&p1));
+    }
+
+    #[test]
+    fn test_sum() {
+        let g = CurvePoint::generator();
+        let points: Vec<CurvePoint> = (0..5)
+            .map(|_| g.mul(&CurveScalar::random_nonzero()))
+            .collect();
+
+        let mut expected = CurvePoint::identity();
+        for point in points.iter() {
+            expected = expected.add(point);
+        }
+
+        assert_eq!(points.iter().sum::<CurvePoint>(), expected);
+        assert_eq!(points.iter().cloned().sum::<CurvePoint>(), expected);
+        assert_eq!(
+            core::iter::empty::<CurvePoint>().sum::<CurvePoint>(),
+            CurvePoint::identity()
+        );
+    }
+
+    #[test]
+    fn test_scalar_multiplication() {
+        let g = CurvePoint::generator();
+        let s1 = CurveScalar::random_nonzero();
+        let s2 = CurveScalar::random_nonzero();
+
+        // (g * s1) * s2 == (g * s2) * s1
+        assert_eq!(g.mul(&s1).mul(&s2), g.mul(&s2).mul(&s1));
+
+        // g * (s1 + s2) == g * s1 + g * s2
+        assert_eq!(g.mul(&(&s1 + &s2)), g.mul(&s1).add(&g.mul(&s2)));
+    }
+}
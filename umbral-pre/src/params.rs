@@ -1,8 +1,22 @@
 use crate::curve::CurvePoint;
+use crate::domain::PARAMETERS_U;
 use crate::hashing::unsafe_hash_to_point;
-use crate::traits::SerializableToArray;
+use crate::traits::{
+    DeserializationError, FieldReader, SerializableToArray, SerializableToFixedBytes,
+};
 
 use generic_array::GenericArray;
+use typenum::Unsigned;
+
+/// The second generator `u` of [`Parameters::standard()`], in the compressed SEC1 form.
+///
+/// It equals `unsafe_hash_to_point(g, b"NuCypher/UmbralParameters/u")`,
+/// where `g` is the serialized generator of secp256k1.
+pub const STANDARD_U: [u8; 33] = [
+    0x03, 0xf2, 0x02, 0xdd, 0xc7, 0x6e, 0xcf, 0x32, 0x77, 0x35, 0x97, 0xee, 0x9a, 0x73, 0x80, 0xda,
+    0x1c, 0x0f, 0x02, 0x75, 0x6c, 0x08, 0x56, 0xa1, 0xf2, 0xd9, 0x7b, 0xdd, 0xd1, 0x0e, 0xd1, 0xc4,
+    0x4a,
+];
 
 /// An object containing shared scheme parameters.
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -13,20 +27,68 @@ pub struct Parameters {
 impl Parameters {
     /// Creates a new parameter object.
     pub fn new() -> Self {
-        let g = CurvePoint::generator();
-        let g_bytes = g.to_array();
+        Self {
+            u: derive_u(unsafe_hash_to_point),
+        }
+    }
 
-        let parameters_seed = b"NuCypher/UmbralParameters/u";
+    /// Returns the parameters with the second generator `u` fixed to [`STANDARD_U`]
+    /// (the value produced by [`new()`](`Self::new()`) in this version),
+    /// so that the result does not depend on the hash-to-point implementation.
+    pub fn standard() -> Self {
+        // The bytes are a valid compressed point, which is checked in tests.
+        Self::from_bytes(STANDARD_U).unwrap()
+    }
 
-        // Only fails with a minuscule probability,
-        // or if the size of a point is too large for the hasher.
-        // In any case, we will notice it in tests.
-        let u = unsafe_hash_to_point(&g_bytes, parameters_seed).unwrap();
+    /// Deserializes the parameters, reporting the reason of a failure.
+    ///
+    /// [`SerializableToArray::from_bytes()`] is equivalent, but discards the error.
+    pub fn try_from_bytes(bytes: impl AsRef<[u8]>) -> Result<Self, DeserializationError> {
+        let mut reader = FieldReader::new::<Self>(bytes.as_ref())?;
+        Ok(Self { u: reader.point()? })
+    }
 
-        Self { u }
+    /// Returns the generator point `g` of the curve group.
+    pub fn g(&self) -> CurvePoint {
+        CurvePoint::generator()
+    }
+
+    /// Returns the second generator point `u`,
+    /// used in the correctness proofs of key fragments and capsule fragments.
+    pub fn u(&self) -> CurvePoint {
+        self.u
     }
 }
 
+/// The number of labels [`derive_u()`] tries before giving up.
+const MAX_U_DERIVATION_ATTEMPTS: u64 = 256;
+
+/// Hashes the generator to the second generator `u` with the given hash-to-point function.
+///
+/// The hash fails with a minuscule probability (or if the size of a point
+/// is too large for the hasher, which we would notice in tests).
+/// Instead of panicking in that case, the hash is retried with the label extended
+/// by a big-endian counter (1, 2, ...). The first attempt uses the label as is,
+/// so in practice the result is the one described in [`STANDARD_U`].
+///
+/// Panics if all of [`MAX_U_DERIVATION_ATTEMPTS`] attempts fail,
+/// which means the hash function is broken.
+fn derive_u(hash_to_point: impl Fn(&[u8], &[u8]) -> Option<CurvePoint>) -> CurvePoint {
+    let g_bytes = CurvePoint::generator().to_array();
+
+    if let Some(u) = hash_to_point(&g_bytes, PARAMETERS_U) {
+        return u;
+    }
+
+    (1..MAX_U_DERIVATION_ATTEMPTS)
+        .find_map(|counter| {
+            let mut label = PARAMETERS_U.to_vec();
+            label.extend_from_slice(&counter.to_be_bytes());
+            hash_to_point(&g_bytes, &label)
+        })
+        .expect("hash to point failed for all the labels while deriving the parameter `u`")
+}
+
 impl SerializableToArray for Parameters {
     type Size = <CurvePoint as SerializableToArray>::Size;
 
@@ -40,6 +102,13 @@ impl SerializableToArray for Parameters {
     }
 }
 
+/// The size of serialized [`Parameters`] in bytes.
+pub const PARAMETERS_SIZE: usize = 33;
+
+impl SerializableToFixedBytes<PARAMETERS_SIZE> for Parameters {}
+const _: [(); PARAMETERS_SIZE] =
+    [(); <<Parameters as SerializableToArray>::Size as Unsigned>::USIZE];
+
 impl Default for Parameters {
     fn default() -> Self {
         Self::new()
@@ -49,7 +118,14 @@ impl Default for Parameters {
 #[cfg(test)]
 mod tests {
 
-    use super::Parameters;
+    use alloc::vec::Vec;
+    use core::cell::RefCell;
+
+    use super::{derive_u, Parameters, MAX_U_DERIVATION_ATTEMPTS, STANDARD_U};
+    use crate::curve::{CurvePoint, PointError};
+    use crate::domain::PARAMETERS_U;
+    use crate::hashing::unsafe_hash_to_point;
+    use crate::traits::DeserializationError;
     use crate::SerializableToArray;
 
     #[test]
@@ -58,6 +134,21 @@ mod tests {
         let p_arr = p.to_array();
         let p_back = Parameters::from_array(&p_arr).unwrap();
         assert_eq!(p, p_back);
+
+        assert_eq!(Parameters::try_from_bytes(p_arr), Ok(p));
+        assert_eq!(
+            Parameters::try_from_bytes(&p_arr[1..]),
+            Err(DeserializationError::WrongLength)
+        );
+
+        let mut bad_arr = p_arr;
+        bad_arr[0] = 5;
+        assert_eq!(
+            Parameters::try_from_bytes(bad_arr),
+            Err(DeserializationError::InvalidPoint(
+                PointError::MalformedEncoding
+            ))
+        );
     }
 
     #[test]
@@ -66,4 +157,62 @@ mod tests {
         let p2 = Parameters::default();
         assert_eq!(p1, p2);
     }
+
+    #[test]
+    fn test_accessors() {
+        let p = Parameters::new();
+        assert_eq!(p.u().to_array(), p.to_array());
+        assert_eq!(p.g(), CurvePoint::generator());
+        assert!(p.g() != p.u());
+    }
+
+    #[test]
+    fn test_standard() {
+        let p = Parameters::standard();
+        assert_eq!(p.u().to_array().as_slice(), &STANDARD_U);
+        assert_eq!(p, Parameters::new());
+    }
+
+    #[test]
+    fn test_derive_u_retries() {
+        // A hash that fails for the first two labels
+        let labels = RefCell::new(Vec::new());
+        let failing_hash = |data: &[u8], label: &[u8]| {
+            labels.borrow_mut().push(label.to_vec());
+            if labels.borrow().len() <= 2 {
+                None
+            } else {
+                unsafe_hash_to_point(data, label)
+            }
+        };
+
+        let u = derive_u(failing_hash);
+
+        let labels = labels.into_inner();
+        assert_eq!(labels.len(), 3);
+        assert_eq!(labels[0], PARAMETERS_U);
+        for (counter, label) in labels[1..].iter().enumerate() {
+            let mut expected = PARAMETERS_U.to_vec();
+            expected.extend_from_slice(&(counter as u64 + 1).to_be_bytes());
+            assert_eq!(label, &expected);
+        }
+
+        let g_bytes = CurvePoint::generator().to_array();
+        assert_eq!(Some(u), unsafe_hash_to_point(&g_bytes, &labels[2]));
+        assert!(u != Parameters::new().u());
+
+        // Without failures the label is used as is
+        assert_eq!(derive_u(unsafe_hash_to_point), Parameters::standard().u());
+    }
+
+    #[test]
+    #[should_panic(expected = "hash to point failed for all the labels")]
+    fn test_derive_u_gives_up() {
+        let attempts = RefCell::new(0u64);
+        let _ = derive_u(|_data: &[u8], _label: &[u8]| {
+            *attempts.borrow_mut() += 1;
+            assert!(*attempts.borrow() <= MAX_U_DERIVATION_ATTEMPTS);
+            None
+        });
+    }
 }
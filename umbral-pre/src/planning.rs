@@ -0,0 +1,170 @@
+//! Helpers for deciding how many proxies to involve in a reencryption.
+
+use libm::{exp, lgamma, log, log1p};
+
+/// The largest number of proxies [`recommended_query_count()`] will consider.
+const MAX_QUERY_COUNT: usize = 1 << 16;
+
+/// Returns the logarithm of the probability of exactly `k` successes
+/// out of `total` independent attempts, each succeeding with probability `p`.
+///
+/// Computed in log space, since for a large `total` the individual factors
+/// (e.g. `(1 - p)^total`) underflow even when the probability itself does not.
+fn log_pmf(k: usize, total: usize, log_p: f64, log_q: f64) -> f64 {
+    let n = total as f64;
+    let k_f = k as f64;
+    lgamma(n + 1.0) - lgamma(k_f + 1.0) - lgamma(n - k_f + 1.0) + k_f * log_p + (n - k_f) * log_q
+}
+
+/// Returns the probability of at least `threshold` successes
+/// out of `total` independent attempts, each succeeding with probability `p`.
+fn success_probability(threshold: usize, total: usize, p: f64) -> f64 {
+    if total < threshold {
+        return 0.0;
+    }
+    if threshold == 0 || p == 1.0 {
+        return 1.0;
+    }
+    if p == 0.0 {
+        return 0.0;
+    }
+
+    let log_p = log(p);
+    let log_q = log1p(-p);
+
+    // The probability of failing the threshold, as a sum of exponents
+    // scaled by the largest one to avoid underflow.
+    let log_pmfs = (0..threshold).map(|k| log_pmf(k, total, log_p, log_q));
+    let max_log_pmf = log_pmfs.clone().fold(f64::NEG_INFINITY, f64::max);
+    let scaled_sum: f64 = log_pmfs.map(|l| exp(l - max_log_pmf)).sum();
+    let failure = exp(max_log_pmf + log(scaled_sum));
+
+    1.0 - failure.min(1.0)
+}
+
+/// Returns the minimum number of proxies to send reencryption requests to,
+/// so that with probability at least `target` at least `threshold` of them respond,
+/// assuming each proxy responds independently with probability `proxy_success_prob`.
+///
+/// Returns `None` if the probabilities are not in the range `[0, 1]`,
+/// or if the target cannot be reached
+/// (e.g. `target` is 1 while proxies are not perfectly reliable).
+pub fn recommended_query_count(
+    threshold: usize,
+    proxy_success_prob: f64,
+    target: f64,
+) -> Option<usize> {
+    if !(0.0..=1.0).contains(&proxy_success_prob) || !(0.0..=1.0).contains(&target) {
+        return None;
+    }
+
+    if threshold == 0 {
+        return Some(0);
+    }
+
+    // With unreliable proxies certainty is never reached
+    // (although the computed probability may round up to 1 for a large number of proxies).
+    if proxy_success_prob == 0.0 || (target == 1.0 && proxy_success_prob < 1.0) {
+        return None;
+    }
+
+    // The success probability grows with the number of proxies,
+    // so the smallest sufficient number can be found with a binary search.
+    let is_enough =
+        |total: usize| success_probability(threshold, total, proxy_success_prob) >= target;
+
+    if threshold > MAX_QUERY_COUNT || !is_enough(MAX_QUERY_COUNT) {
+        return None;
+    }
+
+    let mut low = threshold;
+    let mut high = MAX_QUERY_COUNT;
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if is_enough(mid) {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Some(low)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{recommended_query_count, success_probability};
+
+    fn approx_eq(x: f64, y: f64) -> bool {
+        x - y < 1e-12 && y - x < 1e-12
+    }
+
+    #[test]
+    fn test_success_probability() {
+        // At least 2 out of 3 with p = 0.5: (3 + 1) / 8
+        let prob = success_probability(2, 3, 0.5);
+        assert!(approx_eq(prob, 0.5));
+
+        // All 3 out of 3 with p = 0.9
+        let prob = success_probability(3, 3, 0.9);
+        assert!(approx_eq(prob, 0.729));
+
+        assert_eq!(success_probability(3, 2, 0.9), 0.0);
+
+        // (1 - p)^total underflows here, but the tail probability does not.
+        // Exact value: 1 - sum(C(2106, k), k < 1000) / 2^2106.
+        let prob = success_probability(1000, 2106, 0.5);
+        assert!((prob - 0.990149985510403).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_large_threshold() {
+        // Exact values, computed with arbitrary precision integers:
+        // 2106 is the smallest `total` with at least 1000 successes
+        // with probability 0.99 or more (0.99015 vs 0.98958 for 2105).
+        assert_eq!(recommended_query_count(1000, 0.5, 0.99), Some(2106));
+    }
+
+    #[test]
+    fn test_boundaries() {
+        // Perfectly reliable proxies: the threshold is enough
+        assert_eq!(recommended_query_count(3, 1.0, 1.0), Some(3));
+        assert_eq!(recommended_query_count(3, 1.0, 0.5), Some(3));
+
+        // A low target can be reached with exactly `threshold` proxies
+        assert_eq!(recommended_query_count(3, 0.9, 0.7), Some(3));
+        assert_eq!(recommended_query_count(3, 0.9, 0.0), Some(3));
+
+        assert_eq!(recommended_query_count(0, 0.5, 0.99), Some(0));
+
+        // Unreachable targets and invalid inputs
+        assert_eq!(recommended_query_count(3, 0.9, 1.0), None);
+        assert_eq!(recommended_query_count(3, 0.0, 0.5), None);
+        assert_eq!(recommended_query_count(3, 1.5, 0.5), None);
+        assert_eq!(recommended_query_count(3, 0.5, -0.1), None);
+        assert_eq!(recommended_query_count(3, f64::NAN, 0.5), None);
+    }
+
+    #[test]
+    fn test_monotonicity() {
+        let threshold = 5;
+
+        // More demanding targets need more proxies
+        let mut prev = 0;
+        for &target in [0.5, 0.9, 0.99, 0.999, 0.999999].iter() {
+            let count = recommended_query_count(threshold, 0.8, target).unwrap();
+            assert!(count >= threshold);
+            assert!(count >= prev);
+            prev = count;
+        }
+
+        // More reliable proxies need fewer queries
+        let mut prev = usize::MAX;
+        for &prob in [0.3, 0.5, 0.8, 0.95, 1.0].iter() {
+            let count = recommended_query_count(threshold, prob, 0.99).unwrap();
+            assert!(count <= prev);
+            prev = count;
+        }
+        assert_eq!(prev, threshold);
+    }
+}
@@ -1,32 +1,393 @@
 //! The high-level functional reencryption API.
 
 use crate::capsule::Capsule;
-use crate::capsule_frag::CapsuleFrag;
-use crate::curve::{PublicKey, SecretKey};
-use crate::dem::UmbralDEM;
+use crate::capsule_frag::{CapsuleFrag, MetadataOpening, SlimCapsuleFrag};
+use crate::curve::{CurveScalar, PublicKey, RecoverableSignature, SecretKey};
+use crate::dem::{DemKey, DetachedTag, ExternalNonce, SymmetricBackend, UmbralDEM};
+use crate::domain::{CAPSULE_SENDER, PAIRING_TAG};
 use crate::key_frag::KeyFrag;
 use crate::params::Parameters;
 use crate::traits::SerializableToArray;
 
 use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+
+use generic_array::GenericArray;
+use rand_core::{CryptoRng, RngCore};
+use sha3::{Digest, Sha3_256};
+
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+use typenum::{Unsigned, U32};
+
+/// The maximum size of a plaintext that can be encrypted in one go
+/// (the message size limit of ChaCha20-Poly1305, see RFC 8439).
+pub const MAX_PLAINTEXT_SIZE: u64 = (1 << 38) - 64;
+
+/// Errors that can happen during encryption.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EncryptionError {
+    /// The plaintext is larger than [`MAX_PLAINTEXT_SIZE`].
+    PlaintextTooLarge,
+    /// The provided buffer does not have enough room for the ciphertext.
+    BufferTooSmall,
+    /// The symmetric backend (the built-in one, or a custom one
+    /// given to [`encrypt_with_backend()`]) failed to encrypt the plaintext.
+    BackendFailure,
+}
+
+impl fmt::Display for EncryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PlaintextTooLarge => write!(
+                f,
+                "Plaintext is larger than the maximum of {} bytes",
+                MAX_PLAINTEXT_SIZE
+            ),
+            Self::BufferTooSmall => write!(f, "Buffer is too small for the ciphertext"),
+            Self::BackendFailure => write!(f, "Symmetric backend failed to encrypt the plaintext"),
+        }
+    }
+}
+
+/// Errors that can happen during reencryption
+/// (returned by [`reencrypt_checked()`] and [`reencrypt_capsules()`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReencryptionError {
+    /// The key fragment was created with different [`Parameters`] than the capsule,
+    /// so the resulting capsule fragment would fail verification.
+    ParameterMismatch,
+    /// The key fragment failed verification.
+    InvalidKeyFrag,
+}
+
+impl fmt::Display for ReencryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::ParameterMismatch => write!(
+                f,
+                "The key fragment and the capsule were created with different parameters"
+            ),
+            Self::InvalidKeyFrag => write!(f, "Key fragment verification failed"),
+        }
+    }
+}
+
+/// Errors that can happen during decryption
+/// (returned by [`decrypt_original_checked()`] and [`decrypt_reencrypted_checked()`]).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DecryptionError {
+    /// The ciphertext is shorter than the nonce and the authentication tag of the DEM,
+    /// so it could not have been produced by the encryption.
+    CiphertextTooShort,
+    /// The ciphertext could not be decrypted: either the key is wrong,
+    /// or the ciphertext or the capsule were modified.
+    DecryptionFailed,
+    /// The capsule does not match the expected digest
+    /// (see [`decrypt_reencrypted_with_capsule_check()`]).
+    CapsuleMismatch,
+    /// The capsule fragment with the given index could not be deserialized,
+    /// or failed verification
+    /// (see [`decrypt_reencrypted_from_bytes()`]).
+    InvalidCapsuleFrag(usize),
+    /// Fewer capsule fragments than the threshold were given
+    /// (see [`decrypt_reencrypted_exact()`]).
+    TooFewFragments,
+    /// More capsule fragments than the threshold were given
+    /// (see [`decrypt_reencrypted_exact()`]).
+    TooManyFragments,
+}
+
+impl fmt::Display for DecryptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CiphertextTooShort => write!(
+                f,
+                "Ciphertext must be at least {} bytes long",
+                UmbralDEM::min_ciphertext_size()
+            ),
+            Self::DecryptionFailed => write!(f, "Decryption of the ciphertext failed"),
+            Self::CapsuleMismatch => write!(f, "The capsule does not match the expected digest"),
+            Self::InvalidCapsuleFrag(index) => {
+                write!(f, "Capsule fragment {} is invalid", index)
+            }
+            Self::TooFewFragments => write!(f, "Too few capsule fragments"),
+            Self::TooManyFragments => write!(f, "Too many capsule fragments"),
+        }
+    }
+}
+
+fn check_ciphertext_size(ciphertext: &[u8]) -> Result<(), DecryptionError> {
+    if ciphertext.len() < UmbralDEM::min_ciphertext_size() {
+        return Err(DecryptionError::CiphertextTooShort);
+    }
+    Ok(())
+}
+
+fn check_plaintext_size(size: usize) -> Result<(), EncryptionError> {
+    if size as u64 > MAX_PLAINTEXT_SIZE {
+        return Err(EncryptionError::PlaintextTooLarge);
+    }
+    Ok(())
+}
 
 /// Encrypts the given plaintext message using a DEM scheme,
 /// and encapsulates the key for later reencryption.
 /// Returns the KEM [`Capsule`] and the ciphertext.
+///
+/// The plaintext can be empty, in which case the ciphertext
+/// only consists of the nonce and the authentication tag.
+/// Fails if the plaintext is larger than [`MAX_PLAINTEXT_SIZE`].
 pub fn encrypt(
     params: &Parameters,
     pk: &PublicKey,
     plaintext: &[u8],
-) -> Option<(Capsule, Box<[u8]>)> {
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    check_plaintext_size(plaintext.len())?;
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+    let ciphertext = dem
+        .encrypt(plaintext, &capsule_bytes)
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, ciphertext))
+}
+
+/// A variant of [`encrypt()`] that additionally authenticates (but does not encrypt)
+/// the concatenation of `aad_chunks`, e.g. a file header stored in the clear.
+///
+/// The chunks are hashed incrementally, so the authenticated data
+/// does not have to be assembled in a single buffer.
+/// The ciphertext can be decrypted with [`decrypt_original_with_aad()`]
+/// or [`decrypt_reencrypted_with_aad()`], given the same authenticated data
+/// (not necessarily split into the same chunks).
+pub fn encrypt_with_aad(
+    params: &Parameters,
+    pk: &PublicKey,
+    plaintext: &[u8],
+    aad_chunks: &[&[u8]],
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    check_plaintext_size(plaintext.len())?;
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+    let ciphertext = dem
+        .encrypt_with_aad_chunks(plaintext, &with_capsule(&capsule_bytes, aad_chunks))
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, ciphertext))
+}
+
+/// Prepends the capsule to the authenticated data chunks.
+fn with_capsule<'a>(capsule_bytes: &'a [u8], aad_chunks: &[&'a [u8]]) -> Vec<&'a [u8]> {
+    let mut chunks = Vec::with_capacity(aad_chunks.len() + 1);
+    chunks.push(capsule_bytes);
+    chunks.extend_from_slice(aad_chunks);
+    chunks
+}
+
+/// Same as [`encrypt()`], but encrypts the plaintext with the given symmetric backend
+/// instead of the built-in one.
+///
+/// The ciphertext can be decrypted with [`decrypt_original_with_backend()`]
+/// or [`decrypt_reencrypted_with_backend()`] and the same backend.
+/// With [`ChaCha20Poly1305Backend`](`crate::ChaCha20Poly1305Backend`)
+/// this is equivalent to [`encrypt()`].
+/// Fails if the plaintext is larger than [`MAX_PLAINTEXT_SIZE`],
+/// or if the backend fails to encrypt it.
+pub fn encrypt_with_backend(
+    backend: &impl SymmetricBackend,
+    params: &Parameters,
+    pk: &PublicKey,
+    plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>), EncryptionError> {
+    check_plaintext_size(plaintext.len())?;
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem_key = backend.derive_dem_key(&key_seed.to_array());
+    let ciphertext = backend
+        .encrypt(&dem_key, plaintext, &capsule.to_array())
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, ciphertext))
+}
+
+/// Encrypts several plaintexts under a single encapsulated key,
+/// so that one [`Capsule`] (and one reencryption of it) grants access to all of them.
+///
+/// The symmetric key is derived once, and each plaintext is encrypted with a fresh random nonce,
+/// so the ciphertexts are independent and can be decrypted separately
+/// (with [`decrypt_original()`] or [`decrypt_reencrypted()`] and the returned capsule).
+/// The ciphertexts are returned in the same order as `plaintexts`.
+/// Fails if any of the plaintexts is larger than [`MAX_PLAINTEXT_SIZE`].
+///
+/// **Warning:** every ciphertext is authenticated with the same key and the same data
+/// (the capsule), so only each ciphertext on its own is authenticated,
+/// and not their order or the set as a whole.
+/// Anyone can reorder, drop, duplicate, or swap ciphertexts between the positions,
+/// and each of them will still decrypt.
+/// If that matters, put the index and the total number into the plaintexts,
+/// or encrypt them with [`encrypt_with_aad()`] (with a separate capsule each).
+#[allow(clippy::type_complexity)]
+pub fn encrypt_many(
+    params: &Parameters,
+    pk: &PublicKey,
+    plaintexts: &[&[u8]],
+) -> Result<(Capsule, Box<[Box<[u8]>]>), EncryptionError> {
+    for plaintext in plaintexts {
+        check_plaintext_size(plaintext.len())?;
+    }
     let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
     let dem = UmbralDEM::new(&key_seed.to_array());
     let capsule_bytes = capsule.to_array();
-    let ciphertext = dem.encrypt(plaintext, &capsule_bytes)?;
-    Some((capsule, ciphertext))
+    let ciphertexts = plaintexts
+        .iter()
+        .map(|plaintext| {
+            dem.encrypt(plaintext, &capsule_bytes)
+                .ok_or(EncryptionError::BackendFailure)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok((capsule, ciphertexts.into_boxed_slice()))
+}
+
+/// Encrypts each of `kfrags` to the corresponding proxy key from `proxy_pks`,
+/// so that the key fragments do not travel to the proxies in the clear.
+///
+/// The proxy with the `i`-th key decrypts the `i`-th result with [`decrypt_original()`],
+/// obtaining the serialized key fragment (see [`KeyFrag::from_bytes()`]).
+/// Each ciphertext is larger than a serialized key fragment by the DEM overhead
+/// (a nonce and an authentication tag), and is accompanied by its own capsule.
+///
+/// Returns `None` if the numbers of key fragments and proxy keys differ.
+#[allow(clippy::type_complexity)]
+pub fn distribute_kfrags(
+    params: &Parameters,
+    kfrags: &[KeyFrag],
+    proxy_pks: &[PublicKey],
+) -> Option<Box<[(Capsule, Box<[u8]>)]>> {
+    if kfrags.len() != proxy_pks.len() {
+        return None;
+    }
+
+    kfrags
+        .iter()
+        .zip(proxy_pks.iter())
+        .map(|(kfrag, proxy_pk)| encrypt(params, proxy_pk, &kfrag.to_array()).ok())
+        .collect()
+}
+
+/// Same as [`encrypt()`], but additionally returns the symmetric key
+/// the plaintext was encrypted with (e.g. to be escrowed with a third party).
+///
+/// The ciphertext can then be decrypted with this key alone via [`decrypt_with_key()`],
+/// so it must be protected as carefully as the secret key.
+pub fn encrypt_with_key_export(
+    params: &Parameters,
+    pk: &PublicKey,
+    plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>, DemKey), EncryptionError> {
+    check_plaintext_size(plaintext.len())?;
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem_key = DemKey::from_key_seed(&key_seed.to_array());
+    let dem = UmbralDEM::from_key(&dem_key);
+    let ciphertext = dem
+        .encrypt(plaintext, &capsule.to_array())
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, ciphertext, dem_key))
+}
+
+/// Decrypts the ciphertext using the symmetric key returned by [`encrypt_with_key_export()`].
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_with_key(
+    dem_key: &DemKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+) -> Option<Box<[u8]>> {
+    let dem = UmbralDEM::from_key(dem_key);
+    dem.decrypt(ciphertext, &capsule.to_array())
+}
+
+/// A tag binding a capsule to a ciphertext (see [`pairing_tag()`]).
+pub type PairingTag = GenericArray<u8, U32>;
+
+/// Returns a tag binding the capsule to the ciphertext created along with it:
+/// the SHA3-256 hash of a domain separation string, the serialized capsule, and the ciphertext.
+///
+/// If the capsule and the ciphertext are stored or transmitted separately,
+/// [`verify_pairing()`] can then detect a mix-up with a single hash
+/// instead of a failed decryption after opening the capsule.
+/// The tag is not keyed, so anyone can compute it for any pair;
+/// it only protects against a malicious swap if the tag itself
+/// comes from a trusted source.
+/// The decryption still fails on a mismatched pair regardless of the tag.
+pub fn pairing_tag(capsule: &Capsule, ciphertext: impl AsRef<[u8]>) -> PairingTag {
+    Sha3_256::new()
+        .chain(PAIRING_TAG)
+        .chain(capsule.to_array())
+        .chain(ciphertext.as_ref())
+        .finalize()
+}
+
+/// Checks that `tag` was produced by [`pairing_tag()`] for the given capsule and ciphertext.
+#[must_use = "the verification result must be checked"]
+pub fn verify_pairing(capsule: &Capsule, ciphertext: impl AsRef<[u8]>, tag: &[u8]) -> bool {
+    pairing_tag(capsule, ciphertext).as_slice() == tag
+}
+
+/// Same as [`encrypt()`], but additionally returns the [`pairing_tag()`]
+/// of the capsule and the ciphertext.
+pub fn encrypt_with_pairing_tag(
+    params: &Parameters,
+    pk: &PublicKey,
+    plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>, PairingTag), EncryptionError> {
+    let (capsule, ciphertext) = encrypt(params, pk, plaintext)?;
+    let tag = pairing_tag(&capsule, &ciphertext);
+    Ok((capsule, ciphertext, tag))
+}
+
+fn sender_message(capsule: &Capsule) -> Vec<u8> {
+    let mut message = CAPSULE_SENDER.to_vec();
+    message.extend_from_slice(&capsule.to_array());
+    message
+}
+
+/// Same as [`encrypt()`], but additionally signs the capsule with `sender_sk`,
+/// so that the recipient can check who created it with [`verify_sender()`].
+///
+/// Since the ciphertext is authenticated with the capsule as associated data,
+/// the signature covers it as well, as far as anyone but the holders of the decryption key
+/// (who can create new ciphertexts for the same capsule) are concerned.
+pub fn encrypt_signed(
+    params: &Parameters,
+    pk: &PublicKey,
+    sender_sk: &SecretKey,
+    plaintext: &[u8],
+) -> Result<(Capsule, Box<[u8]>, RecoverableSignature), EncryptionError> {
+    let (capsule, ciphertext) = encrypt(params, pk, plaintext)?;
+    let signature = sender_sk.sign_recoverable(&sender_message(&capsule));
+    Ok((capsule, ciphertext, signature))
+}
+
+/// Verifies that `capsule` was signed by the owner of `sender_pk`
+/// (see [`encrypt_signed()`]).
+#[must_use = "the verification result must be checked"]
+pub fn verify_sender(
+    capsule: &Capsule,
+    sender_pk: &PublicKey,
+    signature: &RecoverableSignature,
+) -> bool {
+    PublicKey::recover(&sender_message(capsule), signature).as_ref() == Some(sender_pk)
 }
 
 /// Attempts to decrypt the ciphertext using the original encryptor's
 /// secret key.
+///
+/// Returns `None` both if `decrypting_sk` is not the key the capsule was created for,
+/// and if the ciphertext was modified. These cases cannot be told apart:
+/// the capsule points are random and carry no commitment to the recipient's key,
+/// so the only way to check the key is to derive the symmetric key from it
+/// and see whether the ciphertext authenticates.
+/// (A modified capsule, on the other hand, is rejected on deserialization.)
+#[must_use = "a failed decryption is only reported in the return value"]
 pub fn decrypt_original(
     decrypting_sk: &SecretKey,
     capsule: &Capsule,
@@ -37,6 +398,250 @@ pub fn decrypt_original(
     dem.decrypt(ciphertext, &capsule.to_array())
 }
 
+/// Same as [`decrypt_original()`], for a ciphertext produced by [`encrypt_with_backend()`].
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_original_with_backend(
+    backend: &impl SymmetricBackend,
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+) -> Option<Box<[u8]>> {
+    let key_seed = capsule.open_original(decrypting_sk);
+    let dem_key = backend.derive_dem_key(&key_seed.to_array());
+    backend.decrypt(&dem_key, ciphertext.as_ref(), &capsule.to_array())
+}
+
+/// Same as [`decrypt_original()`], but reports the reason of a failure.
+///
+/// The ciphertext length is checked before any decryption is attempted.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_original_checked(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<Box<[u8]>, DecryptionError> {
+    check_ciphertext_size(ciphertext.as_ref())?;
+    decrypt_original(decrypting_sk, capsule, ciphertext).ok_or(DecryptionError::DecryptionFailed)
+}
+
+/// Attempts to decrypt the ciphertext produced by [`encrypt_with_aad()`]
+/// using the original encryptor's secret key.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_original_with_aad(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    ciphertext: impl AsRef<[u8]>,
+    aad_chunks: &[&[u8]],
+) -> Option<Box<[u8]>> {
+    let key_seed = capsule.open_original(decrypting_sk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+    dem.decrypt_with_aad_chunks(ciphertext, &with_capsule(&capsule_bytes, aad_chunks))
+}
+
+/// A variant of [`encrypt()`] operating on a caller-provided buffer instead of allocating.
+///
+/// `buf[..len]` is treated as the plaintext.
+/// On success, it is replaced by the ciphertext,
+/// and the KEM [`Capsule`] is returned along with the length of the ciphertext.
+/// The ciphertext is 28 bytes longer than the plaintext (the nonce and the authentication tag),
+/// so `buf` must have at least that much room after `len`,
+/// otherwise [`EncryptionError::BufferTooSmall`] is returned.
+pub fn encrypt_in_place(
+    params: &Parameters,
+    pk: &PublicKey,
+    buf: &mut [u8],
+    len: usize,
+) -> Result<(Capsule, usize), EncryptionError> {
+    check_plaintext_size(len)?;
+    if len > buf.len() || buf.len() - len < UmbralDEM::min_ciphertext_size() {
+        return Err(EncryptionError::BufferTooSmall);
+    }
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+    let ciphertext_len = dem
+        .encrypt_in_place(buf, len, &capsule_bytes)
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, ciphertext_len))
+}
+
+/// A variant of [`decrypt_original()`] operating on a caller-provided buffer
+/// instead of allocating.
+///
+/// `buf[..len]` is treated as the ciphertext.
+/// On success, the plaintext is placed at the beginning of `buf`, and its length is returned.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_original_in_place(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    buf: &mut [u8],
+    len: usize,
+) -> Option<usize> {
+    let key_seed = capsule.open_original(decrypting_sk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt_in_place(buf, len, &capsule.to_array())
+}
+
+/// A variant of [`encrypt_in_place()`] that does not store the nonce in the buffer,
+/// for the cases when it is kept elsewhere.
+///
+/// `buf[..len]` is treated as the plaintext.
+/// On success, it is replaced by the ciphertext followed by the authentication tag
+/// (16 bytes, so `buf` must have at least that much room after `len`),
+/// and the KEM [`Capsule`] is returned along with the nonce and the length of the ciphertext.
+pub fn encrypt_in_place_external_nonce(
+    params: &Parameters,
+    pk: &PublicKey,
+    buf: &mut [u8],
+    len: usize,
+) -> Result<(Capsule, ExternalNonce, usize), EncryptionError> {
+    check_plaintext_size(len)?;
+    if len > buf.len() || buf.len() - len < UmbralDEM::tag_size() {
+        return Err(EncryptionError::BufferTooSmall);
+    }
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+    let (nonce, ciphertext_len) = dem
+        .encrypt_in_place_external_nonce(buf, len, &capsule_bytes)
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, nonce, ciphertext_len))
+}
+
+/// A variant of [`decrypt_original_in_place()`] for ciphertexts
+/// produced by [`encrypt_in_place_external_nonce()`].
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_original_in_place_external_nonce(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    buf: &mut [u8],
+    len: usize,
+    nonce: &ExternalNonce,
+) -> Option<usize> {
+    let key_seed = capsule.open_original(decrypting_sk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt_in_place_external_nonce(buf, len, &capsule.to_array(), nonce)
+}
+
+/// The version of the blob format produced by [`seal()`].
+const SEALED_FORMAT_VERSION: u8 = 1;
+
+/// Encrypts the plaintext for the owner of `pk` with the default [`Parameters`],
+/// and packs the result into a single self-describing blob
+/// (a format version byte, the serialized [`Capsule`], and the ciphertext).
+///
+/// The blob can be decrypted with [`open()`].
+pub fn seal(pk: &PublicKey, plaintext: &[u8]) -> Option<Box<[u8]>> {
+    let params = Parameters::new();
+    let (capsule, ciphertext) = encrypt(&params, pk, plaintext).ok()?;
+    let capsule_bytes = capsule.to_array();
+
+    let mut result = Vec::with_capacity(sealed_size(plaintext.len()));
+    result.push(SEALED_FORMAT_VERSION);
+    result.extend_from_slice(&capsule_bytes);
+    result.extend_from_slice(&ciphertext);
+    Some(result.into_boxed_slice())
+}
+
+/// Returns the length of the ciphertext produced by [`encrypt()`]
+/// for a plaintext of `plaintext_len` bytes (the plaintext, the nonce and the tag).
+pub fn ciphertext_size(plaintext_len: usize) -> usize {
+    plaintext_len + UmbralDEM::min_ciphertext_size()
+}
+
+/// Returns the length of the blob produced by [`seal()`]
+/// for a plaintext of `plaintext_len` bytes, without performing the encryption.
+pub fn sealed_size(plaintext_len: usize) -> usize {
+    1 + <Capsule as SerializableToArray>::Size::to_usize() + ciphertext_size(plaintext_len)
+}
+
+/// Decrypts a blob produced by [`seal()`] using the secret key of the recipient.
+///
+/// Returns `None` if the blob has an unknown version, is malformed, or was tampered with.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn open(decrypting_sk: &SecretKey, sealed: impl AsRef<[u8]>) -> Option<Box<[u8]>> {
+    let sealed = sealed.as_ref();
+    let (version, rest) = sealed.split_first()?;
+    if *version != SEALED_FORMAT_VERSION {
+        return None;
+    }
+
+    let capsule_size = <Capsule as SerializableToArray>::Size::to_usize();
+    if rest.len() < capsule_size {
+        return None;
+    }
+    let (capsule_bytes, ciphertext) = rest.split_at(capsule_size);
+    let capsule = Capsule::from_bytes(capsule_bytes)?;
+    decrypt_original(decrypting_sk, &capsule, ciphertext)
+}
+
+/// A variant of [`encrypt()`] that encrypts `data` in place, keeping its length,
+/// and returns the nonce and the authentication tag separately in a [`DetachedTag`].
+///
+/// Useful when the ciphertext must occupy exactly the space of the plaintext,
+/// and the tag is stored elsewhere.
+/// Fails if `data` is larger than [`MAX_PLAINTEXT_SIZE`].
+pub fn encrypt_detached(
+    params: &Parameters,
+    pk: &PublicKey,
+    data: &mut [u8],
+) -> Result<(Capsule, DetachedTag), EncryptionError> {
+    check_plaintext_size(data.len())?;
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let tag = dem
+        .encrypt_detached(data, &capsule.to_array())
+        .ok_or(EncryptionError::BackendFailure)?;
+    Ok((capsule, tag))
+}
+
+/// Decrypts `data` produced by [`encrypt_detached()`] in place,
+/// using the original encryptor's secret key.
+///
+/// Returns `None` if the decryption fails, in which case `data` is left unchanged.
+/// Since the plaintext is not returned, it is especially important to check the result;
+/// discarding it is reported by the compiler:
+///
+/// ```compile_fail
+/// #![deny(unused_must_use)]
+/// use umbral_pre::{decrypt_original_detached, Capsule, DetachedTag, SecretKey};
+///
+/// fn decrypt(sk: &SecretKey, capsule: &Capsule, data: &mut [u8], tag: &DetachedTag) {
+///     decrypt_original_detached(sk, capsule, data, tag);
+/// }
+/// ```
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_original_detached(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    data: &mut [u8],
+    tag: &DetachedTag,
+) -> Option<()> {
+    let key_seed = capsule.open_original(decrypting_sk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt_detached(data, &capsule.to_array(), tag)
+}
+
+/// Decrypts `data` produced by [`encrypt_detached()`] in place,
+/// using previously reencrypted capsule fragments
+/// (see [`decrypt_reencrypted()`] for details).
+///
+/// Returns `None` if the decryption fails, in which case `data` is left unchanged.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_detached(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    data: &mut [u8],
+    tag: &DetachedTag,
+) -> Option<()> {
+    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags.as_ref())?;
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt_detached(data, &capsule.to_array(), tag)
+}
+
 /// Reencrypts a [`Capsule`] object with a key fragment, creating a capsule fragment.
 ///
 /// Having `threshold` (see [`generate_kfrags()`](`crate::generate_kfrags()`))
@@ -48,6 +653,144 @@ pub fn reencrypt(capsule: &Capsule, kfrag: &KeyFrag, metadata: Option<&[u8]>) ->
     CapsuleFrag::reencrypted(capsule, kfrag, metadata)
 }
 
+/// Same as [`reencrypt()`], but first checks that the key fragment and the capsule
+/// were created with the same [`Parameters`]
+/// (see [`KeyFrag::params()`] and [`Capsule::params()`]).
+pub fn reencrypt_checked(
+    capsule: &Capsule,
+    kfrag: &KeyFrag,
+    metadata: Option<&[u8]>,
+) -> Result<CapsuleFrag, ReencryptionError> {
+    if !capsule.params_match(&kfrag.params()) {
+        return Err(ReencryptionError::ParameterMismatch);
+    }
+    Ok(reencrypt(capsule, kfrag, metadata))
+}
+
+/// Reencrypts several [`Capsule`] objects with the same key fragment
+/// (e.g. one capsule per encrypted record of a policy).
+///
+/// If `verifying_keys` is given, it contains `(signing_pk, delegating_pk, receiving_pk)`,
+/// and the key fragment is checked with them once for the whole batch
+/// with [`KeyFrag::verify()`], and an error is returned if the check fails.
+///
+/// The results are returned in the same order as `capsules`.
+/// An element is `None` if the capsule was created with different [`Parameters`]
+/// than the key fragment (see [`reencrypt_checked()`]).
+pub fn reencrypt_capsules(
+    kfrag: &KeyFrag,
+    capsules: &[Capsule],
+    metadata: Option<&[u8]>,
+    verifying_keys: Option<(&PublicKey, Option<&PublicKey>, Option<&PublicKey>)>,
+) -> Result<Vec<Option<CapsuleFrag>>, ReencryptionError> {
+    if let Some((signing_pk, delegating_pk, receiving_pk)) = verifying_keys {
+        if !kfrag.verify(signing_pk, delegating_pk, receiving_pk) {
+            return Err(ReencryptionError::InvalidKeyFrag);
+        }
+    }
+
+    Ok(capsules
+        .iter()
+        .map(|capsule| reencrypt_checked(capsule, kfrag, metadata).ok())
+        .collect())
+}
+
+/// Reencrypts a [`Capsule`] with several key fragments
+/// (e.g. when a proxy holds more than one fragment of the same policy).
+///
+/// Each key fragment is checked with [`KeyFrag::verify()`] using `signing_pk`,
+/// `delegating_pk` and `receiving_pk`.
+/// The results are returned in exactly the same order as `kfrags`,
+/// with `None` in place of the key fragments that failed verification
+/// or were created with different [`Parameters`] than the capsule,
+/// so that the caller can match the outputs to the inputs by index.
+pub fn reencrypt_many(
+    capsule: &Capsule,
+    kfrags: &[KeyFrag],
+    metadata: Option<&[u8]>,
+    signing_pk: &PublicKey,
+    delegating_pk: Option<&PublicKey>,
+    receiving_pk: Option<&PublicKey>,
+) -> Vec<Option<CapsuleFrag>> {
+    kfrags
+        .iter()
+        .map(|kfrag| {
+            if kfrag.verify(signing_pk, delegating_pk, receiving_pk) {
+                reencrypt_checked(capsule, kfrag, metadata).ok()
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Same as [`reencrypt()`], but instead of binding `metadata` to the capsule fragment directly,
+/// binds a hiding commitment to it, so that the receiver of the fragment does not learn it.
+///
+/// Returns the capsule fragment and the opening of the commitment,
+/// which can later be given to an auditor to reveal the metadata
+/// (see [`CapsuleFrag::open_metadata()`] and [`MetadataOpening`] for details).
+pub fn reencrypt_with_metadata_commitment(
+    capsule: &Capsule,
+    kfrag: &KeyFrag,
+    metadata: &[u8],
+) -> (CapsuleFrag, MetadataOpening) {
+    CapsuleFrag::reencrypted_with_metadata_commitment(capsule, kfrag, metadata)
+}
+
+/// Same as [`reencrypt()`], but uses the given RNG to generate the randomness
+/// of the correctness proof instead of the OS one.
+///
+/// With a deterministic RNG the resulting capsule fragment is deterministic as well,
+/// which is useful for testing, or on platforms without an OS RNG.
+pub fn reencrypt_with_rng(
+    rng: &mut (impl CryptoRng + RngCore),
+    capsule: &Capsule,
+    kfrag: &KeyFrag,
+    metadata: Option<&[u8]>,
+) -> CapsuleFrag {
+    CapsuleFrag::reencrypted_with_rng(rng, capsule, kfrag, metadata)
+}
+
+/// A hook for proxy operators, called by [`reencrypt_observed()`] on every reencryption
+/// (e.g. to keep per-policy statistics or enforce quotas).
+pub trait ReencryptionObserver {
+    /// Called before a capsule fragment is produced.
+    ///
+    /// `kfrag_id` is the ID of the key fragment used,
+    /// which is shared by all the capsule fragments it produces.
+    fn on_reencrypt(&self, capsule: &Capsule, kfrag_id: &CurveScalar);
+}
+
+/// Same as [`reencrypt()`], but notifies `observer` before the reencryption.
+pub fn reencrypt_observed(
+    capsule: &Capsule,
+    kfrag: &KeyFrag,
+    metadata: Option<&[u8]>,
+    observer: &dyn ReencryptionObserver,
+) -> CapsuleFrag {
+    observer.on_reencrypt(capsule, &kfrag.id);
+    reencrypt(capsule, kfrag, metadata)
+}
+
+/// Reencrypts several [`Capsule`] objects with the same key fragment,
+/// distributing the work between threads.
+///
+/// The results are returned in the same order as `capsules`.
+/// Each reencryption is independent and draws its own proof randomness from the OS RNG,
+/// so the result is equivalent to calling [`reencrypt()`] for every capsule.
+#[cfg(feature = "parallel")]
+pub fn reencrypt_batch_parallel(
+    capsules: &[Capsule],
+    kfrag: &KeyFrag,
+    metadata: Option<&[u8]>,
+) -> Vec<CapsuleFrag> {
+    capsules
+        .par_iter()
+        .map(|capsule| reencrypt(capsule, kfrag, metadata))
+        .collect()
+}
+
 /// Decrypts the ciphertext using previously reencrypted capsule fragments.
 ///
 /// `decrypting_sk` is the secret key whose associated public key was used in
@@ -56,31 +799,365 @@ pub fn reencrypt(capsule: &Capsule, kfrag: &KeyFrag, metadata: Option<&[u8]>) ->
 /// `delegating_pk` is the public key of the encrypting party.
 /// Used to check the validity of decryption.
 ///
+/// `cfrags` can be a slice of capsule fragments,
+/// or a [`CfragSet`](`crate::CfragSet`) if they were validated on collection.
+///
 /// One can call [`CapsuleFrag::verify()`] before reencryption to check its integrity.
+#[must_use = "a failed decryption is only reported in the return value"]
 pub fn decrypt_reencrypted(
     decrypting_sk: &SecretKey,
     delegating_pk: &PublicKey,
     capsule: &Capsule,
-    cfrags: &[CapsuleFrag],
+    cfrags: impl AsRef<[CapsuleFrag]>,
     ciphertext: impl AsRef<[u8]>,
 ) -> Option<Box<[u8]>> {
-    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags)?;
+    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags.as_ref())?;
     let dem = UmbralDEM::new(&key_seed.to_array());
     dem.decrypt(&ciphertext, &capsule.to_array())
 }
 
-#[cfg(test)]
-mod tests {
+/// Same as [`decrypt_reencrypted()`], for a ciphertext produced by [`encrypt_with_backend()`].
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_with_backend(
+    backend: &impl SymmetricBackend,
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+) -> Option<Box<[u8]>> {
+    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags.as_ref())?;
+    let dem_key = backend.derive_dem_key(&key_seed.to_array());
+    backend.decrypt(&dem_key, ciphertext.as_ref(), &capsule.to_array())
+}
 
-    use super::{decrypt_original, decrypt_reencrypted, encrypt, reencrypt};
+/// Same as [`decrypt_reencrypted()`], but reports the reason of a failure.
+///
+/// The ciphertext length is checked before the (relatively expensive)
+/// reconstruction of the symmetric key from the capsule fragments.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_checked(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<Box<[u8]>, DecryptionError> {
+    check_ciphertext_size(ciphertext.as_ref())?;
+    decrypt_reencrypted(decrypting_sk, delegating_pk, capsule, cfrags, ciphertext)
+        .ok_or(DecryptionError::DecryptionFailed)
+}
 
-    use crate::key_frag::generate_kfrags;
+/// Same as [`decrypt_reencrypted_checked()`], but if `expected_capsule_digest` is given,
+/// first compares it with [`Capsule::digest()`], failing with
+/// [`DecryptionError::CapsuleMismatch`] before the (relatively expensive)
+/// reconstruction of the symmetric key.
+///
+/// This lets one detect a capsule mixed up with another one
+/// instead of getting an opaque decryption failure.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_with_capsule_check(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+    expected_capsule_digest: Option<&[u8]>,
+) -> Result<Box<[u8]>, DecryptionError> {
+    if let Some(expected) = expected_capsule_digest {
+        if capsule.digest().as_slice() != expected {
+            return Err(DecryptionError::CapsuleMismatch);
+        }
+    }
+    decrypt_reencrypted_checked(decrypting_sk, delegating_pk, capsule, cfrags, ciphertext)
+}
 
-    use crate::capsule_frag::CapsuleFrag;
+/// Same as [`decrypt_reencrypted_checked()`], but requires exactly `threshold`
+/// capsule fragments (the value given to [`generate_kfrags()`](`crate::generate_kfrags()`)),
+/// failing with [`DecryptionError::TooFewFragments`] or [`DecryptionError::TooManyFragments`]
+/// otherwise.
+///
+/// Any `threshold` or more distinct fragments of the same set reconstruct the key,
+/// so extra fragments are not an error for [`decrypt_reencrypted()`];
+/// this variant is for callers that want a mistake in collecting the fragments reported.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_exact(
+    threshold: usize,
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+) -> Result<Box<[u8]>, DecryptionError> {
+    let cfrags = cfrags.as_ref();
+    if cfrags.len() < threshold {
+        return Err(DecryptionError::TooFewFragments);
+    }
+    if cfrags.len() > threshold {
+        return Err(DecryptionError::TooManyFragments);
+    }
+    decrypt_reencrypted_checked(decrypting_sk, delegating_pk, capsule, cfrags, ciphertext)
+}
 
-    use alloc::vec::Vec;
+/// Same as [`decrypt_reencrypted_checked()`], but takes the capsule fragments serialized,
+/// as they come from the network or a foreign function interface.
+///
+/// If `verifying_pk` is given, every fragment is also verified with it
+/// (see [`CapsuleFrag::verify()`]), taking the public key of `decrypting_sk`
+/// as the receiving key.
+/// The index of the first fragment that fails to deserialize or verify is reported in
+/// [`DecryptionError::InvalidCapsuleFrag`].
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_from_bytes(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrag_bytes: &[&[u8]],
+    ciphertext: impl AsRef<[u8]>,
+    verifying_pk: Option<&PublicKey>,
+) -> Result<Box<[u8]>, DecryptionError> {
+    let receiving_pk = decrypting_sk.public_key();
+    let cfrags = cfrag_bytes
+        .iter()
+        .enumerate()
+        .map(|(index, bytes)| {
+            let cfrag =
+                CapsuleFrag::from_bytes(bytes).ok_or(DecryptionError::InvalidCapsuleFrag(index))?;
+            match verifying_pk {
+                Some(verifying_pk)
+                    if !cfrag.verify(capsule, delegating_pk, receiving_pk, verifying_pk) =>
+                {
+                    Err(DecryptionError::InvalidCapsuleFrag(index))
+                }
+                _ => Ok(cfrag),
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    decrypt_reencrypted_checked(decrypting_sk, delegating_pk, capsule, cfrags, ciphertext)
+}
 
-    use crate::{Parameters, PublicKey, SecretKey};
+/// Same as [`decrypt_reencrypted()`], but uses capsule fragments
+/// with the key fragment signatures stripped.
+///
+/// See [`SlimCapsuleFrag`] for the reduced guarantees;
+/// one can call [`SlimCapsuleFrag::verify()`] to check the fragments' integrity.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_slim(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: &[SlimCapsuleFrag],
+    ciphertext: impl AsRef<[u8]>,
+) -> Option<Box<[u8]>> {
+    let key_seed = capsule.open_reencrypted_slim(decrypting_sk, delegating_pk, cfrags)?;
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt(&ciphertext, &capsule.to_array())
+}
+
+/// Decrypts the ciphertext produced by [`encrypt_with_aad()`]
+/// using previously reencrypted capsule fragments
+/// (see [`decrypt_reencrypted()`] for details).
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_with_aad(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+    aad_chunks: &[&[u8]],
+) -> Option<Box<[u8]>> {
+    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags.as_ref())?;
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+    dem.decrypt_with_aad_chunks(ciphertext, &with_capsule(&capsule_bytes, aad_chunks))
+}
+
+/// Advances `indices` (a strictly increasing sequence of indices less than `n`)
+/// to the next combination in lexicographic order.
+/// Returns `false` if `indices` was the last combination.
+fn next_combination(indices: &mut [usize], n: usize) -> bool {
+    let k = indices.len();
+    for i in (0..k).rev() {
+        if indices[i] < n - k + i {
+            indices[i] += 1;
+            for j in i + 1..k {
+                indices[j] = indices[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+/// A variant of [`decrypt_reencrypted()`] for the case when more than `threshold`
+/// capsule fragments are available, and some of them may be faulty
+/// in a way not detected by [`CapsuleFrag::verify()`].
+///
+/// Tries subsets of `threshold` capsule fragments in lexicographic order,
+/// and returns the plaintext decrypted with the first subset that succeeds.
+/// At most `max_attempts` subsets are tried; the total number of subsets is
+/// the binomial coefficient `C(cfrags.len(), threshold)`, so with `k` faulty fragments
+/// one may need to try up to `C(cfrags.len(), threshold) - C(cfrags.len() - k, threshold) + 1`
+/// of them.
+///
+/// Returns `None` if no subset succeeded within `max_attempts`,
+/// or if `threshold` is zero or greater than the number of fragments.
+#[allow(clippy::too_many_arguments)]
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_best_effort(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    ciphertext: impl AsRef<[u8]>,
+    threshold: usize,
+    max_attempts: usize,
+) -> Option<Box<[u8]>> {
+    let cfrags = cfrags.as_ref();
+    if threshold == 0 || threshold > cfrags.len() {
+        return None;
+    }
+
+    let mut indices: Vec<usize> = (0..threshold).collect();
+    let mut subset = Vec::<CapsuleFrag>::with_capacity(threshold);
+    for _ in 0..max_attempts {
+        subset.clear();
+        subset.extend(indices.iter().map(|i| cfrags[*i].clone()));
+
+        let plaintext =
+            decrypt_reencrypted(decrypting_sk, delegating_pk, capsule, &subset, &ciphertext);
+        if plaintext.is_some() {
+            return plaintext;
+        }
+
+        if !next_combination(&mut indices, cfrags.len()) {
+            break;
+        }
+    }
+
+    None
+}
+
+/// A variant of [`decrypt_reencrypted()`] operating on a caller-provided buffer
+/// instead of allocating for the plaintext.
+///
+/// `buf[..len]` is treated as the ciphertext.
+/// On success, the plaintext is placed at the beginning of `buf`, and its length is returned.
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_in_place(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    buf: &mut [u8],
+    len: usize,
+) -> Option<usize> {
+    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags.as_ref())?;
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt_in_place(buf, len, &capsule.to_array())
+}
+
+/// A variant of [`decrypt_reencrypted_in_place()`] for ciphertexts
+/// produced by [`encrypt_in_place_external_nonce()`].
+#[must_use = "a failed decryption is only reported in the return value"]
+pub fn decrypt_reencrypted_in_place_external_nonce(
+    decrypting_sk: &SecretKey,
+    delegating_pk: &PublicKey,
+    capsule: &Capsule,
+    cfrags: impl AsRef<[CapsuleFrag]>,
+    buf: &mut [u8],
+    len: usize,
+    nonce: &ExternalNonce,
+) -> Option<usize> {
+    let key_seed = capsule.open_reencrypted(decrypting_sk, delegating_pk, cfrags.as_ref())?;
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    dem.decrypt_in_place_external_nonce(buf, len, &capsule.to_array(), nonce)
+}
+
+#[cfg(test)]
+mod tests {
+
+    #[cfg(target_pointer_width = "64")]
+    use super::{check_plaintext_size, MAX_PLAINTEXT_SIZE};
+    use super::{
+        ciphertext_size, decrypt_original, decrypt_original_checked, decrypt_original_detached,
+        decrypt_original_in_place, decrypt_original_in_place_external_nonce,
+        decrypt_original_with_aad, decrypt_original_with_backend, decrypt_reencrypted,
+        decrypt_reencrypted_best_effort, decrypt_reencrypted_checked, decrypt_reencrypted_detached,
+        decrypt_reencrypted_exact, decrypt_reencrypted_from_bytes, decrypt_reencrypted_in_place,
+        decrypt_reencrypted_in_place_external_nonce, decrypt_reencrypted_with_aad,
+        decrypt_reencrypted_with_backend, decrypt_reencrypted_with_capsule_check, decrypt_with_key,
+        distribute_kfrags, encrypt, encrypt_detached, encrypt_in_place,
+        encrypt_in_place_external_nonce, encrypt_many, encrypt_signed, encrypt_with_aad,
+        encrypt_with_backend, encrypt_with_key_export, encrypt_with_pairing_tag, next_combination,
+        open, reencrypt, reencrypt_capsules, reencrypt_checked, reencrypt_many, reencrypt_observed,
+        reencrypt_with_rng, seal, sealed_size, verify_pairing, verify_sender, DecryptionError,
+        EncryptionError, ReencryptionError, ReencryptionObserver,
+    };
+
+    use crate::key_frag::{generate_kfrags, KeyFrag};
+
+    use crate::capsule::Capsule;
+    use crate::capsule_frag::{CapsuleFrag, CfragSet};
+    use crate::curve::{CurvePoint, CurveScalar};
+    use crate::dem::{ChaCha20Poly1305Backend, DemKey, ExternalNonce, SymmetricBackend};
+    use crate::traits::SerializableToArray;
+
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+
+    use rand_core::{CryptoRng, RngCore};
+
+    use crate::{Parameters, PublicKey, SecretKey};
+
+    /// The keys of a delegation and its key fragments.
+    struct Delegation {
+        params: Parameters,
+        delegating_sk: SecretKey,
+        delegating_pk: PublicKey,
+        signing_sk: SecretKey,
+        signing_pk: PublicKey,
+        receiving_sk: SecretKey,
+        receiving_pk: PublicKey,
+        kfrags: Box<[KeyFrag]>,
+    }
+
+    fn prepare_kfrags(threshold: usize, num_kfrags: usize) -> Delegation {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let signing_sk = SecretKey::random();
+        let signing_pk = PublicKey::from_secret_key(&signing_sk);
+
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            threshold,
+            num_kfrags,
+            true,
+            true,
+        );
+
+        Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            kfrags,
+        }
+    }
 
     #[test]
     fn test_simple_api() {
@@ -162,5 +1239,1285 @@ mod tests {
         )
         .unwrap();
         assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // Bob can also validate the cfrags structurally on collection
+        let cfrag_set = CfragSet::try_from_iter(cfrags).unwrap();
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrag_set,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+    }
+
+    #[test]
+    fn test_decryption_failures() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        // Wrong recipient
+        let other_sk = SecretKey::random();
+        assert!(decrypt_original(&other_sk, &capsule, &ciphertext).is_none());
+
+        // Modified ciphertext (both in the nonce and in the encrypted data)
+        for pos in [0, ciphertext.len() / 2, ciphertext.len() - 1].iter() {
+            let mut modified = ciphertext.to_vec();
+            modified[*pos] ^= 1;
+            assert!(decrypt_original(&delegating_sk, &capsule, &modified).is_none());
+        }
+
+        // Modified capsule
+        let mut capsule_bytes = capsule.to_array();
+        let last = capsule_bytes.len() - 1;
+        capsule_bytes[last] ^= 1;
+        assert!(Capsule::from_bytes(capsule_bytes).is_none());
+
+        // Capsule from another encryption
+        let (other_capsule, _ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        assert!(decrypt_original(&delegating_sk, &other_capsule, &ciphertext).is_none());
+
+        assert_eq!(
+            decrypt_original(&delegating_sk, &capsule, &ciphertext).as_deref(),
+            Some(&plaintext[..])
+        );
+    }
+
+    #[test]
+    fn test_key_export() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext, dem_key) =
+            encrypt_with_key_export(&params, &delegating_pk, plaintext).unwrap();
+
+        // The escrowed key is stored and restored
+        let dem_key_back = DemKey::from_bytes(dem_key.to_array()).unwrap();
+
+        let plaintext_escrow = decrypt_with_key(&dem_key_back, &capsule, &ciphertext).unwrap();
+        assert_eq!(&plaintext_escrow as &[u8], plaintext);
+
+        // The regular decryption still works
+        let plaintext_alice = decrypt_original(&delegating_sk, &capsule, &ciphertext).unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+
+        // The key is bound to the capsule and the ciphertext
+        let (capsule2, ciphertext2, dem_key2) =
+            encrypt_with_key_export(&params, &delegating_pk, plaintext).unwrap();
+        assert!(decrypt_with_key(&dem_key2, &capsule, &ciphertext).is_none());
+        assert!(decrypt_with_key(&dem_key, &capsule2, &ciphertext2).is_none());
+        assert!(decrypt_with_key(&dem_key, &capsule2, &ciphertext).is_none());
+    }
+
+    #[test]
+    fn test_encrypt_signed() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let sender_sk = SecretKey::random();
+        let sender_pk = PublicKey::from_secret_key(&sender_sk);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext, signature) =
+            encrypt_signed(&params, &delegating_pk, &sender_sk, plaintext).unwrap();
+
+        assert!(verify_sender(&capsule, &sender_pk, &signature));
+
+        let other_pk = PublicKey::from_secret_key(&SecretKey::random());
+        assert!(!verify_sender(&capsule, &other_pk, &signature));
+
+        let (other_capsule, _ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        assert!(!verify_sender(&other_capsule, &sender_pk, &signature));
+
+        // A plain signature on the capsule bytes is not accepted
+        let plain_signature = sender_sk.sign_recoverable(&capsule.to_array());
+        assert!(!verify_sender(&capsule, &sender_pk, &plain_signature));
+
+        let plaintext_alice = decrypt_original(&delegating_sk, &capsule, &ciphertext).unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+    }
+
+    #[test]
+    fn test_in_place_api() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let mut buf = [0u8; 64];
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+
+        let (capsule, ciphertext_len) =
+            encrypt_in_place(&params, &delegating_pk, &mut buf, plaintext.len()).unwrap();
+        assert_eq!(ciphertext_len, plaintext.len() + 28);
+        let ciphertext = buf;
+
+        // The result is compatible with the allocating API
+        let plaintext_alice =
+            decrypt_original(&delegating_sk, &capsule, &ciphertext[..ciphertext_len]).unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+
+        let plaintext_len =
+            decrypt_original_in_place(&delegating_sk, &capsule, &mut buf, ciphertext_len).unwrap();
+        assert_eq!(&buf[..plaintext_len], plaintext);
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let mut buf = ciphertext;
+        let plaintext_len = decrypt_reencrypted_in_place(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &mut buf,
+            ciphertext_len,
+        )
+        .unwrap();
+        assert_eq!(&buf[..plaintext_len], plaintext);
+
+        // Not enough room for the nonce and the tag
+        let mut small_buf = [0u8; 20];
+        assert_eq!(
+            encrypt_in_place(&params, &delegating_pk, &mut small_buf, 10),
+            Err(EncryptionError::BufferTooSmall)
+        );
+        assert_eq!(
+            encrypt_in_place(&params, &delegating_pk, &mut small_buf, 30),
+            Err(EncryptionError::BufferTooSmall)
+        );
+        let mut exact_buf = [0u8; 38];
+        let (_capsule, len) =
+            encrypt_in_place(&params, &delegating_pk, &mut exact_buf, 10).unwrap();
+        assert_eq!(len, exact_buf.len());
+
+        // Ciphertext is shorter than the nonce and the tag
+        assert!(decrypt_original_in_place(&delegating_sk, &capsule, &mut buf, 20).is_none());
+    }
+
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn test_plaintext_size_limit() {
+        let params = Parameters::new();
+        let pk = PublicKey::from_secret_key(&SecretKey::random());
+
+        assert!(check_plaintext_size(MAX_PLAINTEXT_SIZE as usize).is_ok());
+        assert_eq!(
+            check_plaintext_size(MAX_PLAINTEXT_SIZE as usize + 1),
+            Err(EncryptionError::PlaintextTooLarge)
+        );
+
+        // The size is checked before the buffer
+        let mut buf = [0u8; 64];
+        assert_eq!(
+            encrypt_in_place(&params, &pk, &mut buf, MAX_PLAINTEXT_SIZE as usize + 1),
+            Err(EncryptionError::PlaintextTooLarge)
+        );
+
+        // `encrypt_detached()` takes the size from the slice, and a slice over the limit
+        // cannot be allocated here, so only the success at a small size is checked;
+        // the limit itself is enforced by `check_plaintext_size()`, tested above.
+        let mut data = [0u8; 64];
+        assert!(encrypt_detached(&params, &pk, &mut data).is_ok());
+    }
+
+    #[test]
+    fn test_next_combination() {
+        let mut indices = [0, 1];
+        let mut combinations = Vec::new();
+        loop {
+            combinations.push(indices);
+            if !next_combination(&mut indices, 4) {
+                break;
+            }
+        }
+        assert_eq!(
+            combinations,
+            [[0, 1], [0, 2], [0, 3], [1, 2], [1, 3], [2, 3]]
+        );
+    }
+
+    #[test]
+    fn test_best_effort_decryption() {
+        let threshold = 3;
+
+        let Delegation {
+            params,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(threshold, 4);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        let mut cfrags: Vec<CapsuleFrag> = kfrags
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        // Corrupt the first cfrag in a way that is structurally consistent with the others
+        cfrags[0].point_e1 = &cfrags[0].point_e1 + &CurvePoint::generator();
+
+        assert!(decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags[..threshold],
+            &ciphertext
+        )
+        .is_none());
+
+        // The only good subset is the last one, [1, 2, 3]
+        let plaintext_bob = decrypt_reencrypted_best_effort(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+            threshold,
+            4,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // Not enough attempts
+        assert!(decrypt_reencrypted_best_effort(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+            threshold,
+            3,
+        )
+        .is_none());
+
+        // Threshold larger than the number of cfrags
+        assert!(decrypt_reencrypted_best_effort(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+            5,
+            100,
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn test_detached_api() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let mut data = *plaintext;
+        let (capsule, tag) = encrypt_detached(&params, &delegating_pk, &mut data).unwrap();
+        let ciphertext = data;
+
+        decrypt_original_detached(&delegating_sk, &capsule, &mut data, &tag).unwrap();
+        assert_eq!(&data, plaintext);
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let mut data = ciphertext;
+        decrypt_reencrypted_detached(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &mut data,
+            &tag,
+        )
+        .unwrap();
+        assert_eq!(&data, plaintext);
+
+        // Wrong key
+        let mut data = ciphertext;
+        assert!(decrypt_original_detached(&receiving_sk, &capsule, &mut data, &tag).is_none());
+        assert_eq!(data, ciphertext);
+    }
+
+    /// A toy (insecure) backend: XORs the plaintext with the key,
+    /// and appends the first byte of the key and the authenticated data as a "tag".
+    struct XorBackend;
+
+    impl SymmetricBackend for XorBackend {
+        fn encrypt(&self, key: &DemKey, plaintext: &[u8], aad: &[u8]) -> Option<Box<[u8]>> {
+            let key = key.to_array();
+            let mut ciphertext: Vec<u8> = plaintext
+                .iter()
+                .zip(key.iter().cycle())
+                .map(|(x, k)| x ^ k)
+                .collect();
+            ciphertext.push(key[0] ^ aad[0]);
+            Some(ciphertext.into_boxed_slice())
+        }
+
+        fn decrypt(&self, key: &DemKey, ciphertext: &[u8], aad: &[u8]) -> Option<Box<[u8]>> {
+            let key = key.to_array();
+            let (tag, ciphertext) = ciphertext.split_last()?;
+            if *tag != key[0] ^ aad[0] {
+                return None;
+            }
+            let plaintext: Vec<u8> = ciphertext
+                .iter()
+                .zip(key.iter().cycle())
+                .map(|(x, k)| x ^ k)
+                .collect();
+            Some(plaintext.into_boxed_slice())
+        }
+    }
+
+    #[test]
+    fn test_symmetric_backend() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) =
+            encrypt_with_backend(&XorBackend, &params, &delegating_pk, plaintext).unwrap();
+        assert_eq!(ciphertext.len(), plaintext.len() + 1);
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let plaintext_alice =
+            decrypt_original_with_backend(&XorBackend, &delegating_sk, &capsule, &ciphertext)
+                .unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+        let plaintext_bob = decrypt_reencrypted_with_backend(
+            &XorBackend,
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+
+        // Backends are not interchangeable
+        assert!(decrypt_original(&delegating_sk, &capsule, &ciphertext).is_none());
+
+        // The built-in backend is compatible with the functions without a backend parameter
+        let (capsule, ciphertext) =
+            encrypt_with_backend(&ChaCha20Poly1305Backend, &params, &delegating_pk, plaintext)
+                .unwrap();
+        let plaintext_alice = decrypt_original(&delegating_sk, &capsule, &ciphertext).unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let plaintext_alice = decrypt_original_with_backend(
+            &ChaCha20Poly1305Backend,
+            &delegating_sk,
+            &capsule,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+    }
+
+    #[test]
+    fn test_decryption_errors() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        assert_eq!(
+            decrypt_original_checked(&delegating_sk, &capsule, &ciphertext)
+                .unwrap()
+                .as_ref(),
+            plaintext
+        );
+        assert_eq!(
+            decrypt_reencrypted_checked(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &cfrags,
+                &ciphertext
+            )
+            .unwrap()
+            .as_ref(),
+            plaintext
+        );
+
+        // Shorter than the nonce
+        let short = &ciphertext[..5];
+        assert_eq!(
+            decrypt_original_checked(&delegating_sk, &capsule, short),
+            Err(DecryptionError::CiphertextTooShort)
+        );
+        assert_eq!(
+            decrypt_reencrypted_checked(&receiving_sk, &delegating_pk, &capsule, &cfrags, short),
+            Err(DecryptionError::CiphertextTooShort)
+        );
+
+        // Long enough, but not authentic
+        let truncated = &ciphertext[..ciphertext.len() - 1];
+        assert_eq!(
+            decrypt_original_checked(&delegating_sk, &capsule, truncated),
+            Err(DecryptionError::DecryptionFailed)
+        );
+        assert_eq!(
+            decrypt_reencrypted_checked(&receiving_sk, &delegating_pk, &capsule, &[], &ciphertext),
+            Err(DecryptionError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn test_decrypt_from_bytes() {
+        let Delegation {
+            params,
+            delegating_pk,
+            signing_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        let cfrag_arrays: Vec<_> = kfrags
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None).to_array())
+            .collect();
+        let cfrag_bytes: Vec<&[u8]> = cfrag_arrays.iter().map(|arr| arr.as_slice()).collect();
+
+        for verifying_pk in [None, Some(&signing_pk)].iter() {
+            let plaintext_bob = decrypt_reencrypted_from_bytes(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &cfrag_bytes[..2],
+                &ciphertext,
+                *verifying_pk,
+            )
+            .unwrap();
+            assert_eq!(&plaintext_bob as &[u8], plaintext);
+        }
+
+        // A fragment that cannot be deserialized
+        let mut bad_cfrag = cfrag_arrays[1].to_vec();
+        bad_cfrag[0] = 0x05;
+        let mixed = [cfrag_bytes[0], &bad_cfrag, cfrag_bytes[2]];
+        assert_eq!(
+            decrypt_reencrypted_from_bytes(
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                &mixed,
+                &ciphertext,
+                None,
+            ),
+            Err(DecryptionError::InvalidCapsuleFrag(1))
+        );
+
+        // A fragment that deserializes, but does not verify
+        #[cfg(not(umbral_insecure_no_proofs))]
+        {
+            let mut bad_cfrag = cfrag_arrays[2].to_vec();
+            let last = bad_cfrag.len() - 1;
+            bad_cfrag[last] ^= 1;
+            let mixed = [cfrag_bytes[0], cfrag_bytes[1], &bad_cfrag];
+            assert_eq!(
+                decrypt_reencrypted_from_bytes(
+                    &receiving_sk,
+                    &delegating_pk,
+                    &capsule,
+                    &mixed,
+                    &ciphertext,
+                    Some(&signing_pk),
+                ),
+                Err(DecryptionError::InvalidCapsuleFrag(2))
+            );
+        }
+    }
+
+    #[test]
+    fn test_pairing_tag() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        let (capsule, ciphertext, tag) =
+            encrypt_with_pairing_tag(&params, &pk, b"peace at dawn").unwrap();
+        let (other_capsule, other_ciphertext, other_tag) =
+            encrypt_with_pairing_tag(&params, &pk, b"peace at dusk").unwrap();
+
+        assert!(verify_pairing(&capsule, &ciphertext, &tag));
+        assert!(verify_pairing(
+            &other_capsule,
+            &other_ciphertext,
+            &other_tag
+        ));
+
+        // Swapped ciphertexts or capsules are detected without decryption
+        assert!(!verify_pairing(&capsule, &other_ciphertext, &tag));
+        assert!(!verify_pairing(&other_capsule, &ciphertext, &tag));
+        assert!(!verify_pairing(&capsule, &ciphertext, &other_tag));
+
+        // A modified ciphertext or a truncated tag
+        let mut tampered = ciphertext.to_vec();
+        tampered[0] ^= 1;
+        assert!(!verify_pairing(&capsule, &tampered, &tag));
+        assert!(!verify_pairing(&capsule, &ciphertext, &tag[..31]));
+
+        assert_eq!(
+            decrypt_original(&sk, &capsule, &ciphertext)
+                .unwrap()
+                .as_ref(),
+            b"peace at dawn"
+        );
+    }
+
+    #[test]
+    fn test_capsule_check() {
+        let Delegation {
+            params,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let (other_capsule, _other_ciphertext) =
+            encrypt(&params, &delegating_pk, plaintext).unwrap();
+        let digest = capsule.digest();
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let decrypt = |capsule: &Capsule, expected_digest: Option<&[u8]>| {
+            decrypt_reencrypted_with_capsule_check(
+                &receiving_sk,
+                &delegating_pk,
+                capsule,
+                &cfrags,
+                &ciphertext,
+                expected_digest,
+            )
+        };
+
+        assert_eq!(
+            decrypt(&capsule, Some(&digest)).unwrap().as_ref(),
+            plaintext
+        );
+        assert_eq!(decrypt(&capsule, None).unwrap().as_ref(), plaintext);
+
+        // A mixed-up capsule is caught before opening
+        assert_eq!(
+            decrypt(&other_capsule, Some(&digest)),
+            Err(DecryptionError::CapsuleMismatch)
+        );
+        // Without the digest, only the decryption itself fails
+        assert_eq!(
+            decrypt(&other_capsule, None),
+            Err(DecryptionError::DecryptionFailed)
+        );
+    }
+
+    #[test]
+    fn test_distribute_kfrags() {
+        let Delegation {
+            params,
+            delegating_pk,
+            signing_pk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let proxy_sks: Vec<SecretKey> = (0..kfrags.len()).map(|_| SecretKey::random()).collect();
+        let proxy_pks: Vec<PublicKey> = proxy_sks.iter().map(PublicKey::from_secret_key).collect();
+
+        let encrypted = distribute_kfrags(&params, &kfrags, &proxy_pks).unwrap();
+        assert_eq!(encrypted.len(), kfrags.len());
+
+        for (i, (proxy_sk, (capsule, ciphertext))) in
+            proxy_sks.iter().zip(encrypted.iter()).enumerate()
+        {
+            let kfrag_bytes = decrypt_original(proxy_sk, capsule, ciphertext).unwrap();
+            let kfrag = KeyFrag::from_bytes(&kfrag_bytes).unwrap();
+            assert_eq!(kfrag, kfrags[i]);
+            assert!(kfrag.verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
+
+            // Other proxies cannot decrypt it
+            let other_sk = &proxy_sks[(i + 1) % proxy_sks.len()];
+            assert!(decrypt_original(other_sk, capsule, ciphertext).is_none());
+        }
+
+        // The numbers of kfrags and proxies must match
+        assert!(distribute_kfrags(&params, &kfrags, &proxy_pks[..2]).is_none());
+    }
+
+    #[test]
+    fn test_encrypt_many() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintexts: [&[u8]; 3] = [b"peace at dawn", b"", b"peace at dawn"];
+        let (capsule, ciphertexts) = encrypt_many(&params, &delegating_pk, &plaintexts).unwrap();
+        assert_eq!(ciphertexts.len(), plaintexts.len());
+
+        // Same plaintexts produce different ciphertexts because of distinct nonces
+        assert!(ciphertexts[0] != ciphertexts[2]);
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        for (plaintext, ciphertext) in plaintexts.iter().zip(ciphertexts.iter()) {
+            let plaintext_alice = decrypt_original(&delegating_sk, &capsule, ciphertext).unwrap();
+            assert_eq!(&plaintext_alice as &[u8], *plaintext);
+
+            let plaintext_bob =
+                decrypt_reencrypted(&receiving_sk, &delegating_pk, &capsule, &cfrags, ciphertext)
+                    .unwrap();
+            assert_eq!(&plaintext_bob as &[u8], *plaintext);
+        }
+    }
+
+    #[test]
+    fn test_seal_and_open() {
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        let plaintext = b"peace at dawn";
+        let sealed = seal(&pk, plaintext).unwrap();
+        let opened = open(&sk, &sealed).unwrap();
+        assert_eq!(&opened as &[u8], plaintext);
+
+        // Wrong key
+        let other_sk = SecretKey::random();
+        assert!(open(&other_sk, &sealed).is_none());
+
+        // Tampered capsule, ciphertext, or version
+        for &idx in [0, 1, 100, sealed.len() - 1].iter() {
+            let mut tampered = sealed.to_vec();
+            tampered[idx] ^= 1;
+            assert!(open(&sk, &tampered).is_none());
+        }
+
+        // Truncated blob
+        assert!(open(&sk, &sealed[..sealed.len() - 1]).is_none());
+        assert!(open(&sk, &sealed[..50]).is_none());
+        assert!(open(&sk, b"").is_none());
+    }
+
+    #[test]
+    fn test_empty_plaintext() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        // Allocating API
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, b"").unwrap();
+        assert_eq!(ciphertext.len(), ciphertext_size(0));
+        let plaintext = decrypt_original(&delegating_sk, &capsule, &ciphertext).unwrap();
+        assert!(plaintext.is_empty());
+
+        // In-place API
+        let mut buf = [0u8; 28];
+        let (capsule_in_place, len) =
+            encrypt_in_place(&params, &delegating_pk, &mut buf, 0).unwrap();
+        assert_eq!(len, buf.len());
+        assert_eq!(
+            decrypt_original_in_place(&delegating_sk, &capsule_in_place, &mut buf, len),
+            Some(0)
+        );
+
+        // Reencrypted flow
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+        let plaintext_bob = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert!(plaintext_bob.is_empty());
+
+        // The tag is still checked
+        let mut tampered = ciphertext.to_vec();
+        let last = tampered.len() - 1;
+        tampered[last] ^= 1;
+        assert!(decrypt_original(&delegating_sk, &capsule, &tampered).is_none());
+    }
+
+    #[test]
+    fn test_reencrypt_checked() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_sk,
+            signing_pk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let other_params = Parameters::from_array(&(&params.u() + &params.g()).to_array()).unwrap();
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        assert_eq!(capsule.params(), params);
+
+        assert_eq!(kfrags[0].params(), params);
+        let cfrag = reencrypt_checked(&capsule, &kfrags[0], None).unwrap();
+        assert!(cfrag.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+
+        let other_kfrags = generate_kfrags(
+            &other_params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+        assert_eq!(
+            reencrypt_checked(&capsule, &other_kfrags[0], None),
+            Err(ReencryptionError::ParameterMismatch)
+        );
+    }
+
+    #[test]
+    fn test_reencrypt_capsules() {
+        let Delegation {
+            params,
+            delegating_pk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let other_params = Parameters::from_array(&(&params.u() + &params.g()).to_array()).unwrap();
+
+        let plaintexts: [&[u8]; 3] = [b"peace at dawn", b"retreat at dusk", b""];
+        let (capsules, ciphertexts): (Vec<_>, Vec<_>) = plaintexts
+            .iter()
+            .map(|plaintext| encrypt(&params, &delegating_pk, plaintext).unwrap())
+            .unzip();
+
+        let batches: Vec<Vec<Option<CapsuleFrag>>> = kfrags[..2]
+            .iter()
+            .map(|kfrag| {
+                reencrypt_capsules(
+                    kfrag,
+                    &capsules,
+                    None,
+                    Some((&signing_pk, Some(&delegating_pk), Some(&receiving_pk))),
+                )
+                .unwrap()
+            })
+            .collect();
+
+        for (i, plaintext) in plaintexts.iter().enumerate() {
+            let cfrags: Vec<CapsuleFrag> = batches
+                .iter()
+                .map(|batch| batch[i].clone().unwrap())
+                .collect();
+            assert!(cfrags.iter().all(|cfrag| cfrag.verify(
+                &capsules[i],
+                &delegating_pk,
+                &receiving_pk,
+                &signing_pk
+            )));
+            let decrypted = decrypt_reencrypted(
+                &receiving_sk,
+                &delegating_pk,
+                &capsules[i],
+                &cfrags,
+                &ciphertexts[i],
+            )
+            .unwrap();
+            assert_eq!(&decrypted as &[u8], *plaintext);
+        }
+
+        // Verification is skipped if no signing key is given
+        let cfrags = reencrypt_capsules(&kfrags[0], &capsules, None, None).unwrap();
+        assert!(cfrags.iter().all(|cfrag| cfrag.is_some()));
+
+        // A capsule with different parameters is skipped
+        let (other_capsule, _ciphertext) =
+            encrypt(&other_params, &delegating_pk, b"peace at dawn").unwrap();
+        let cfrags =
+            reencrypt_capsules(&kfrags[0], &[capsules[0], other_capsule], None, None).unwrap();
+        assert!(cfrags[0].is_some());
+        assert!(cfrags[1].is_none());
+
+        // Wrong signing key
+        assert_eq!(
+            reencrypt_capsules(
+                &kfrags[0],
+                &capsules,
+                None,
+                Some((&delegating_pk, Some(&delegating_pk), Some(&receiving_pk))),
+            ),
+            Err(ReencryptionError::InvalidKeyFrag)
+        );
+    }
+
+    #[test]
+    fn test_reencrypt_many() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            signing_pk,
+            receiving_sk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        // The second key fragment is signed by someone else
+        let other_signing_sk = SecretKey::random();
+        let other_kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &other_signing_sk,
+            2,
+            3,
+            true,
+            true,
+        );
+        let requested = [
+            kfrags[0].clone(),
+            other_kfrags[1].clone(),
+            kfrags[2].clone(),
+        ];
+
+        let cfrags = reencrypt_many(
+            &capsule,
+            &requested,
+            None,
+            &signing_pk,
+            Some(&delegating_pk),
+            Some(&receiving_pk),
+        );
+        assert_eq!(cfrags.len(), requested.len());
+        assert!(cfrags[1].is_none());
+
+        // The outputs match the inputs by index
+        for (cfrag, kfrag) in cfrags.iter().zip(requested.iter()) {
+            if let Some(cfrag) = cfrag {
+                assert_eq!(cfrag.kfrag_id(), kfrag.id());
+            }
+        }
+
+        let valid_cfrags: Vec<CapsuleFrag> = cfrags.into_iter().flatten().collect();
+        assert_eq!(valid_cfrags.len(), 2);
+        let decrypted = decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &valid_cfrags,
+            &ciphertext,
+        )
+        .unwrap();
+        assert_eq!(&decrypted as &[u8], plaintext);
+    }
+
+    #[test]
+    fn test_decrypt_reencrypted_exact() {
+        let threshold = 2;
+
+        let Delegation {
+            params,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(threshold, 3);
+
+        let plaintext = b"peace at dawn";
+        let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+
+        let cfrags: Vec<CapsuleFrag> = kfrags
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let decrypt = |cfrags: &[CapsuleFrag]| {
+            decrypt_reencrypted_exact(
+                threshold,
+                &receiving_sk,
+                &delegating_pk,
+                &capsule,
+                cfrags,
+                &ciphertext,
+            )
+        };
+
+        let decrypted = decrypt(&cfrags[..threshold]).unwrap();
+        assert_eq!(&decrypted as &[u8], plaintext);
+
+        // Too few
+        assert_eq!(decrypt(&cfrags[..1]), Err(DecryptionError::TooFewFragments));
+        assert_eq!(decrypt(&[]), Err(DecryptionError::TooFewFragments));
+
+        // Too many: the lenient version succeeds, but the exact one reports it
+        assert!(decrypt_reencrypted(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext
+        )
+        .is_some());
+        assert_eq!(decrypt(&cfrags), Err(DecryptionError::TooManyFragments));
+    }
+
+    #[test]
+    fn test_sealed_size() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        for &len in [0usize, 1, 13, 1000].iter() {
+            let plaintext = vec![7u8; len];
+
+            let (_capsule, ciphertext) = encrypt(&params, &pk, &plaintext).unwrap();
+            assert_eq!(ciphertext.len(), ciphertext_size(len));
+
+            let sealed = seal(&pk, &plaintext).unwrap();
+            assert_eq!(sealed.len(), sealed_size(len));
+        }
+    }
+
+    #[cfg(feature = "parallel")]
+    #[test]
+    fn test_reencrypt_batch_parallel() {
+        use super::reencrypt_batch_parallel;
+
+        let Delegation {
+            params,
+            delegating_pk,
+            signing_pk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let capsules: Vec<_> = (0..8)
+            .map(|_| {
+                encrypt(&params, &delegating_pk, b"peace at dawn")
+                    .unwrap()
+                    .0
+            })
+            .collect();
+
+        let cfrags_parallel = reencrypt_batch_parallel(&capsules, &kfrags[0], None);
+        let cfrags_sequential: Vec<CapsuleFrag> = capsules
+            .iter()
+            .map(|capsule| reencrypt(capsule, &kfrags[0], None))
+            .collect();
+
+        assert_eq!(cfrags_parallel.len(), capsules.len());
+        for ((capsule, cfrag_p), cfrag_s) in capsules
+            .iter()
+            .zip(cfrags_parallel.iter())
+            .zip(cfrags_sequential.iter())
+        {
+            // Proofs use fresh randomness, but the reencrypted parts must be the same.
+            assert_eq!(cfrag_p.point_e1, cfrag_s.point_e1);
+            assert_eq!(cfrag_p.point_v1, cfrag_s.point_v1);
+            assert_eq!(cfrag_p.kfrag_id, cfrag_s.kfrag_id);
+            assert_eq!(cfrag_p.precursor, cfrag_s.precursor);
+            assert!(cfrag_p.verify(capsule, &delegating_pk, &receiving_pk, &signing_pk));
+        }
+    }
+
+    struct CountingObserver {
+        count: Cell<usize>,
+        last_kfrag_id: Cell<Option<CurveScalar>>,
+    }
+
+    impl ReencryptionObserver for CountingObserver {
+        fn on_reencrypt(&self, _capsule: &Capsule, kfrag_id: &CurveScalar) {
+            self.count.set(self.count.get() + 1);
+            self.last_kfrag_id.set(Some(*kfrag_id));
+        }
+    }
+
+    #[test]
+    fn test_reencrypt_observed() {
+        let Delegation {
+            params,
+            delegating_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+
+        let observer = CountingObserver {
+            count: Cell::new(0),
+            last_kfrag_id: Cell::new(None),
+        };
+
+        for (i, kfrag) in kfrags.iter().enumerate() {
+            reencrypt_observed(&capsule, kfrag, None, &observer);
+            assert_eq!(observer.count.get(), i + 1);
+            assert_eq!(observer.last_kfrag_id.get(), Some(kfrag.id));
+        }
+
+        // The plain version does not go through the observer
+        reencrypt(&capsule, &kfrags[0], None);
+        assert_eq!(observer.count.get(), kfrags.len());
+    }
+
+    /// A deterministic (and completely insecure) RNG based on SplitMix64.
+    struct TestRng(u64);
+
+    impl RngCore for TestRng {
+        fn next_u32(&mut self) -> u32 {
+            self.next_u64() as u32
+        }
+
+        fn next_u64(&mut self) -> u64 {
+            self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+            let mut z = self.0;
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        }
+
+        fn fill_bytes(&mut self, dest: &mut [u8]) {
+            rand_core::impls::fill_bytes_via_next(self, dest)
+        }
+
+        fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+            self.fill_bytes(dest);
+            Ok(())
+        }
+    }
+
+    impl CryptoRng for TestRng {}
+
+    #[test]
+    fn test_reencrypt_with_rng() {
+        let Delegation {
+            params,
+            delegating_pk,
+            signing_pk,
+            receiving_pk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+
+        let cfrag1 = reencrypt_with_rng(&mut TestRng(123), &capsule, &kfrags[0], Some(b"meta"));
+        let cfrag2 = reencrypt_with_rng(&mut TestRng(123), &capsule, &kfrags[0], Some(b"meta"));
+
+        // Same RNG state means the same proof, so the serialized fragments match exactly
+        assert_eq!(cfrag1, cfrag2);
+        assert_eq!(cfrag1.to_array(), cfrag2.to_array());
+
+        assert!(cfrag1.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+        assert!(cfrag2.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+
+        #[cfg(not(umbral_insecure_no_proofs))]
+        {
+            let cfrag3 = reencrypt_with_rng(&mut TestRng(456), &capsule, &kfrags[0], Some(b"meta"));
+            assert_ne!(cfrag1.to_array(), cfrag3.to_array());
+        }
+    }
+
+    #[test]
+    fn test_encrypt_with_aad() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let header = b"file header, stored in the clear";
+
+        let (capsule, ciphertext) = encrypt_with_aad(
+            &params,
+            &delegating_pk,
+            plaintext,
+            &[&header[..4], &header[4..]],
+        )
+        .unwrap();
+
+        let plaintext_alice =
+            decrypt_original_with_aad(&delegating_sk, &capsule, &ciphertext, &[header]).unwrap();
+        assert_eq!(&plaintext_alice as &[u8], plaintext);
+
+        // The header is authenticated
+        assert!(
+            decrypt_original_with_aad(&delegating_sk, &capsule, &ciphertext, &[b"other"]).is_none()
+        );
+        assert!(decrypt_original(&delegating_sk, &capsule, &ciphertext).is_none());
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let plaintext_bob = decrypt_reencrypted_with_aad(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &ciphertext,
+            &[header],
+        )
+        .unwrap();
+        assert_eq!(&plaintext_bob as &[u8], plaintext);
+    }
+
+    #[test]
+    fn test_in_place_external_nonce() {
+        let Delegation {
+            params,
+            delegating_sk,
+            delegating_pk,
+            receiving_sk,
+            kfrags,
+            ..
+        } = prepare_kfrags(2, 3);
+
+        let plaintext = b"peace at dawn";
+        let mut buf = [0u8; 64];
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+
+        let (capsule, nonce, ciphertext_len) =
+            encrypt_in_place_external_nonce(&params, &delegating_pk, &mut buf, plaintext.len())
+                .unwrap();
+        assert_eq!(ciphertext_len, plaintext.len() + 16);
+        let ciphertext = buf;
+
+        // The nonce is stored separately
+        let nonce = ExternalNonce::from_bytes(nonce.to_array()).unwrap();
+
+        let plaintext_len = decrypt_original_in_place_external_nonce(
+            &delegating_sk,
+            &capsule,
+            &mut buf,
+            ciphertext_len,
+            &nonce,
+        )
+        .unwrap();
+        assert_eq!(&buf[..plaintext_len], plaintext);
+
+        let cfrags: Vec<CapsuleFrag> = kfrags[0..2]
+            .iter()
+            .map(|kfrag| reencrypt(&capsule, kfrag, None))
+            .collect();
+
+        let mut buf = ciphertext;
+        let plaintext_len = decrypt_reencrypted_in_place_external_nonce(
+            &receiving_sk,
+            &delegating_pk,
+            &capsule,
+            &cfrags,
+            &mut buf,
+            ciphertext_len,
+            &nonce,
+        )
+        .unwrap();
+        assert_eq!(&buf[..plaintext_len], plaintext);
+
+        // Not enough room for the tag
+        let mut small_buf = [0u8; 20];
+        assert_eq!(
+            encrypt_in_place_external_nonce(&params, &delegating_pk, &mut small_buf, 10),
+            Err(EncryptionError::BufferTooSmall)
+        );
+        assert_eq!(
+            encrypt_in_place_external_nonce(&params, &delegating_pk, &mut small_buf, 30),
+            Err(EncryptionError::BufferTooSmall)
+        );
+        let mut exact_buf = [0u8; 26];
+        let (_capsule, _nonce, len) =
+            encrypt_in_place_external_nonce(&params, &delegating_pk, &mut exact_buf, 10).unwrap();
+        assert_eq!(len, exact_buf.len());
     }
 }
@@ -0,0 +1,179 @@
+//! Property-based tests of serialization and of the full reencryption flow.
+//!
+//! These complement the fixed-input unit tests in the other modules.
+//! The inputs are generated by `proptest`, which shrinks a failing case to a minimal one
+//! (e.g. the shortest plaintext or the smallest threshold) and reports it.
+
+use alloc::vec::Vec;
+use core::fmt::Debug;
+
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use crate::curve::{CurvePoint, CurveScalar};
+use crate::{
+    decrypt_original, decrypt_reencrypted, encrypt, encrypt_detached, generate_kfrags, reencrypt,
+    Capsule, CapsuleFrag, DetachedTag, KeyFrag, Parameters, PublicKey, RecoverableSignature,
+    SecretKey, SerializableToArray,
+};
+
+/// The full flow involves a lot of curve arithmetic, so the number of cases is reduced.
+const CASES: u32 = 32;
+
+fn secret_key() -> impl Strategy<Value = SecretKey> {
+    any::<[u8; 32]>().prop_filter_map("not a valid secret key", |bytes| {
+        SecretKey::from_bytes(bytes)
+    })
+}
+
+fn nonzero_scalar() -> impl Strategy<Value = CurveScalar> {
+    any::<[u8; 32]>().prop_filter_map("not a valid nonzero scalar", |bytes| {
+        CurveScalar::from_bytes(bytes).filter(|scalar| scalar != &CurveScalar::default())
+    })
+}
+
+/// Returns `(threshold, num_kfrags)` with `1 <= threshold <= num_kfrags <= max_kfrags`.
+fn threshold_and_num_kfrags(max_kfrags: usize) -> impl Strategy<Value = (usize, usize)> {
+    (1..=max_kfrags).prop_flat_map(|num_kfrags| (1..=num_kfrags, Just(num_kfrags)))
+}
+
+fn check_round_trip<T: SerializableToArray + PartialEq + Debug>(obj: &T) {
+    let arr = obj.to_array();
+    assert_eq!(&T::from_array(&arr).unwrap(), obj);
+    assert_eq!(&T::from_bytes(&arr).unwrap(), obj);
+    assert!(T::from_bytes(&arr[1..]).is_none());
+}
+
+fn check_flow(
+    threshold: usize,
+    num_kfrags: usize,
+    plaintext: &[u8],
+    delegating_sk: &SecretKey,
+    signing_sk: &SecretKey,
+    receiving_sk: &SecretKey,
+) {
+    let params = Parameters::new();
+
+    let delegating_pk = PublicKey::from_secret_key(delegating_sk);
+    let signing_pk = PublicKey::from_secret_key(signing_sk);
+    let receiving_pk = PublicKey::from_secret_key(receiving_sk);
+
+    let (capsule, ciphertext) = encrypt(&params, &delegating_pk, plaintext).unwrap();
+    let plaintext_alice = decrypt_original(delegating_sk, &capsule, &ciphertext);
+    assert_eq!(plaintext_alice.as_deref(), Some(plaintext));
+
+    let kfrags = generate_kfrags(
+        &params,
+        delegating_sk,
+        &receiving_pk,
+        signing_sk,
+        threshold,
+        num_kfrags,
+        true,
+        true,
+    );
+    assert_eq!(kfrags.len(), num_kfrags);
+
+    // Any `threshold` fragments will do; take them from the end.
+    let cfrags: Vec<CapsuleFrag> = kfrags[num_kfrags - threshold..]
+        .iter()
+        .map(|kfrag| {
+            assert!(kfrag.verify(&signing_pk, Some(&delegating_pk), Some(&receiving_pk)));
+            reencrypt(&capsule, kfrag, None)
+        })
+        .collect();
+
+    for cfrag in cfrags.iter() {
+        assert!(cfrag.verify(&capsule, &delegating_pk, &receiving_pk, &signing_pk));
+    }
+
+    let plaintext_bob =
+        decrypt_reencrypted(receiving_sk, &delegating_pk, &capsule, &cfrags, &ciphertext);
+    assert_eq!(plaintext_bob.as_deref(), Some(plaintext));
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(CASES))]
+
+    #[test]
+    fn test_serialization_round_trips(
+        delegating_sk in secret_key(),
+        signing_sk in secret_key(),
+        receiving_sk in secret_key(),
+        scalar in nonzero_scalar(),
+        message in vec(any::<u8>(), 0..64),
+        metadata in vec(any::<u8>(), 0..16),
+        sign_delegating_key in any::<bool>(),
+        sign_receiving_key in any::<bool>(),
+    ) {
+        let params = Parameters::new();
+        check_round_trip(&params);
+
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        check_round_trip(&delegating_sk);
+        check_round_trip(&delegating_pk);
+
+        check_round_trip(&scalar);
+        check_round_trip(&CurvePoint::generator().mul(&scalar));
+
+        let signature: RecoverableSignature = signing_sk.sign_recoverable(&message);
+        check_round_trip(&signature);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, &message).unwrap();
+        check_round_trip::<Capsule>(&capsule);
+
+        let mut data = message.clone();
+        let tag: DetachedTag = encrypt_detached(&params, &delegating_pk, &mut data)
+            .unwrap()
+            .1;
+        check_round_trip(&tag);
+
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            1,
+            1,
+            sign_delegating_key,
+            sign_receiving_key,
+        );
+        check_round_trip::<KeyFrag>(&kfrags[0]);
+
+        let cfrag = reencrypt(&capsule, &kfrags[0], Some(&metadata));
+        check_round_trip::<CapsuleFrag>(&cfrag);
+    }
+
+    #[test]
+    fn test_full_flow(
+        (threshold, num_kfrags) in threshold_and_num_kfrags(8),
+        plaintext in vec(any::<u8>(), 0..256),
+        delegating_sk in secret_key(),
+        signing_sk in secret_key(),
+        receiving_sk in secret_key(),
+    ) {
+        check_flow(threshold, num_kfrags, &plaintext, &delegating_sk, &signing_sk, &receiving_sk);
+    }
+}
+
+#[test]
+fn test_full_flow_edge_cases() {
+    // Shrinking tends to arrive at these anyway, but they are always checked explicitly.
+    for &(threshold, num_kfrags, plaintext) in &[
+        (1, 1, &b""[..]),
+        (1, 1, &b"x"[..]),
+        (3, 3, &b"peace at dawn"[..]),
+        (1, 4, &b"peace at dawn"[..]),
+    ] {
+        check_flow(
+            threshold,
+            num_kfrags,
+            plaintext,
+            &SecretKey::random(),
+            &SecretKey::random(),
+            &SecretKey::random(),
+        );
+    }
+}
@@ -0,0 +1,194 @@
+//! `serde` support for the public objects, based on their [`SerializableToArray`] encoding.
+//!
+//! Human-readable formats (e.g. JSON) get a hex string,
+//! and binary formats get a byte array.
+//! Deserialization goes through `from_array()`, so the usual validity checks apply.
+
+use alloc::string::String;
+use core::fmt;
+use core::marker::PhantomData;
+
+use generic_array::GenericArray;
+use serde::de::{Error, SeqAccess, Unexpected, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use typenum::Unsigned;
+
+use crate::capsule::Capsule;
+use crate::capsule_frag::CapsuleFrag;
+use crate::curve::PublicKey;
+use crate::key_frag::KeyFrag;
+use crate::params::Parameters;
+use crate::traits::SerializableToArray;
+
+const HEX_DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        result.push(HEX_DIGITS[(byte >> 4) as usize] as char);
+        result.push(HEX_DIGITS[(byte & 0xf) as usize] as char);
+    }
+    result
+}
+
+fn hex_digit(c: u8) -> Option<u8> {
+    match c {
+        b'0'..=b'9' => Some(c - b'0'),
+        b'a'..=b'f' => Some(c - b'a' + 10),
+        b'A'..=b'F' => Some(c - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes a hex string into `out`, failing if the length does not match exactly.
+fn from_hex(s: &str, out: &mut [u8]) -> Option<()> {
+    let s = s.as_bytes();
+    if s.len() != out.len() * 2 {
+        return None;
+    }
+    for (byte, pair) in out.iter_mut().zip(s.chunks(2)) {
+        *byte = (hex_digit(pair[0])? << 4) | hex_digit(pair[1])?;
+    }
+    Some(())
+}
+
+fn serialize_as_array<T, S>(obj: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: SerializableToArray,
+    S: Serializer,
+{
+    let arr = obj.to_array();
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&to_hex(&arr))
+    } else {
+        serializer.serialize_bytes(&arr)
+    }
+}
+
+struct ArrayVisitor<T>(PhantomData<T>);
+
+impl<T: SerializableToArray> ArrayVisitor<T> {
+    fn from_arr<E: Error>(arr: &GenericArray<u8, T::Size>) -> Result<T, E> {
+        T::from_array(arr).ok_or_else(|| E::custom("invalid object representation"))
+    }
+}
+
+impl<'de, T: SerializableToArray> Visitor<'de> for ArrayVisitor<T> {
+    type Value = T;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let size = T::Size::to_usize();
+        write!(
+            formatter,
+            "{} bytes, or a hex string of {} characters",
+            size,
+            size * 2
+        )
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+        let mut arr = GenericArray::<u8, T::Size>::default();
+        from_hex(v, &mut arr).ok_or_else(|| E::invalid_value(Unexpected::Str(v), &self))?;
+        Self::from_arr(&arr)
+    }
+
+    fn visit_bytes<E: Error>(self, v: &[u8]) -> Result<Self::Value, E> {
+        if v.len() != T::Size::to_usize() {
+            return Err(E::invalid_length(v.len(), &self));
+        }
+        Self::from_arr(GenericArray::from_slice(v))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut arr = GenericArray::<u8, T::Size>::default();
+        for (i, byte) in arr.iter_mut().enumerate() {
+            *byte = seq
+                .next_element()?
+                .ok_or_else(|| A::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(A::Error::invalid_length(arr.len() + 1, &self));
+        }
+        Self::from_arr(&arr)
+    }
+}
+
+fn deserialize_from_array<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: SerializableToArray,
+    D: Deserializer<'de>,
+{
+    if deserializer.is_human_readable() {
+        deserializer.deserialize_str(ArrayVisitor::<T>(PhantomData))
+    } else {
+        deserializer.deserialize_bytes(ArrayVisitor::<T>(PhantomData))
+    }
+}
+
+macro_rules! impl_serde_via_array {
+    ($type:ty) => {
+        impl Serialize for $type {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serialize_as_array(self, serializer)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                deserialize_from_array(deserializer)
+            }
+        }
+    };
+}
+
+impl_serde_via_array!(Capsule);
+impl_serde_via_array!(CapsuleFrag);
+impl_serde_via_array!(KeyFrag);
+impl_serde_via_array!(Parameters);
+impl_serde_via_array!(PublicKey);
+
+#[cfg(test)]
+mod tests {
+
+    use super::{from_hex, to_hex};
+    use crate::{encrypt, Capsule, Parameters, PublicKey, SecretKey, SerializableToArray};
+
+    #[test]
+    fn test_hex() {
+        let bytes = [0x00, 0x1f, 0xa0, 0xff];
+        let s = to_hex(&bytes);
+        assert_eq!(s, "001fa0ff");
+
+        let mut decoded = [0u8; 4];
+        from_hex(&s, &mut decoded).unwrap();
+        assert_eq!(decoded, bytes);
+        from_hex("001FA0FF", &mut decoded).unwrap();
+        assert_eq!(decoded, bytes);
+
+        assert!(from_hex("001fa0f", &mut decoded).is_none());
+        assert!(from_hex("001fa0fg", &mut decoded).is_none());
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+        let (capsule, _ciphertext) = encrypt(&params, &pk, b"peace at dawn").unwrap();
+
+        let json = serde_json::to_string(&capsule).unwrap();
+        assert_eq!(json, ["\"", &to_hex(&capsule.to_array()), "\""].concat());
+        let capsule_back: Capsule = serde_json::from_str(&json).unwrap();
+        assert_eq!(capsule, capsule_back);
+
+        // Invalid capsule (failing the internal consistency check)
+        let mut capsule_arr = capsule.to_array();
+        let last = capsule_arr.len() - 1;
+        capsule_arr[last] ^= 1;
+        let bad_json = ["\"", &to_hex(&capsule_arr), "\""].concat();
+        assert!(serde_json::from_str::<Capsule>(&bad_json).is_err());
+
+        // Wrong length
+        assert!(serde_json::from_str::<Capsule>("\"0011\"").is_err());
+    }
+}
@@ -0,0 +1,304 @@
+use alloc::vec;
+use core::fmt;
+use std::io::{self, Read, Write};
+
+use crate::capsule::Capsule;
+use crate::curve::{PublicKey, SecretKey};
+use crate::dem::UmbralDEM;
+use crate::params::Parameters;
+use crate::traits::SerializableToArray;
+
+/// The size of a plaintext chunk in the streams produced by [`encrypt_reader()`].
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Errors that can happen in [`encrypt_reader()`] and [`decrypt_reader()`].
+///
+/// Unlike the other error types, this one is not `Copy`,
+/// since it carries the IO errors of the reader or the writer as is.
+#[derive(Debug)]
+pub enum StreamError {
+    /// Reading from the source or writing to the destination failed.
+    Io(io::Error),
+    /// A chunk could not be encrypted.
+    EncryptionFailed,
+    /// A chunk could not be decrypted: the stream was modified, truncated,
+    /// or does not correspond to the capsule.
+    DecryptionFailed,
+}
+
+impl From<io::Error> for StreamError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(error) => write!(f, "IO error: {}", error),
+            Self::EncryptionFailed => write!(f, "Encryption of a chunk failed"),
+            Self::DecryptionFailed => write!(f, "Decryption of a chunk failed"),
+        }
+    }
+}
+
+impl std::error::Error for StreamError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Reads from `reader` until `buf` is full or the end of the stream is reached,
+/// retrying on interruptions. Returns the number of bytes read.
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(error) if error.kind() == io::ErrorKind::Interrupted => {}
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(filled)
+}
+
+/// Encrypts everything read from `reader` for the owner of `pk`,
+/// writing the ciphertext to `writer`, and returns the KEM [`Capsule`].
+///
+/// The data is processed in chunks of 64 KiB, so the whole plaintext is never held in memory,
+/// and its size is not limited by [`MAX_PLAINTEXT_SIZE`](`crate::MAX_PLAINTEXT_SIZE`).
+/// Each chunk is authenticated separately, along with its position in the stream
+/// and the capsule. The last chunk is always shorter than the others (and can be empty),
+/// which lets [`decrypt_reader()`] detect a truncated stream.
+///
+/// Note that the result is not compatible with [`encrypt()`](`crate::encrypt()`).
+pub fn encrypt_reader(
+    params: &Parameters,
+    pk: &PublicKey,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> Result<Capsule, StreamError> {
+    let (capsule, key_seed) = Capsule::from_pubkey(params, pk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+
+    let mut buf = vec![0u8; CHUNK_SIZE + UmbralDEM::tag_size()];
+    let mut index: u64 = 0;
+    loop {
+        let len = read_full(&mut reader, &mut buf[..CHUNK_SIZE])?;
+        let last = len < CHUNK_SIZE;
+        let ciphertext_len = dem
+            .encrypt_chunk_in_place(&mut buf, len, &capsule_bytes, index, last)
+            .ok_or(StreamError::EncryptionFailed)?;
+        writer.write_all(&buf[..ciphertext_len])?;
+
+        if last {
+            break;
+        }
+        index = index.checked_add(1).ok_or(StreamError::EncryptionFailed)?;
+    }
+
+    writer.flush()?;
+    Ok(capsule)
+}
+
+/// Decrypts a stream produced by [`encrypt_reader()`] with the original secret key,
+/// reading the ciphertext from `reader` and writing the plaintext to `writer`.
+///
+/// Every chunk is verified before it is written, but the stream as a whole
+/// is only known to be intact when the function returns successfully.
+/// If it returns an error, the data already written to `writer` must be discarded.
+pub fn decrypt_reader(
+    decrypting_sk: &SecretKey,
+    capsule: &Capsule,
+    mut reader: impl Read,
+    mut writer: impl Write,
+) -> Result<(), StreamError> {
+    let key_seed = capsule.open_original(decrypting_sk);
+    let dem = UmbralDEM::new(&key_seed.to_array());
+    let capsule_bytes = capsule.to_array();
+
+    let chunk_ciphertext_size = CHUNK_SIZE + UmbralDEM::tag_size();
+    let mut buf = vec![0u8; chunk_ciphertext_size];
+    let mut index: u64 = 0;
+    loop {
+        let len = read_full(&mut reader, &mut buf)?;
+        let last = len < chunk_ciphertext_size;
+        let plaintext_len = dem
+            .decrypt_chunk_in_place(&mut buf, len, &capsule_bytes, index, last)
+            .ok_or(StreamError::DecryptionFailed)?;
+        writer.write_all(&buf[..plaintext_len])?;
+
+        if last {
+            break;
+        }
+        index = index.checked_add(1).ok_or(StreamError::DecryptionFailed)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use alloc::vec::Vec;
+    use std::io::{self, Cursor, Read};
+
+    use super::{decrypt_reader, encrypt_reader, StreamError, CHUNK_SIZE};
+    use crate::{Parameters, PublicKey, SecretKey};
+
+    /// A reader returning at most a few bytes per call, and interrupting every other call.
+    struct SlowReader<R> {
+        inner: R,
+        interrupt: bool,
+    }
+
+    impl<R: Read> Read for SlowReader<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            let len = buf.len().min(1000);
+            self.inner.read(&mut buf[..len])
+        }
+    }
+
+    struct FailingReader;
+
+    impl Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> io::Result<usize> {
+            Err(io::Error::from(io::ErrorKind::Other))
+        }
+    }
+
+    fn make_plaintext(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i % 251) as u8).collect()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        // A multi-megabyte buffer, a multiple of the chunk size, and an empty one
+        for &len in &[3 * 1024 * 1024 + 123, 2 * CHUNK_SIZE, 0] {
+            let plaintext = make_plaintext(len);
+
+            let mut ciphertext = Vec::new();
+            let capsule =
+                encrypt_reader(&params, &pk, Cursor::new(&plaintext), &mut ciphertext).unwrap();
+
+            let mut decrypted = Vec::new();
+            decrypt_reader(&sk, &capsule, Cursor::new(&ciphertext), &mut decrypted).unwrap();
+            assert_eq!(decrypted, plaintext);
+
+            // Partial reads are handled on both sides
+            let slow = SlowReader {
+                inner: Cursor::new(&ciphertext),
+                interrupt: false,
+            };
+            let mut decrypted = Vec::new();
+            decrypt_reader(&sk, &capsule, slow, &mut decrypted).unwrap();
+            assert_eq!(decrypted, plaintext);
+
+            let slow = SlowReader {
+                inner: Cursor::new(&plaintext),
+                interrupt: false,
+            };
+            let mut ciphertext = Vec::new();
+            let capsule = encrypt_reader(&params, &pk, slow, &mut ciphertext).unwrap();
+            let mut decrypted = Vec::new();
+            decrypt_reader(&sk, &capsule, Cursor::new(&ciphertext), &mut decrypted).unwrap();
+            assert_eq!(decrypted, plaintext);
+        }
+    }
+
+    #[test]
+    fn test_modified_stream() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        let plaintext = make_plaintext(2 * CHUNK_SIZE);
+        let mut ciphertext = Vec::new();
+        let capsule =
+            encrypt_reader(&params, &pk, Cursor::new(&plaintext), &mut ciphertext).unwrap();
+        let chunk_len = ciphertext.len() / 2;
+
+        let check_fails = |stream: &[u8]| {
+            let mut decrypted = Vec::new();
+            let result = decrypt_reader(&sk, &capsule, Cursor::new(stream), &mut decrypted);
+            assert!(matches!(result, Err(StreamError::DecryptionFailed)));
+        };
+
+        // Truncated at a chunk boundary (the empty last chunk is dropped)
+        check_fails(&ciphertext[..ciphertext.len() - 16]);
+        check_fails(&ciphertext[..chunk_len]);
+
+        // Truncated in the middle of a chunk
+        check_fails(&ciphertext[..chunk_len + 100]);
+
+        // Swapped chunks
+        let mut swapped = Vec::new();
+        swapped.extend_from_slice(&ciphertext[chunk_len..2 * chunk_len]);
+        swapped.extend_from_slice(&ciphertext[..chunk_len]);
+        swapped.extend_from_slice(&ciphertext[2 * chunk_len..]);
+        check_fails(&swapped);
+
+        // A modified byte
+        let mut modified = ciphertext.clone();
+        modified[CHUNK_SIZE + 100] ^= 1;
+        check_fails(&modified);
+
+        // A wrong key
+        let other_sk = SecretKey::random();
+        let mut decrypted = Vec::new();
+        let result = decrypt_reader(
+            &other_sk,
+            &capsule,
+            Cursor::new(&ciphertext),
+            &mut decrypted,
+        );
+        assert!(matches!(result, Err(StreamError::DecryptionFailed)));
+    }
+
+    #[test]
+    fn test_io_errors() {
+        let params = Parameters::new();
+        let sk = SecretKey::random();
+        let pk = PublicKey::from_secret_key(&sk);
+
+        let mut ciphertext = Vec::new();
+        let result = encrypt_reader(&params, &pk, FailingReader, &mut ciphertext);
+        match result {
+            Err(StreamError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::Other),
+            _ => panic!("expected an IO error"),
+        }
+
+        let (capsule, _ciphertext) = crate::encrypt(&params, &pk, b"peace at dawn").unwrap();
+        let mut decrypted = Vec::new();
+        let result = decrypt_reader(&sk, &capsule, FailingReader, &mut decrypted);
+        assert!(matches!(result, Err(StreamError::Io(_))));
+
+        // Writing to a full buffer
+        let plaintext = make_plaintext(1000);
+        let mut small_buf = [0u8; 100];
+        let result = encrypt_reader(
+            &params,
+            &pk,
+            Cursor::new(&plaintext),
+            Cursor::new(&mut small_buf[..]),
+        );
+        match result {
+            Err(StreamError::Io(error)) => assert_eq!(error.kind(), io::ErrorKind::WriteZero),
+            _ => panic!("expected an IO error"),
+        }
+    }
+}
@@ -1,3 +1,6 @@
+use crate::curve::{CurvePoint, CurveScalar, PointError, Signature, SignatureError};
+
+use core::fmt;
 use core::ops::Sub;
 use generic_array::sequence::Split;
 use generic_array::{ArrayLength, GenericArray};
@@ -56,6 +59,107 @@ where
     }
 }
 
+/// Errors that can happen when deserializing an object composed of several fields
+/// (see e.g. [`Capsule::try_from_bytes()`](`crate::Capsule::try_from_bytes()`)).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DeserializationError {
+    /// The number of bytes differs from the serialized size of the object.
+    WrongLength,
+    /// One of the points could not be deserialized.
+    InvalidPoint(PointError),
+    /// One of the scalars is not less than the curve order.
+    InvalidScalar,
+    /// One of the signatures could not be deserialized.
+    InvalidSignature(SignatureError),
+    /// One of the flags is neither `0` nor `1`.
+    InvalidBool,
+    /// All the fields are valid, but the capsule does not verify.
+    InvalidCapsule,
+}
+
+impl fmt::Display for DeserializationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongLength => write!(f, "Wrong number of bytes"),
+            Self::InvalidPoint(err) => write!(f, "Invalid point: {}", err),
+            Self::InvalidScalar => write!(f, "Invalid scalar"),
+            Self::InvalidSignature(err) => write!(f, "Invalid signature: {}", err),
+            Self::InvalidBool => write!(f, "Invalid boolean flag"),
+            Self::InvalidCapsule => write!(f, "Capsule verification failed"),
+        }
+    }
+}
+
+impl From<PointError> for DeserializationError {
+    fn from(err: PointError) -> Self {
+        Self::InvalidPoint(err)
+    }
+}
+
+impl From<SignatureError> for DeserializationError {
+    fn from(err: SignatureError) -> Self {
+        Self::InvalidSignature(err)
+    }
+}
+
+/// Splits a serialized object into its fields, reporting why a field cannot be deserialized.
+pub(crate) struct FieldReader<'a>(&'a [u8]);
+
+impl<'a> FieldReader<'a> {
+    /// Fails if `bytes` is not exactly as long as a serialized `T`.
+    pub fn new<T: SerializableToArray>(bytes: &'a [u8]) -> Result<Self, DeserializationError> {
+        if bytes.len() == T::Size::to_usize() {
+            Ok(Self(bytes))
+        } else {
+            Err(DeserializationError::WrongLength)
+        }
+    }
+
+    fn next<T: SerializableToArray>(&mut self) -> &'a [u8] {
+        let (field, rest) = self.0.split_at(T::Size::to_usize());
+        self.0 = rest;
+        field
+    }
+
+    pub fn point(&mut self) -> Result<CurvePoint, DeserializationError> {
+        Ok(CurvePoint::try_from_bytes(self.next::<CurvePoint>())?)
+    }
+
+    pub fn scalar(&mut self) -> Result<CurveScalar, DeserializationError> {
+        CurveScalar::from_bytes(self.next::<CurveScalar>())
+            .ok_or(DeserializationError::InvalidScalar)
+    }
+
+    pub fn signature(&mut self) -> Result<Signature, DeserializationError> {
+        Ok(Signature::try_from_bytes(self.next::<Signature>())?)
+    }
+
+    pub fn bool(&mut self) -> Result<bool, DeserializationError> {
+        bool::from_bytes(self.next::<bool>()).ok_or(DeserializationError::InvalidBool)
+    }
+}
+
+/// A companion of [`SerializableToArray`] producing plain `[u8; N]` arrays,
+/// so that the callers do not have to depend on `generic_array`.
+///
+/// `N` is the same as the length of [`SerializableToArray::Size`]
+/// and is also exported as a constant (e.g. [`CAPSULE_SIZE`](`crate::CAPSULE_SIZE`)).
+/// Every implementation is accompanied by a `const` item whose type only checks
+/// if the two lengths are equal, so [`to_fixed_bytes()`](`Self::to_fixed_bytes`) cannot panic.
+pub trait SerializableToFixedBytes<const N: usize>: SerializableToArray {
+    /// Produces a byte array with the object's contents.
+    fn to_fixed_bytes(&self) -> [u8; N] {
+        let mut result = [0u8; N];
+        result.copy_from_slice(&self.to_array());
+        result
+    }
+
+    /// Attempts to produce the object back from the serialized form.
+    fn from_fixed_bytes(bytes: &[u8; N]) -> Option<Self> {
+        Self::from_bytes(bytes)
+    }
+}
+
 impl SerializableToArray for bool {
     type Size = U1;
 
@@ -0,0 +1,368 @@
+//! A documented, stable byte layout for the objects exchanged between parties,
+//! intended for implementations in other languages.
+//!
+//! The layouts are defined here field by field, in terms of the following primitives:
+//!
+//! * point (33 bytes): a SEC1 compressed curve point,
+//!   that is, `0x02` (even y) or `0x03` (odd y) followed by the big-endian x coordinate;
+//! * scalar (32 bytes): a big-endian integer modulo the curve order;
+//! * signature (64 bytes): an ECDSA signature, big-endian `r` followed by big-endian `s`;
+//! * bool (1 byte): `0x00` or `0x01`.
+//!
+//! [`Capsule`] (131 bytes):
+//!
+//! | Offset | Size | Field                       |
+//! |--------|------|-----------------------------|
+//! | 0      | 33   | parameters (point `u`)      |
+//! | 33     | 33   | point `E`                   |
+//! | 66     | 33   | point `V`                   |
+//! | 99     | 32   | signature (scalar)          |
+//!
+//! [`KeyFrag`] (293 bytes):
+//!
+//! | Offset | Size | Field                       |
+//! |--------|------|-----------------------------|
+//! | 0      | 33   | parameters (point `u`)      |
+//! | 33     | 32   | ID (scalar)                 |
+//! | 65     | 32   | key (scalar)                |
+//! | 97     | 33   | precursor (point)           |
+//! | 130    | 33   | commitment (point)          |
+//! | 163    | 64   | signature for the proxy     |
+//! | 227    | 64   | signature for the receiver  |
+//! | 291    | 1    | delegating key signed       |
+//! | 292    | 1    | receiving key signed        |
+//!
+//! [`CapsuleFrag`] (391 bytes):
+//!
+//! | Offset | Size | Field                       |
+//! |--------|------|-----------------------------|
+//! | 0      | 33   | point `E1`                  |
+//! | 33     | 33   | point `V1`                  |
+//! | 66     | 32   | key fragment ID (scalar)    |
+//! | 98     | 33   | precursor (point)           |
+//! | 131    | 33   | proof: point `E2`           |
+//! | 164    | 33   | proof: point `V2`           |
+//! | 197    | 33   | proof: kfrag commitment     |
+//! | 230    | 33   | proof: kfrag PoK (point)    |
+//! | 263    | 32   | proof: signature (scalar)   |
+//! | 295    | 64   | proof: kfrag signature      |
+//! | 359    | 32   | proof: metadata (scalar)    |
+//!
+//! The objects are converted with the regular serialization:
+//! [`to_wire()`](`WireFormat::to_wire()`) is [`to_array()`](`crate::SerializableToArray::to_array()`),
+//! and [`from_wire()`](`WireFormat::from_wire()`) is the `try_from_bytes()` of the type
+//! (e.g. [`Capsule::try_from_bytes()`]).
+//! The layouts above are the contract, pinned by test vectors built from the primitives,
+//! so a change of the serialization or of the ECC backend that alters any of the encodings
+//! is caught by the tests.
+
+use alloc::vec::Vec;
+
+use crate::capsule::{Capsule, CAPSULE_SIZE};
+use crate::capsule_frag::{CapsuleFrag, CAPSULE_FRAG_SIZE};
+use crate::key_frag::{KeyFrag, KEY_FRAG_SIZE};
+use crate::traits::{DeserializationError, SerializableToArray};
+
+/// Conversion to and from the wire format described in the [module docs](`self`).
+pub trait WireFormat: SerializableToArray {
+    /// The size of the encoded object in bytes.
+    const WIRE_SIZE: usize;
+
+    /// Encodes the object.
+    fn to_wire(&self) -> Vec<u8> {
+        self.to_array().to_vec()
+    }
+
+    /// Decodes the object, reporting the reason of a failure: a wrong length,
+    /// an invalid field, or a failed internal consistency check.
+    fn from_wire(bytes: &[u8]) -> Result<Self, DeserializationError>;
+}
+
+impl WireFormat for Capsule {
+    const WIRE_SIZE: usize = CAPSULE_SIZE;
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(bytes)
+    }
+}
+
+impl WireFormat for KeyFrag {
+    const WIRE_SIZE: usize = KEY_FRAG_SIZE;
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(bytes)
+    }
+}
+
+impl WireFormat for CapsuleFrag {
+    const WIRE_SIZE: usize = CAPSULE_FRAG_SIZE;
+
+    fn from_wire(bytes: &[u8]) -> Result<Self, DeserializationError> {
+        Self::try_from_bytes(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use generic_array::typenum::Unsigned;
+
+    use alloc::vec::Vec;
+
+    use super::WireFormat;
+    use crate::capsule_frag::CapsuleFragProof;
+    use crate::curve::{CurvePoint, CurveScalar, PointError, Signature};
+    use crate::hashing::ScalarDigest;
+    use crate::key_frag::KeyFragProof;
+    use crate::traits::DeserializationError;
+    use crate::{
+        encrypt, generate_kfrags, reencrypt, Capsule, CapsuleFrag, KeyFrag, Parameters, PublicKey,
+        SecretKey, SerializableToArray,
+    };
+
+    const POINT_SIZE: usize = 33;
+    const SCALAR_SIZE: usize = 32;
+    const SIGNATURE_SIZE: usize = 64;
+
+    #[test]
+    fn test_primitives() {
+        assert_eq!(
+            <CurvePoint as SerializableToArray>::Size::to_usize(),
+            POINT_SIZE
+        );
+        assert_eq!(
+            <CurveScalar as SerializableToArray>::Size::to_usize(),
+            SCALAR_SIZE
+        );
+        assert_eq!(
+            <Signature as SerializableToArray>::Size::to_usize(),
+            SIGNATURE_SIZE
+        );
+
+        // Scalars are big-endian
+        let mut one = [0u8; SCALAR_SIZE];
+        one[SCALAR_SIZE - 1] = 1;
+        assert_eq!(CurveScalar::from_u64(1).to_array().as_slice(), &one);
+
+        // Points are SEC1-compressed
+        let g = CurvePoint::generator().to_array();
+        assert_eq!(g[0], 0x02);
+        assert_eq!(&g[1..5], &[0x79, 0xbe, 0x66, 0x7e]);
+    }
+
+    fn check_round_trip<T>(obj: &T)
+    where
+        T: WireFormat + PartialEq + core::fmt::Debug,
+    {
+        let wire = obj.to_wire();
+        assert_eq!(wire.len(), T::WIRE_SIZE);
+        assert_eq!(wire.as_slice(), obj.to_array().as_slice());
+        assert_eq!(&T::from_wire(&wire).unwrap(), obj);
+
+        assert_eq!(
+            T::from_wire(&wire[1..]),
+            Err(DeserializationError::WrongLength)
+        );
+        let mut longer = wire.clone();
+        longer.push(0);
+        assert_eq!(
+            T::from_wire(&longer),
+            Err(DeserializationError::WrongLength)
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let params = Parameters::new();
+
+        let delegating_sk = SecretKey::random();
+        let delegating_pk = PublicKey::from_secret_key(&delegating_sk);
+        let signing_sk = SecretKey::random();
+        let receiving_sk = SecretKey::random();
+        let receiving_pk = PublicKey::from_secret_key(&receiving_sk);
+
+        let (capsule, _ciphertext) = encrypt(&params, &delegating_pk, b"peace at dawn").unwrap();
+        let kfrags = generate_kfrags(
+            &params,
+            &delegating_sk,
+            &receiving_pk,
+            &signing_sk,
+            2,
+            3,
+            true,
+            false,
+        );
+        let cfrag = reencrypt(&capsule, &kfrags[0], Some(b"metadata"));
+
+        check_round_trip(&capsule);
+        check_round_trip(&kfrags[0]);
+        check_round_trip(&cfrag);
+
+        // Spot-check the documented offsets
+        let capsule_wire = capsule.to_wire();
+        assert_eq!(&capsule_wire[33..66], capsule.point_e.to_array().as_slice());
+        assert_eq!(&capsule_wire[99..], capsule.signature.to_array().as_slice());
+
+        let kfrag_wire = kfrags[0].to_wire();
+        assert_eq!(&kfrag_wire[33..65], kfrags[0].id.to_array().as_slice());
+        assert_eq!(
+            &kfrag_wire[130..163],
+            kfrags[0].proof.commitment.to_array().as_slice()
+        );
+        assert_eq!(&kfrag_wire[291..], &[1, 0]);
+
+        let cfrag_wire = cfrag.to_wire();
+        assert_eq!(&cfrag_wire[66..98], cfrag.kfrag_id.to_array().as_slice());
+        assert_eq!(
+            &cfrag_wire[359..],
+            cfrag.proof.metadata.to_array().as_slice()
+        );
+
+        // An invalid bool field
+        let mut bad_kfrag = kfrag_wire;
+        bad_kfrag[292] = 2;
+        assert_eq!(
+            KeyFrag::from_wire(&bad_kfrag),
+            Err(DeserializationError::InvalidBool)
+        );
+
+        // The capsule consistency check applies
+        let mut bad_capsule = capsule_wire;
+        bad_capsule[Capsule::WIRE_SIZE - 1] ^= 1;
+        assert_eq!(
+            Capsule::from_wire(&bad_capsule),
+            Err(DeserializationError::InvalidCapsule)
+        );
+
+        // An invalid point
+        let mut bad_cfrag = cfrag_wire;
+        bad_cfrag[0] = 0x05;
+        assert_eq!(
+            CapsuleFrag::from_wire(&bad_cfrag),
+            Err(DeserializationError::InvalidPoint(
+                PointError::MalformedEncoding
+            ))
+        );
+    }
+
+    // Compressed multiples of the secp256k1 generator, `i * G` for `i` in 1..=4
+    const POINTS: [&str; 4] = [
+        "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+        "02c6047f9441ed7d6d3045406e95c07cd85c778e4b8cef3ca7abac09b95c709ee5",
+        "02f9308a019258c31049344f85f89d5229b531c845836f99b08601f113bce036f9",
+        "02e493dbf1c10d80f3581e4904930b1404cc6c13900ee0758474fa94abe8c4cd13",
+    ];
+
+    fn unhex(s: &str) -> Vec<u8> {
+        (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    fn point(i: u64) -> CurvePoint {
+        &CurvePoint::generator() * &CurveScalar::from_u64(i)
+    }
+
+    /// The big-endian encoding of a small scalar.
+    fn scalar_bytes(x: u8) -> Vec<u8> {
+        let mut bytes = [0u8; SCALAR_SIZE].to_vec();
+        bytes[SCALAR_SIZE - 1] = x;
+        bytes
+    }
+
+    /// A signature with small `r` and `s`, and its encoding.
+    fn small_signature(r: u8, s: u8) -> (Signature, Vec<u8>) {
+        let mut bytes = scalar_bytes(r);
+        bytes.extend(scalar_bytes(s));
+        (Signature::from_bytes(&bytes).unwrap(), bytes)
+    }
+
+    fn check_vector<T>(obj: &T, expected: &[u8])
+    where
+        T: WireFormat + PartialEq + core::fmt::Debug,
+    {
+        assert_eq!(expected.len(), T::WIRE_SIZE);
+        assert_eq!(obj.to_wire().as_slice(), expected);
+        assert_eq!(&T::from_wire(expected).unwrap(), obj);
+    }
+
+    #[test]
+    fn test_vectors() {
+        let params = Parameters::new();
+        let u_bytes = params.u().to_array();
+        for (i, expected) in POINTS.iter().enumerate() {
+            assert_eq!(
+                point(i as u64 + 1).to_array().as_slice(),
+                unhex(expected).as_slice()
+            );
+        }
+
+        // A valid capsule with `E = G`, `V = 2G`
+        let (point_e, point_v) = (point(1), point(2));
+        let h = ScalarDigest::new()
+            .chain_points(&[point_e, point_v])
+            .finalize();
+        let signature = &CurveScalar::from_u64(2) + &(&CurveScalar::from_u64(1) * &h);
+        let capsule = Capsule::new_verified(params, point_e, point_v, signature).unwrap();
+        let mut expected = u_bytes.to_vec();
+        expected.extend(unhex(POINTS[0]));
+        expected.extend(unhex(POINTS[1]));
+        expected.extend(signature.to_array());
+        check_vector(&capsule, &expected);
+
+        let (signature_for_proxy, signature_for_proxy_bytes) = small_signature(5, 6);
+        let (signature_for_bob, signature_for_bob_bytes) = small_signature(7, 8);
+        let kfrag = KeyFrag {
+            params,
+            id: CurveScalar::from_u64(1),
+            key: CurveScalar::from_u64(2),
+            precursor: point(3),
+            proof: KeyFragProof {
+                commitment: point(4),
+                signature_for_proxy,
+                signature_for_bob,
+                delegating_key_signed: true,
+                receiving_key_signed: false,
+            },
+        };
+        let mut expected = u_bytes.to_vec();
+        expected.extend(scalar_bytes(1));
+        expected.extend(scalar_bytes(2));
+        expected.extend(unhex(POINTS[2]));
+        expected.extend(unhex(POINTS[3]));
+        expected.extend(signature_for_proxy_bytes);
+        expected.extend(signature_for_bob_bytes);
+        expected.extend([1, 0].iter());
+        check_vector(&kfrag, &expected);
+
+        let (kfrag_signature, kfrag_signature_bytes) = small_signature(9, 10);
+        let cfrag = CapsuleFrag {
+            point_e1: point(1),
+            point_v1: point(2),
+            kfrag_id: CurveScalar::from_u64(3),
+            precursor: point(3),
+            proof: CapsuleFragProof {
+                point_e2: point(4),
+                point_v2: point(1),
+                kfrag_commitment: point(2),
+                kfrag_pok: point(3),
+                signature: CurveScalar::from_u64(4),
+                kfrag_signature,
+                metadata: CurveScalar::from_u64(5),
+            },
+        };
+        let mut expected = Vec::new();
+        for &i in [0, 1].iter() {
+            expected.extend(unhex(POINTS[i]));
+        }
+        expected.extend(scalar_bytes(3));
+        for &i in [2, 3, 0, 1, 2].iter() {
+            expected.extend(unhex(POINTS[i]));
+        }
+        expected.extend(scalar_bytes(4));
+        expected.extend(kfrag_signature_bytes);
+        expected.extend(scalar_bytes(5));
+        check_vector(&cfrag, &expected);
+    }
+}
@@ -0,0 +1,128 @@
+//! Checks that constructing and formatting every error type of the crate does not allocate,
+//! so that the errors are usable on targets without an allocator.
+//!
+//! The test installs a global allocator that counts the allocations made while it is armed.
+//! It runs without the default test harness (see `Cargo.toml`),
+//! so there are no other threads that could allocate in the meantime.
+
+use core::fmt::{self, Write};
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::alloc::{GlobalAlloc, Layout, System};
+
+use umbral_pre::codec::CodecError;
+use umbral_pre::convenience::StrDecryptionError;
+use umbral_pre::{
+    CapsuleFragVerificationError, CfragSetError, DecryptionError, DecryptorError,
+    DeserializationError, EncryptionError, OpenReencryptedError, PointError, ReencryptionError,
+    SignatureError,
+};
+
+struct CountingAllocator;
+
+static ARMED: AtomicBool = AtomicBool::new(false);
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ARMED.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if ARMED.load(Ordering::SeqCst) {
+            ALLOCATIONS.fetch_add(1, Ordering::SeqCst);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// A `fmt::Write` sink in a fixed stack buffer.
+struct StackWriter {
+    buf: [u8; 128],
+    len: usize,
+}
+
+impl Write for StackWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > self.buf.len() {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+/// Formats the error and returns the length of the message.
+fn format(err: &dyn fmt::Display) -> usize {
+    let mut writer = StackWriter {
+        buf: [0u8; 128],
+        len: 0,
+    };
+    write!(writer, "{}", err).unwrap();
+    writer.len
+}
+
+fn main() {
+    // Prepared in advance, since there is no way to construct a `Utf8Error` directly.
+    let mut invalid_utf8 = [0u8; 1];
+    invalid_utf8[0] = 0xff;
+    let utf8_error = core::str::from_utf8(&invalid_utf8).unwrap_err();
+
+    ARMED.store(true, Ordering::SeqCst);
+    let lengths = [
+        format(&EncryptionError::PlaintextTooLarge),
+        format(&EncryptionError::BufferTooSmall),
+        format(&EncryptionError::BackendFailure),
+        format(&ReencryptionError::ParameterMismatch),
+        format(&ReencryptionError::InvalidKeyFrag),
+        format(&DecryptionError::CiphertextTooShort),
+        format(&DecryptionError::DecryptionFailed),
+        format(&DecryptionError::CapsuleMismatch),
+        format(&DecryptionError::InvalidCapsuleFrag(2)),
+        format(&DecryptionError::TooFewFragments),
+        format(&DecryptionError::TooManyFragments),
+        format(&OpenReencryptedError::NotEnoughCfrags),
+        format(&OpenReencryptedError::MismatchedPrecursors),
+        format(&OpenReencryptedError::RepeatingKfragIds),
+        format(&CapsuleFragVerificationError::VerificationFailed),
+        format(&CfragSetError::MismatchedPrecursors),
+        format(&CfragSetError::RepeatingKfragIds),
+        format(&DecryptorError::InvalidCfrag),
+        format(&DecryptorError::DecryptionFailed),
+        format(&StrDecryptionError::DecryptionFailed),
+        format(&StrDecryptionError::InvalidUtf8(utf8_error)),
+        format(&PointError::MalformedEncoding),
+        format(&PointError::NotOnCurve),
+        format(&SignatureError::MalformedEncoding),
+        format(&SignatureError::HighS),
+        format(&DeserializationError::WrongLength),
+        format(&DeserializationError::InvalidPoint(PointError::NotOnCurve)),
+        format(&DeserializationError::InvalidScalar),
+        format(&DeserializationError::InvalidSignature(
+            SignatureError::HighS,
+        )),
+        format(&DeserializationError::InvalidBool),
+        format(&DeserializationError::InvalidCapsule),
+        format(&CodecError::BufferTooSmall),
+        format(&CodecError::UnknownVersion(2)),
+        format(&CodecError::UnexpectedTypeTag(4)),
+        format(&CodecError::InvalidLength),
+        format(&CodecError::InvalidObject),
+    ];
+    ARMED.store(false, Ordering::SeqCst);
+
+    assert_eq!(ALLOCATIONS.load(Ordering::SeqCst), 0);
+    assert!(lengths.iter().all(|len| *len > 0));
+    println!("test error_formatting_without_alloc ... ok");
+}